@@ -0,0 +1,149 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use internal_error::InternalError;
+use observability::axum::unknown_fallback_handler;
+use tokio::sync::Notify;
+
+use crate::config::{AdminServerConfig, HttpConfig, ListenerConfig};
+use crate::http_serve::{HttpServeOptions, HttpServer};
+use crate::listener::{ListenerAddr, ServerListener};
+use crate::maintenance::{MaintenanceMode, maintenance_router};
+use crate::readiness::{ReadinessAudience, readiness_handler};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Health, readiness, metrics, and build info routes. Access to metrics
+/// requires the bearer token when it is specified. The readiness report
+/// is served according to its audience.
+pub fn system_router(
+    metrics_token: Option<&str>,
+    readiness_audience: ReadinessAudience,
+) -> axum::Router {
+    let mut metrics_router = axum::Router::new().route(
+        "/system/metrics",
+        axum::routing::get(observability::metrics::metrics_handler),
+    );
+
+    if let Some(metrics_token) = metrics_token {
+        metrics_router = metrics_router.route_layer(axum::middleware::from_fn_with_state(
+            Arc::new(metrics_token.to_string()),
+            bearer_token_middleware,
+        ));
+    }
+
+    axum::Router::new()
+        .route(
+            "/system/health",
+            axum::routing::get(observability::health::health_handler),
+        )
+        .route(
+            "/system/info",
+            axum::routing::get(observability::build_info::build_info_handler),
+        )
+        .route(
+            "/system/ready",
+            axum::routing::get(readiness_handler).with_state(readiness_audience),
+        )
+        .merge(metrics_router)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Builds the server of the dedicated admin listener that serves the system
+/// routes, the maintenance mode toggle, and, in E2E mode, the E2E routes
+/// instead of the main HTTP listener
+pub async fn build_admin_server(
+    config: &AdminServerConfig,
+    listener_config: Option<&ListenerConfig>,
+    catalog: dill::Catalog,
+    e2e_enabled: bool,
+    http_config: &HttpConfig,
+) -> Result<(HttpServer, ListenerAddr, Option<Arc<Notify>>), InternalError> {
+    let addr = SocketAddr::from((config.address, config.port));
+    let listener = ServerListener::bind_configured(addr, listener_config, None).await?;
+    let local_addr = listener.local_addr().clone();
+
+    let mut router = system_router(config.metrics_token.as_deref(), ReadinessAudience::Internal);
+
+    // Toggling maintenance mode is served by the HTTP API only when there is no
+    // admin listener
+    if let Ok(maintenance) = catalog.get_one::<MaintenanceMode>() {
+        router = router.merge(maintenance_router(maintenance));
+    }
+
+    let mut router = router
+        .fallback(unknown_fallback_handler)
+        .layer(axum::extract::Extension(catalog));
+
+    let maybe_shutdown_notify = if e2e_enabled {
+        let shutdown_notify = Arc::new(Notify::new());
+
+        router = router.nest(
+            "/e2e",
+            kamu_adapter_http::e2e::e2e_router(shutdown_notify.clone()),
+        );
+
+        Some(shutdown_notify)
+    } else {
+        None
+    };
+
+    let server = HttpServer::new(
+        listener,
+        router,
+        HttpServeOptions {
+            header_read_timeout: http_config.limits.header_read_timeout.into(),
+            idle_timeout: http_config.limits.idle_timeout.map(Into::into),
+        },
+    );
+    Ok((server, local_addr, maybe_shutdown_notify))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) async fn bearer_token_middleware(
+    State(expected_token): State<Arc<String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let token = request
+        .headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    if !token.is_some_and(|token| secrets_eq(token.as_bytes(), expected_token.as_bytes())) {
+        return (
+            http::StatusCode::UNAUTHORIZED,
+            [(http::header::WWW_AUTHENTICATE, "Bearer")],
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Compares secrets via their fixed-length hashes without leaking either the
+/// position of the first mismatch or the length of the expected secret via
+/// timing
+fn secrets_eq(a: &[u8], b: &[u8]) -> bool {
+    use sha2::Digest as _;
+
+    let (a, b) = (sha2::Sha256::digest(a), sha2::Sha256::digest(b));
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
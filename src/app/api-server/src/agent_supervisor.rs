@@ -0,0 +1,592 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_utils::BackgroundAgent;
+use graceful_shutdown::CancellationToken;
+use internal_error::*;
+use tokio_util::task::AbortOnDropHandle;
+
+use crate::config::{
+    BackgroundAgentsConfig,
+    LeaderElectionConfig,
+    MaintenanceConfig,
+    ShutdownConfig,
+};
+use crate::leader_election::LeaderElection;
+use crate::maintenance::MaintenanceMode;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Background agent that finishes its current iteration and returns once the
+/// `stop` token is cancelled, instead of being interrupted
+#[async_trait::async_trait]
+pub trait GracefulBackgroundAgent: Send + Sync {
+    fn agent_name(&self) -> &'static str;
+
+    async fn run(&self, stop: CancellationToken) -> Result<(), InternalError>;
+}
+
+/// Names of all [`BackgroundAgent`]s and [`GracefulBackgroundAgent`]s
+/// registered in the catalog
+pub fn background_agent_names(catalog: &dill::Catalog) -> Result<Vec<&'static str>, InternalError> {
+    let agents = catalog
+        .get::<dill::AllOf<dyn BackgroundAgent>>()
+        .int_err()?;
+    let graceful_agents = catalog
+        .get::<dill::AllOf<dyn GracefulBackgroundAgent>>()
+        .int_err()?;
+
+    Ok(agents
+        .iter()
+        .map(|agent| agent.agent_name())
+        .chain(graceful_agents.iter().map(|agent| agent.agent_name()))
+        .collect())
+}
+
+enum SupervisedAgent {
+    Interruptible(Arc<dyn BackgroundAgent>),
+    Graceful(Arc<dyn GracefulBackgroundAgent>),
+}
+
+impl SupervisedAgent {
+    /// Instantiates the agent with the given name anew
+    fn resolve(catalog: &dill::Catalog, agent_name: &str) -> Result<Self, InternalError> {
+        if let Some(agent) = catalog
+            .get::<dill::AllOf<dyn GracefulBackgroundAgent>>()
+            .int_err()?
+            .into_iter()
+            .find(|agent| agent.agent_name() == agent_name)
+        {
+            return Ok(Self::Graceful(agent));
+        }
+
+        catalog
+            .get::<dill::AllOf<dyn BackgroundAgent>>()
+            .int_err()?
+            .into_iter()
+            .find(|agent| agent.agent_name() == agent_name)
+            .map(Self::Interruptible)
+            .ok_or_else(|| InternalError::new(format!("Background agent {agent_name} not found")))
+    }
+
+    async fn run(&self, stop: CancellationToken) -> Result<(), InternalError> {
+        match self {
+            Self::Interruptible(agent) => agent.run().await,
+            Self::Graceful(agent) => agent.run(stop).await,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentState {
+    Running,
+    /// Singleton agent waiting for this replica to become the leader
+    Standby,
+    /// Agent is stopped while maintenance mode is enabled
+    Paused,
+    /// Agent has failed and is waiting for a restart
+    Restarting {
+        restarts: u32,
+        last_error: String,
+    },
+    /// Agent has exceeded its failure budget
+    Failed {
+        last_error: String,
+    },
+}
+
+/// Current state of every supervised agent
+#[derive(Debug, Default)]
+pub struct BackgroundAgentsStatus {
+    states: Mutex<BTreeMap<String, AgentState>>,
+}
+
+#[dill::component(pub)]
+#[dill::scope(dill::Singleton)]
+impl BackgroundAgentsStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, agent_name: &str, state: AgentState) {
+        self.states
+            .lock()
+            .unwrap()
+            .insert(agent_name.to_string(), state);
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, AgentState> {
+        self.states.lock().unwrap().clone()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct BackgroundAgentMetrics {
+    pub restarts_num: prometheus::IntCounterVec,
+}
+
+impl BackgroundAgentMetrics {
+    pub fn new() -> Self {
+        use prometheus::*;
+
+        Self {
+            restarts_num: IntCounterVec::new(
+                Opts::new(
+                    "background_agent_restarts_total",
+                    "Restarts of background agents after a failure",
+                ),
+                &["agent"],
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl Default for BackgroundAgentMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl observability::metrics::MetricsProvider for BackgroundAgentMetrics {
+    fn register(&self, reg: &prometheus::Registry) -> prometheus::Result<()> {
+        reg.register(Box::new(self.restarts_num.clone()))?;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Tracks failures of an agent within a sliding window and computes the
+/// exponential restart delay
+#[derive(Debug)]
+pub struct RestartPolicy {
+    min_delay: Duration,
+    max_delay: Duration,
+    budget: usize,
+    window: Duration,
+    failures: VecDeque<Instant>,
+    delay: Duration,
+}
+
+impl RestartPolicy {
+    pub fn new(config: &BackgroundAgentsConfig) -> Self {
+        let min_delay: Duration = config.restart_min_delay.into();
+        Self {
+            min_delay,
+            max_delay: config.restart_max_delay.into(),
+            budget: config.failure_budget as usize,
+            window: config.failure_budget_window.into(),
+            failures: VecDeque::new(),
+            delay: min_delay,
+        }
+    }
+
+    /// Registers a failure of an agent that was started at `started_at` and
+    /// returns the delay before the restart or `None` if the budget is
+    /// exhausted
+    pub fn on_failure(&mut self, started_at: Instant, now: Instant) -> Option<Duration> {
+        // Agent that ran long enough is considered recovered
+        if now.duration_since(started_at) >= self.max_delay {
+            self.delay = self.min_delay;
+        }
+
+        self.failures.push_back(now);
+        while let Some(first) = self.failures.front()
+            && now.duration_since(*first) > self.window
+        {
+            self.failures.pop_front();
+        }
+
+        if self.failures.len() > self.budget {
+            return None;
+        }
+
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(self.max_delay);
+        Some(delay)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Runs background agents, restarting the failed ones with exponential
+/// backoff. An agent completing without an error is treated as a failure too,
+/// as agents are expected to run forever. Every restart resolves a new
+/// instance of the agent from the catalog, so the state of the failed one is
+/// not carried over (unless the agent is registered as a singleton).
+///
+/// Singleton agents run only while this replica is the elected leader, and
+/// are stopped when the leadership is lost.
+///
+/// On shutdown [`GracefulBackgroundAgent`]s are signalled to stop and are
+/// awaited for up to the `shutdown.agentsTimeout`, after which they are
+/// aborted. Plain [`BackgroundAgent`]s don't support cooperative
+/// cancellation, so they are interrupted at their next suspension point and
+/// are expected to recover the work that was in progress upon the next start.
+///
+/// While maintenance mode is enabled [`GracefulBackgroundAgent`]s are stopped
+/// the same way and are started again once it's disabled. Plain agents keep
+/// running, so that they are not interrupted in the middle of a task, unless
+/// `maintenance.interruptAgents` is set.
+pub struct AgentSupervisor {
+    config: Arc<BackgroundAgentsConfig>,
+    shutdown_config: Arc<ShutdownConfig>,
+    leader_election_config: Arc<LeaderElectionConfig>,
+    leader_election: Arc<dyn LeaderElection>,
+    maintenance_config: Arc<MaintenanceConfig>,
+    maintenance: Arc<MaintenanceMode>,
+    metrics: Arc<BackgroundAgentMetrics>,
+    status: Arc<BackgroundAgentsStatus>,
+}
+
+#[dill::component(pub)]
+impl AgentSupervisor {
+    pub fn new(
+        config: Arc<BackgroundAgentsConfig>,
+        shutdown_config: Arc<ShutdownConfig>,
+        leader_election_config: Arc<LeaderElectionConfig>,
+        leader_election: Arc<dyn LeaderElection>,
+        maintenance_config: Arc<MaintenanceConfig>,
+        maintenance: Arc<MaintenanceMode>,
+        metrics: Arc<BackgroundAgentMetrics>,
+        status: Arc<BackgroundAgentsStatus>,
+    ) -> Self {
+        Self {
+            config,
+            shutdown_config,
+            leader_election_config,
+            leader_election,
+            maintenance_config,
+            maintenance,
+            metrics,
+            status,
+        }
+    }
+
+    /// Runs the agents with the given names that are resolved from the
+    /// `catalog` until one of them exhausts its failure budget or the
+    /// `shutdown` token is cancelled
+    pub async fn run(
+        self: Arc<Self>,
+        catalog: dill::Catalog,
+        agents: Vec<&'static str>,
+        shutdown: CancellationToken,
+    ) -> Result<(), InternalError> {
+        let (singleton_agents, agents): (Vec<_>, Vec<_>) = agents
+            .into_iter()
+            .partition(|agent_name| self.is_singleton(agent_name));
+
+        // Stops the rest of the agents when one of them exhausts its budget
+        let stop = shutdown.child_token();
+
+        let mut tasks = Vec::new();
+        if !agents.is_empty() {
+            let run = self
+                .clone()
+                .run_group(catalog.clone(), agents, stop.clone());
+            tasks.push(AbortOnDropHandle::new(tokio::spawn(run)));
+        }
+        if !singleton_agents.is_empty() {
+            let run_on_leader = self
+                .clone()
+                .run_on_leader(catalog, singleton_agents, stop.clone());
+            tasks.push(AbortOnDropHandle::new(tokio::spawn(run_on_leader)));
+        }
+
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        let (result, _index, remaining) = futures::future::select_all(tasks).await;
+        stop.cancel();
+        futures::future::join_all(remaining).await;
+
+        flatten_join_result(result)
+    }
+
+    fn is_singleton(&self, agent_name: &str) -> bool {
+        match &self.leader_election_config.singleton_agents {
+            Some(singleton_agents) => singleton_agents.iter().any(|name| name == agent_name),
+            None => true,
+        }
+    }
+
+    /// Whether the agent is stopped while maintenance mode is enabled
+    fn is_pausable(&self, catalog: &dill::Catalog, agent_name: &str) -> bool {
+        self.maintenance_config.interrupt_agents
+            || catalog
+                .get::<dill::AllOf<dyn GracefulBackgroundAgent>>()
+                .is_ok_and(|agents| agents.iter().any(|agent| agent.agent_name() == agent_name))
+    }
+
+    /// Runs the agents until `stop` is cancelled or one of them exhausts its
+    /// failure budget, pausing the ones that support it while maintenance mode
+    /// is enabled
+    async fn run_group(
+        self: Arc<Self>,
+        catalog: dill::Catalog,
+        agents: Vec<&'static str>,
+        stop: CancellationToken,
+    ) -> Result<(), InternalError> {
+        let (pausable_agents, agents): (Vec<_>, Vec<_>) = agents
+            .into_iter()
+            .partition(|agent_name| self.is_pausable(&catalog, agent_name));
+
+        let group_stop = stop.child_token();
+
+        let mut tasks = self.spawn_supervised(&catalog, agents, &group_stop);
+        if !pausable_agents.is_empty() {
+            let run_pausable =
+                self.clone()
+                    .run_pausable(catalog, pausable_agents, group_stop.clone());
+            tasks.push(AbortOnDropHandle::new(tokio::spawn(run_pausable)));
+        }
+
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        let (result, _index, remaining) = futures::future::select_all(tasks).await;
+        group_stop.cancel();
+        futures::future::join_all(remaining).await;
+
+        flatten_join_result(result)
+    }
+
+    async fn run_pausable(
+        self: Arc<Self>,
+        catalog: dill::Catalog,
+        agents: Vec<&'static str>,
+        stop: CancellationToken,
+    ) -> Result<(), InternalError> {
+        loop {
+            if self.maintenance.is_enabled() {
+                for agent_name in &agents {
+                    self.status.set(agent_name, AgentState::Paused);
+                }
+
+                tokio::select! {
+                    () = self.maintenance.wait_until(false) => {}
+                    () = stop.cancelled() => return Ok(()),
+                }
+                tracing::info!("Maintenance mode disabled, resuming background agents");
+            }
+
+            let pause = stop.child_token();
+            let mut tasks = futures::future::select_all(self.spawn_supervised(
+                &catalog,
+                agents.clone(),
+                &pause,
+            ));
+
+            tokio::select! {
+                (result, _index, remaining) = &mut tasks => {
+                    pause.cancel();
+                    futures::future::join_all(remaining).await;
+                    return flatten_join_result(result);
+                }
+                () = self.maintenance.wait_until(true) => {
+                    tracing::info!("Maintenance mode enabled, pausing background agents");
+                    pause.cancel();
+                    futures::future::join_all(tasks.into_inner()).await;
+                }
+            }
+        }
+    }
+
+    fn spawn_supervised(
+        &self,
+        catalog: &dill::Catalog,
+        agents: Vec<&'static str>,
+        shutdown: &CancellationToken,
+    ) -> Vec<AbortOnDropHandle<Result<(), InternalError>>> {
+        agents
+            .into_iter()
+            .map(|agent_name| {
+                let policy = RestartPolicy::new(&self.config);
+                let stop_timeout = self.shutdown_config.agents_timeout.into();
+                let metrics = self.metrics.clone();
+                let status = self.status.clone();
+                AbortOnDropHandle::new(tokio::spawn(supervise(
+                    catalog.clone(),
+                    agent_name,
+                    policy,
+                    stop_timeout,
+                    metrics,
+                    status,
+                    shutdown.clone(),
+                )))
+            })
+            .collect()
+    }
+
+    /// Runs the agents while this replica holds the leadership and waits to be
+    /// elected again after it was lost, aborting the agents as soon as the
+    /// leadership is lost. The leadership is kept while the agents
+    /// are paused in maintenance mode, so that the replicas where it was not
+    /// enabled don't take over.
+    async fn run_on_leader(
+        self: Arc<Self>,
+        catalog: dill::Catalog,
+        agents: Vec<&'static str>,
+        shutdown: CancellationToken,
+    ) -> Result<(), InternalError> {
+        loop {
+            for agent_name in &agents {
+                self.status.set(agent_name, AgentState::Standby);
+            }
+
+            let lease = tokio::select! {
+                res = self.leader_election.acquire_leadership() => res?,
+                () = shutdown.cancelled() => return Ok(()),
+            };
+
+            let run =
+                self.clone()
+                    .run_group(catalog.clone(), agents.clone(), shutdown.child_token());
+
+            tokio::select! {
+                res = run => return res,
+                () = lease.lost() => {
+                    // Another replica may already be the leader, so the agents are aborted
+                    // right away instead of being let to complete their iterations
+                    tracing::warn!("Leadership lost, aborting singleton background agents");
+                }
+            }
+        }
+    }
+}
+
+fn flatten_join_result(
+    result: Result<Result<(), InternalError>, tokio::task::JoinError>,
+) -> Result<(), InternalError> {
+    match result {
+        Ok(res) => res,
+        Err(join_error) => Err(InternalError::new(format!(
+            "Background agent supervisor panicked: {join_error}"
+        ))),
+    }
+}
+
+async fn supervise(
+    catalog: dill::Catalog,
+    agent_name: &'static str,
+    mut policy: RestartPolicy,
+    stop_timeout: Duration,
+    metrics: Arc<BackgroundAgentMetrics>,
+    status: Arc<BackgroundAgentsStatus>,
+    shutdown: CancellationToken,
+) -> Result<(), InternalError> {
+    let mut restarts = 0;
+
+    loop {
+        tracing::info!("Starting background agent: {}", agent_name);
+        status.set(agent_name, AgentState::Running);
+        let started_at = Instant::now();
+
+        let agent = SupervisedAgent::resolve(&catalog, agent_name);
+        let is_graceful = matches!(agent, Ok(SupervisedAgent::Graceful(_)));
+
+        // Running in a separate task to survive panics
+        let mut run = AbortOnDropHandle::new(tokio::spawn({
+            let stop = shutdown.clone();
+            async move { agent?.run(stop).await }
+        }));
+
+        let result = tokio::select! {
+            res = &mut run => res,
+            () = shutdown.cancelled() => {
+                let stopped =
+                    is_graceful && tokio::time::timeout(stop_timeout, &mut run).await.is_ok();
+                if !stopped {
+                    if is_graceful {
+                        tracing::error!(
+                            "Background agent {} did not stop within {}s, aborting",
+                            agent_name,
+                            stop_timeout.as_secs_f64(),
+                        );
+                        status.set(
+                            agent_name,
+                            AgentState::Failed {
+                                last_error: "Aborted as it did not stop in time".to_string(),
+                            },
+                        );
+                    }
+                    run.abort();
+                    let _ = run.await;
+                }
+                tracing::info!("Background agent {} stopped", agent_name);
+                return Ok(());
+            }
+        };
+
+        // Graceful agent may complete on its own right after being signalled
+        if shutdown.is_cancelled() {
+            tracing::info!("Background agent {} stopped", agent_name);
+            return Ok(());
+        }
+
+        let error = match result {
+            Ok(Err(error)) => {
+                tracing::error!(
+                    error = ?error,
+                    error_msg = %error,
+                    "Background agent {} failed",
+                    agent_name,
+                );
+                error.to_string()
+            }
+            Ok(Ok(())) => {
+                tracing::warn!("Background agent {} completed unexpectedly", agent_name);
+                "Completed unexpectedly".to_string()
+            }
+            Err(join_error) => {
+                tracing::error!("Background agent {} panicked: {}", agent_name, join_error);
+                format!("Panicked: {join_error}")
+            }
+        };
+
+        let Some(delay) = policy.on_failure(started_at, Instant::now()) else {
+            status.set(agent_name, AgentState::Failed { last_error: error });
+            return Err(InternalError::new(format!(
+                "Background agent {agent_name} exceeded its failure budget"
+            )));
+        };
+
+        restarts += 1;
+        metrics.restarts_num.with_label_values(&[agent_name]).inc();
+        status.set(
+            agent_name,
+            AgentState::Restarting {
+                restarts,
+                last_error: error,
+            },
+        );
+
+        tracing::warn!(
+            "Restarting background agent {} in {}s",
+            agent_name,
+            delay.as_secs_f64()
+        );
+        tokio::select! {
+            () = tokio::time::sleep(delay) => {}
+            () = shutdown.cancelled() => return Ok(()),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
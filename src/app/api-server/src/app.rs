@@ -23,6 +23,8 @@ use tracing::{error, info, warn};
 use url::Url;
 
 use crate::commands::{Command, CommandDesc};
+use crate::flow_request_id::RequestIdFlowRunService;
+use crate::maintenance::MaintenanceStatus;
 use crate::ui_configuration::{UIConfiguration, UIFeatureFlags};
 use crate::{
     AccessTokenLifecycleNotifier,
@@ -86,6 +88,7 @@ pub async fn run(args: cli::Cli, config: config::ApiServerConfig) -> Result<(),
     );
 
     let db_config = config.database.clone();
+    let config_roles = config.roles.clone();
 
     let e2e_http_port = args
         .e2e_output_data_path
@@ -112,6 +115,8 @@ pub async fn run(args: cli::Cli, config: config::ApiServerConfig) -> Result<(),
         // Connect database and obtain a connection pool
         let catalog_with_pool = connect_database_initially(&catalog).await?;
 
+        crate::flow_request_id::init_flow_request_ids_table(&catalog_with_pool).await?;
+
         // Periodically refresh password in the connection pool, if configured
         spawn_password_refreshing_job(&db_config, &catalog_with_pool).await;
 
@@ -137,18 +142,38 @@ pub async fn run(args: cli::Cli, config: config::ApiServerConfig) -> Result<(),
         cli::Command::Metrics(_) => {
             Box::new(commands::ListMetricsCommand::builder(metrics_registry).cast())
         }
-        cli::Command::Run(c) => Box::new(
-            commands::RunCommand::builder(
-                server_account_subject.clone(),
-                c.address,
-                c.http_port,
-                c.flightsql_port,
-                args.e2e_output_data_path,
-                e2e_http_port,
-                c.read_only,
+        cli::Command::Run(c) => {
+            let roles = if c.roles.is_empty() {
+                commands::RunRoles::new(&config_roles, c.read_only)?
+            } else {
+                let roles: Vec<_> = c.roles.into_iter().map(Into::into).collect();
+                commands::RunRoles::new(&roles, c.read_only)?
+            };
+
+            Box::new(
+                commands::RunCommand::builder(
+                    server_account_subject.clone(),
+                    c.address,
+                    c.http_port,
+                    c.flightsql_port,
+                    args.e2e_output_data_path,
+                    e2e_http_port,
+                    c.read_only,
+                    roles,
+                )
+                .cast(),
             )
-            .cast(),
-        ),
+        }
+        cli::Command::Maintenance(c) => {
+            let action = match c.subcommand {
+                cli::Maintenance::Enable(sc) => commands::MaintenanceAction::Enable {
+                    message: sc.message,
+                },
+                cli::Maintenance::Disable(_) => commands::MaintenanceAction::Disable,
+                cli::Maintenance::Status(_) => commands::MaintenanceAction::Status,
+            };
+            Box::new(commands::MaintenanceCommand::builder(c.admin_url, action).cast())
+        }
         cli::Command::Debug(c) => match c.subcommand {
             cli::Debug::Depgraph(_) => Box::new(commands::DebugDepgraphCommand::builder().cast()),
             cli::Debug::SearchReindex(_) => {
@@ -246,6 +271,11 @@ pub fn load_config(path: Option<&PathBuf>) -> Result<config::ApiServerConfig, In
     // TODO: Think how to make this a `setty` feature
     cfg.engine.datafusion_embedded.merge_with_defaults();
 
+    if let Some(oracle) = &cfg.oracle {
+        kamu_oracle_provider::publication::Publication::from_configs(&oracle.publications)
+            .int_err()?;
+    }
+
     Ok(cfg)
 }
 
@@ -354,6 +384,37 @@ pub async fn init_dependencies(
     b.add::<kamu_adapter_flight_sql::KamuFlightSqlService>();
     //
 
+    // HTTP
+    if let Some(rate_limit) = &config.http.rate_limit {
+        crate::rate_limit::validate_config(rate_limit)?;
+        b.add_value(rate_limit.clone());
+        b.add::<crate::rate_limit::RateLimiter>();
+    }
+    b.add_value(crate::rate_limit::RateLimitMetrics::new());
+    b.bind::<dyn MetricsProvider, crate::rate_limit::RateLimitMetrics>();
+    b.add_value(config.http);
+    b.add_value(config.listen);
+    if let Some(admin_config) = config.admin {
+        b.add_value(admin_config);
+    }
+    if let Some(web_ui_config) = config.ui {
+        b.add_value(web_ui_config);
+    }
+    //
+
+    // TLS
+    if let Some(tls_config) = config.tls {
+        if let Some(client_auth) = &tls_config.client_auth {
+            b.add_value(
+                crate::client_cert_auth::ClientCertAccountMapping::from_config(
+                    client_auth.accounts.clone(),
+                )?,
+            );
+        }
+        b.add_value(tls_config);
+    }
+    //
+
     b.add::<kamu::FetchService>();
     b.add_value(config.source.to_infra_cfg());
     b.add_value(config.source.mqtt.to_infra_cfg());
@@ -485,6 +546,18 @@ pub async fn init_dependencies(
 
     b.add_value(config.outbox.into_system());
 
+    b.add_value(config.background_agents);
+    b.add_value(config.leader_election);
+    b.add_value(crate::leader_election::LeaderElectionMetrics::new());
+    b.bind::<dyn MetricsProvider, crate::leader_election::LeaderElectionMetrics>();
+    b.add_value(config.shutdown);
+    b.add_value(config.maintenance);
+    b.add::<crate::maintenance::MaintenanceMode>();
+    b.add::<crate::agent_supervisor::BackgroundAgentsStatus>();
+    b.add::<crate::agent_supervisor::AgentSupervisor>();
+    b.add_value(crate::agent_supervisor::BackgroundAgentMetrics::new());
+    b.bind::<dyn MetricsProvider, crate::agent_supervisor::BackgroundAgentMetrics>();
+
     // Webhooks configuration
     let webhooks_config = config.webhooks;
     {
@@ -583,6 +656,9 @@ pub async fn init_dependencies(
     });
 
     kamu_flow_system_services::register_dependencies(&mut b);
+    // Keeps the request IDs of the manually triggered flows for notifications
+    b.add::<RequestIdFlowRunService>();
+    b.bind::<dyn kamu_flow_system::FlowRunService, RequestIdFlowRunService>();
 
     kamu_adapter_auth_oso_rebac::register_dependencies(&mut b);
     kamu_datasets_services::register_dependencies(
@@ -601,6 +677,12 @@ pub async fn init_dependencies(
 
     configure_repository(&mut b, repo_url, &config.repo, &s3_metrics).await;
 
+    b.add::<crate::readiness::StartupJobsStatus>();
+    b.add::<crate::readiness::ReadinessState>();
+    b.add_builder(crate::readiness::ReadinessChecker::builder(
+        repo_url.clone(),
+    ));
+
     kamu_accounts_services::register_dependencies(
         &mut b,
         kamu_accounts_services::AccountDomainDependenciesOptions {
@@ -665,6 +747,30 @@ pub async fn init_dependencies(
             protocols.base_url_rest = base_url_rest;
         }
 
+        // Oracle
+        if let Some(oracle_config) = config.oracle {
+            let oracle_config = oracle_config.into_provider_config(protocols.base_url_rest.clone());
+            b.add_value(kamu_oracle_provider::OdfOracleProviderMetrics::new(
+                oracle_config.chain_id,
+                oracle_config.api_url.host_str().unwrap_or_default(),
+            ));
+            b.bind::<dyn MetricsProvider, kamu_oracle_provider::OdfOracleProviderMetrics>();
+            b.add_value(oracle_config);
+            b.add::<crate::OracleProviderAgent>();
+        }
+
+        if !config.tenant_domains.is_empty() {
+            if matches!(tenancy_config, TenancyConfig::SingleTenant) {
+                return InternalError::bail(
+                    "Tenant domains require the multi-tenant mode".to_string(),
+                );
+            }
+            b.add_value(crate::tenant_domains::TenantDomains::from_config(
+                config.tenant_domains,
+                &protocols,
+            )?);
+        }
+
         b.add_value(kamu::domain::ServerUrlConfig::new(protocols));
     }
 
@@ -809,6 +915,9 @@ pub async fn init_dependencies(
                 enable_compression: cfg.enable_compression,
                 ca_cert_pem_path: cfg.ca_cert_pem_path,
             });
+            b.add_value(crate::readiness::ElasticsearchReadinessConfig {
+                username: cfg.username,
+            });
             b.add_value(kamu_search_elasticsearch::ElasticsearchRepositoryConfig {
                 index_prefix: cfg.index_prefix,
                 embedding_dimensions: cfg.embedding_dimensions,
@@ -828,6 +937,10 @@ pub async fn init_dependencies(
             allow_anonymous: config.auth.allow_anonymous,
             ..UIFeatureFlags::default()
         },
+        // Depends on the command and is set by the `run` command
+        read_only: false,
+        // Filled in upon each request
+        maintenance: MaintenanceStatus::default(),
     });
     //
 
@@ -46,6 +46,7 @@ pub enum Command {
     Run(Run),
     Gql(GqlGroup),
     Metrics(Metrics),
+    Maintenance(MaintenanceGroup),
     Debug(DebugGroup),
 }
 
@@ -66,9 +67,36 @@ pub struct Run {
     #[arg(long)]
     pub flightsql_port: Option<u16>,
 
-    /// Run server in read-only mode where it will not write to a database
+    /// Run server in read-only mode where it rejects all modifications and will
+    /// not write to a database
     #[arg(long)]
     pub read_only: bool,
+
+    /// Parts of the server to run, overriding the `roles` config
+    #[arg(long = "role", value_name = "ROLE", value_delimiter = ',')]
+    pub roles: Vec<RunRole>,
+}
+
+/// Spelled the same way as [`crate::config::ServerRole`]
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+#[value(rename_all = "PascalCase")]
+pub enum RunRole {
+    /// HTTP and Flight SQL APIs
+    Api,
+    /// Flight SQL API only
+    FlightSql,
+    /// Background agents with the health endpoint
+    Worker,
+}
+
+impl From<RunRole> for crate::config::ServerRole {
+    fn from(value: RunRole) -> Self {
+        match value {
+            RunRole::Api => Self::Api,
+            RunRole::FlightSql => Self::FlightSql,
+            RunRole::Worker => Self::Worker,
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -120,6 +148,57 @@ pub struct Metrics {}
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Maintenance mode of a running node
+#[derive(Debug, clap::Args)]
+#[command(after_help = r#"
+Requests are sent to the admin listener of the node specified in the `admin`
+config or via `--admin-url`. Nodes without the admin listener serve the toggle
+on the HTTP API when `maintenance.token` is configured. The token from the
+config is sent with the requests.
+
+Example:
+    kamu-api-server maintenance enable --message 'Upgrading storage'
+"#)]
+pub struct MaintenanceGroup {
+    /// URL of the admin listener or the HTTP API of the node
+    #[arg(long, global = true)]
+    pub admin_url: Option<url::Url>,
+
+    #[command(subcommand)]
+    pub subcommand: Maintenance,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Maintenance {
+    Enable(MaintenanceEnable),
+    Disable(MaintenanceDisable),
+    Status(MaintenanceStatus),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Rejects writes and pauses background agents
+#[derive(Debug, clap::Args)]
+pub struct MaintenanceEnable {
+    /// Message for the clients, falls back to the one from the config
+    #[arg(long)]
+    pub message: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Resumes writes and background agents
+#[derive(Debug, clap::Args)]
+pub struct MaintenanceDisable {}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Prints out whether maintenance mode is enabled
+#[derive(Debug, clap::Args)]
+pub struct MaintenanceStatus {}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
 /// GraphQL related command group
 #[derive(Debug, clap::Args)]
 pub struct DebugGroup {
@@ -0,0 +1,197 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::pin::Pin;
+use std::str::FromStr as _;
+use std::task::{Context, Poll};
+
+use database_common::DatabaseTransactionRunner;
+use futures::Future;
+use internal_error::*;
+use kamu_accounts::{AccountService, CurrentAccountSubject};
+use tower::{Layer, Service};
+
+use crate::config::ClientCertAccountConfig;
+use crate::listener::ServerConnectInfo;
+use crate::tls::ClientCertIdentity;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Maps identities of verified client certificates to accounts
+#[derive(Debug, Clone, Default)]
+pub struct ClientCertAccountMapping {
+    entries: Vec<(String, odf::AccountName)>,
+}
+
+impl ClientCertAccountMapping {
+    pub fn from_config(accounts: Vec<ClientCertAccountConfig>) -> Result<Self, InternalError> {
+        let entries = accounts
+            .into_iter()
+            .map(|entry| {
+                let account_name =
+                    odf::AccountName::from_str(&entry.account_name).map_err(|err| {
+                        InternalError::new(format!(
+                            "Invalid account name '{}' for client certificate '{}': {err}",
+                            entry.account_name, entry.identity
+                        ))
+                    })?;
+                Ok((entry.identity, account_name))
+            })
+            .collect::<Result<_, InternalError>>()?;
+
+        Ok(Self { entries })
+    }
+
+    /// Returns the account of the first entry matching the common name or any
+    /// of the alternative names of the certificate
+    pub fn resolve(&self, identity: &ClientCertIdentity) -> Option<&odf::AccountName> {
+        self.entries
+            .iter()
+            .find(|(name, _)| identity.names().any(|n| n == name))
+            .map(|(_, account_name)| account_name)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Authenticates requests arriving over connections with a verified client
+/// certificate as the mapped account.
+///
+/// Must be placed after the regular `AuthenticationLayer`, which puts the
+/// request catalog into the extensions. Bearer tokens take precedence, so the
+/// subject is replaced only for requests that are anonymous otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct ClientCertAuthenticationLayer {}
+
+impl ClientCertAuthenticationLayer {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<Svc> Layer<Svc> for ClientCertAuthenticationLayer {
+    type Service = ClientCertAuthenticationMiddleware<Svc>;
+
+    fn layer(&self, inner: Svc) -> Self::Service {
+        ClientCertAuthenticationMiddleware { inner }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct ClientCertAuthenticationMiddleware<Svc> {
+    inner: Svc,
+}
+
+impl<Svc, B> Service<http::Request<B>> for ClientCertAuthenticationMiddleware<Svc>
+where
+    Svc: Service<http::Request<B>> + Send + Clone + 'static,
+    Svc::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = Svc::Response;
+    type Error = Svc::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // Inspired by https://github.com/tower-rs/tower/issues/547#issuecomment-767629149
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let client_cert = ServerConnectInfo::from_extensions(request.extensions())
+                .and_then(|info| info.client_cert.clone());
+
+            if let Some(client_cert) = client_cert
+                && let Some(catalog) = request.extensions().get::<dill::Catalog>().cloned()
+            {
+                match authenticate(&catalog, &client_cert).await {
+                    Ok(Some(catalog)) => {
+                        request.extensions_mut().insert(catalog);
+                    }
+                    Ok(None) => {}
+                    // Request proceeds as anonymous
+                    Err(err) => tracing::error!(
+                        error = ?err,
+                        error_msg = %err,
+                        "Failed to authenticate client certificate",
+                    ),
+                }
+            }
+
+            inner.call(request).await
+        })
+    }
+}
+
+/// Returns the request catalog with the account subject of the certificate or
+/// `None` if the request should proceed unchanged
+async fn authenticate(
+    catalog: &dill::Catalog,
+    client_cert: &ClientCertIdentity,
+) -> Result<Option<dill::Catalog>, InternalError> {
+    let Ok(mapping) = catalog.get_one::<ClientCertAccountMapping>() else {
+        return Ok(None);
+    };
+
+    let subject = catalog.get_one::<CurrentAccountSubject>().int_err()?;
+    if !matches!(subject.as_ref(), CurrentAccountSubject::Anonymous(_)) {
+        return Ok(None);
+    }
+
+    let Some(account_name) = mapping.resolve(client_cert).cloned() else {
+        tracing::debug!(
+            ?client_cert,
+            "Client certificate is not mapped to an account"
+        );
+        return Ok(None);
+    };
+
+    let account = DatabaseTransactionRunner::new(catalog.clone())
+        .transactional(|transaction_catalog| async move {
+            let account_service = transaction_catalog
+                .get_one::<dyn AccountService>()
+                .int_err()?;
+            account_service
+                .account_by_name(&account_name)
+                .await
+                .int_err()
+        })
+        .await?;
+
+    let Some(account) = account else {
+        tracing::warn!(
+            ?client_cert,
+            "Account mapped to the client certificate does not exist",
+        );
+        return Ok(None);
+    };
+
+    tracing::debug!(
+        account_name = %account.account_name,
+        "Authenticated with client certificate",
+    );
+
+    Ok(Some(
+        catalog
+            .builder_chained()
+            .add_value(CurrentAccountSubject::logged(
+                account.id,
+                account.account_name,
+            ))
+            .build(),
+    ))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,196 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::HeaderMap;
+use internal_error::*;
+use ipnet::IpNet;
+
+use crate::listener::ServerConnectInfo;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Parses a list of addresses or CIDR ranges
+pub fn parse_ip_nets(values: &[String]) -> Result<Vec<IpNet>, InternalError> {
+    values
+        .iter()
+        .map(|value| {
+            value
+                .parse::<IpNet>()
+                .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| {
+                    InternalError::new(format!("Invalid IP address or CIDR range: {value}"))
+                })
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// IP address of the client that sent the request, taking trusted reverse
+/// proxies into account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// Returns the resolved client IP, falling back to the address of the peer
+    /// when the request didn't pass through [`client_ip_middleware`]. Requests
+    /// of the peers connected over Unix domain sockets have no client IP
+    /// unless it was forwarded by them.
+    pub fn from_extensions(extensions: &http::Extensions) -> Option<IpAddr> {
+        extensions.get::<Self>().map(|ip| ip.0).or_else(|| {
+            ServerConnectInfo::from_extensions(extensions)
+                .and_then(|info| info.remote_addr.ip())
+                .map(|ip| ip.to_canonical())
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    nets: Vec<IpNet>,
+}
+
+impl TrustedProxies {
+    pub fn from_config(values: &[String]) -> Result<Self, InternalError> {
+        Ok(Self {
+            nets: parse_ip_nets(values)?,
+        })
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.nets.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Determines the client IP by walking the chain of forwarding hops from
+    /// the closest one and stopping at the first address that is not a
+    /// trusted proxy. Forwarding headers are ignored unless the peer itself is
+    /// trusted.
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let peer = peer.to_canonical();
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        self.resolve_forwarded(headers).unwrap_or(peer)
+    }
+
+    /// Determines the client IP from the forwarding headers of a request sent
+    /// by a trusted proxy, returning `None` when they are absent
+    pub fn resolve_forwarded(&self, headers: &HeaderMap) -> Option<IpAddr> {
+        let mut client = None;
+        for hop in forwarded_hops(headers).into_iter().rev() {
+            // Obfuscated or malformed identifiers end the chain at the last
+            // known hop
+            let Some(ip) = hop else {
+                break;
+            };
+
+            client = Some(ip);
+            if !self.is_trusted(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Extracts the chain of client addresses from the `Forwarded` header,
+/// falling back to `X-Forwarded-For` when it's absent
+fn forwarded_hops(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let forwarded: Vec<_> = headers
+        .get_all(http::header::FORWARDED)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|element| {
+            element
+                .split(';')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                .and_then(|(_, value)| parse_node(value))
+        })
+        .collect();
+
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(parse_node)
+        .collect()
+}
+
+/// Parses a node identifier such as `192.0.2.1`, `192.0.2.1:4711`,
+/// `2001:db8::1`, or `"[2001:db8::1]:4711"`. Ports may be obfuscated.
+fn parse_node(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+
+    let ip = if let Some(rest) = value.strip_prefix('[') {
+        let (ip, port) = rest.split_once(']')?;
+        if !port.is_empty() && !port.starts_with(':') {
+            return None;
+        }
+        ip.parse().ok()?
+    } else if let Ok(ip) = value.parse::<IpAddr>() {
+        ip
+    } else {
+        let (host, _port) = value.rsplit_once(':')?;
+        IpAddr::V4(host.parse().ok()?)
+    };
+
+    Some(ip.to_canonical())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Resolves the client IP of the request and makes it available to the inner
+/// layers via the [`ClientIp`] extension and the `client_ip` field of the
+/// request span.
+///
+/// Peers connected over Unix domain sockets are local processes, like a
+/// reverse proxy, that are allowed to connect by the permissions of the socket
+/// file, so they are trusted to forward the client IP. Their requests without
+/// the forwarding headers have no client IP.
+pub async fn client_ip_middleware(
+    State(trusted_proxies): State<Arc<TrustedProxies>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(peer) =
+        ServerConnectInfo::from_extensions(request.extensions()).map(|info| info.remote_addr)
+    else {
+        return next.run(request).await;
+    };
+
+    let client_ip = match peer.ip() {
+        Some(ip) => trusted_proxies.resolve(ip, request.headers()),
+        None => match trusted_proxies.resolve_forwarded(request.headers()) {
+            Some(ip) => ip,
+            None => return next.run(request).await,
+        },
+    };
+    request.extensions_mut().insert(ClientIp(client_ip));
+
+    tracing::Span::current().record("client_ip", tracing::field::display(client_ip));
+    next.run(request).await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,129 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use internal_error::*;
+use url::Url;
+
+use super::{Command, CommandDesc};
+use crate::config::{AdminServerConfig, MaintenanceConfig};
+use crate::maintenance::{EnableMaintenanceRequest, MaintenanceStatus};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub enum MaintenanceAction {
+    Enable { message: Option<String> },
+    Disable,
+    Status,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Toggles maintenance mode of a running node via its admin listener
+#[dill::component]
+#[dill::interface(dyn Command)]
+#[dill::meta(CommandDesc {
+    needs_admin_auth: false,
+    needs_transaction: false,
+})]
+pub struct MaintenanceCommand {
+    admin_config: Option<Arc<AdminServerConfig>>,
+    maintenance_config: Arc<MaintenanceConfig>,
+
+    #[dill::component(explicit)]
+    admin_url: Option<Url>,
+
+    #[dill::component(explicit)]
+    action: MaintenanceAction,
+}
+
+impl MaintenanceCommand {
+    fn endpoint(&self) -> Result<Url, InternalError> {
+        let admin_url = match (&self.admin_url, &self.admin_config) {
+            (Some(admin_url), _) => admin_url.clone(),
+            (None, Some(admin_config)) => {
+                // Listener bound to all interfaces is reachable via the loopback one
+                let address = match admin_config.address {
+                    IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                    address => address,
+                };
+                Url::parse(&format!(
+                    "http://{}/",
+                    SocketAddr::new(address, admin_config.port)
+                ))
+                .int_err()?
+            }
+            (None, None) => {
+                return InternalError::bail(
+                    "Maintenance mode is toggled via the admin listener, either configure `admin` \
+                     or specify `--admin-url`, which can point to the HTTP API when \
+                     `maintenance.token` is configured"
+                        .to_string(),
+                );
+            }
+        };
+
+        admin_url.join("system/maintenance").int_err()
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for MaintenanceCommand {
+    async fn run(&self) -> Result<(), InternalError> {
+        let endpoint = self.endpoint()?;
+        let client = reqwest::Client::new();
+
+        let request = match &self.action {
+            MaintenanceAction::Enable { message } => client
+                .put(endpoint)
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(
+                    serde_json::to_vec(&EnableMaintenanceRequest {
+                        message: message.clone(),
+                    })
+                    .int_err()?,
+                ),
+            MaintenanceAction::Disable => client.delete(endpoint),
+            MaintenanceAction::Status => client.get(endpoint),
+        };
+        let request = match &self.maintenance_config.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+
+        let response = request
+            .send()
+            .await
+            .int_err()?
+            .error_for_status()
+            .int_err()?;
+        let status: MaintenanceStatus =
+            serde_json::from_slice(&response.bytes().await.int_err()?).int_err()?;
+
+        if !status.enabled {
+            println!("Maintenance mode: disabled");
+            return Ok(());
+        }
+
+        println!("Maintenance mode: enabled");
+        if let Some(since) = status.since {
+            println!("Since: {}", since.to_rfc3339());
+        }
+        if let Some(message) = status.message {
+            println!("Message: {message}");
+        }
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
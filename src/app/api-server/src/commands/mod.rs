@@ -12,6 +12,7 @@ mod debug_search_reindex_command;
 mod gql_query_command;
 mod gql_schema_command;
 mod list_metrics_command;
+mod maintenance_command;
 mod run_command;
 
 pub use debug_depgraph_command::*;
@@ -19,6 +20,7 @@ pub use debug_search_reindex_command::*;
 pub use gql_query_command::*;
 pub use gql_schema_command::*;
 pub use list_metrics_command::*;
+pub use maintenance_command::*;
 pub use run_command::*;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -7,20 +7,37 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::future::{Future, IntoFuture as _};
+use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
 
-use async_utils::BackgroundAgent;
 use internal_error::*;
 use kamu::domain::TenancyConfig;
 use kamu_accounts::CurrentAccountSubject;
+use tokio::task::JoinHandle;
 
 use super::{Command, CommandDesc};
+use crate::agent_supervisor::{AgentSupervisor, background_agent_names};
+use crate::config::{
+    AdminServerConfig,
+    HttpConfig,
+    ListenConfig,
+    ServerRole,
+    ShutdownConfig,
+    TlsConfig,
+};
+use crate::readiness::StartupJobsStatus;
+use crate::tls::{ALPN_H2, ALPN_HTTP1, ReloadableCertResolver, build_tls_acceptor};
 use crate::ui_configuration::UIConfiguration;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
+const AGENTS_ABORT_GRACE: Duration = Duration::from_secs(1);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[dill::component]
 #[dill::interface(dyn Command)]
 #[dill::meta(CommandDesc {
@@ -32,6 +49,13 @@ pub struct RunCommand {
 
     tenancy_config: TenancyConfig,
     ui_config: UIConfiguration,
+    tls_config: Option<Arc<TlsConfig>>,
+    http_config: Arc<HttpConfig>,
+    listen_config: Arc<ListenConfig>,
+    admin_config: Option<Arc<AdminServerConfig>>,
+    startup_jobs_status: Arc<StartupJobsStatus>,
+    agent_supervisor: Arc<AgentSupervisor>,
+    shutdown_config: Arc<ShutdownConfig>,
 
     #[dill::component(explicit)]
     server_account_subject: CurrentAccountSubject,
@@ -50,6 +74,43 @@ pub struct RunCommand {
 
     #[dill::component(explicit)]
     read_only: bool,
+
+    #[dill::component(explicit)]
+    roles: RunRoles,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Parts of the server that are run by the instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunRoles {
+    pub http: bool,
+    pub flightsql: bool,
+    pub agents: bool,
+}
+
+impl RunRoles {
+    /// Background agents are not run in read-only mode even with the `Worker`
+    /// role, as they process flows, tasks, and outbox messages that modify the
+    /// shared state
+    pub fn new(roles: &[ServerRole], read_only: bool) -> Result<Self, InternalError> {
+        if roles.is_empty() {
+            return InternalError::bail("At least one server role has to be specified".to_string());
+        }
+
+        let is_worker = roles.iter().any(|r| matches!(r, ServerRole::Worker));
+        if is_worker && read_only {
+            tracing::warn!("Background agents are not run in read-only mode");
+        }
+
+        Ok(Self {
+            http: roles.iter().any(|r| matches!(r, ServerRole::Api)),
+            flightsql: roles
+                .iter()
+                .any(|r| matches!(r, ServerRole::Api | ServerRole::FlightSql)),
+            agents: is_worker && !read_only,
+        })
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -72,69 +133,159 @@ impl Command for RunCommand {
             .add_value(self.server_account_subject.clone())
             .build();
 
-        if !self.read_only {
-            init_on_startup::run_startup_jobs(&system_catalog)
-                .await
-                .int_err()?;
-        }
-
         let address = self
             .address
             .unwrap_or(std::net::Ipv4Addr::new(127, 0, 0, 1).into());
 
+        let (http_tls_acceptor, flightsql_tls_acceptor) = match &self.tls_config {
+            Some(tls_config) => {
+                // Both listeners share the resolver so a certificate is reloaded only once
+                let resolver = Arc::new(ReloadableCertResolver::load(
+                    &tls_config.cert_path,
+                    &tls_config.key_path,
+                )?);
+                resolver.spawn_reload_watcher(tls_config.reload_interval.into());
+
+                (
+                    Some(build_tls_acceptor(
+                        tls_config,
+                        resolver.clone(),
+                        &[ALPN_HTTP1],
+                    )?),
+                    Some(build_tls_acceptor(tls_config, resolver, &[ALPN_H2])?),
+                )
+            }
+            None => (None, None),
+        };
+        let (http_scheme, flightsql_scheme) = if self.tls_config.is_some() {
+            ("https", "flightsql+tls")
+        } else {
+            ("http", "flightsql")
+        };
+
         // API servers are built from the regular catalog
         // that does not contain any auth subject, thus they will rely on
         // their own middlewares to authenticate per request / session and execute
         // all processing in the user context.
-        let (http_server, local_addr, maybe_shutdown_notify) = crate::http_server::build_server(
-            address,
-            self.http_port,
-            self.catalog.clone(),
-            self.tenancy_config,
-            self.ui_config.clone(),
-            self.e2e_http_port,
-            self.e2e_output_data_path.as_ref(),
-        )
-        .await?;
-
-        let flightsql_server = crate::flightsql_server::FlightSqlServer::new(
-            address,
-            self.flightsql_port,
-            self.catalog.clone(),
-        )
-        .await;
-
-        if let Some(e2e_output_data_path) = &self.e2e_output_data_path {
-            let e2e_file_content = format!(
-                "http://{}\nhttp://{}",
-                local_addr,
-                flightsql_server.local_addr()
+        let (http_server, maybe_shutdown_notify) = if self.roles.http {
+            let (http_server, local_addr, maybe_shutdown_notify) =
+                crate::http_server::build_server(
+                    address,
+                    self.http_port,
+                    self.listen_config.http.as_ref(),
+                    self.catalog.clone(),
+                    self.tenancy_config,
+                    UIConfiguration {
+                        read_only: self.read_only,
+                        ..self.ui_config.clone()
+                    },
+                    self.e2e_http_port,
+                    self.e2e_output_data_path.as_ref(),
+                    http_tls_acceptor,
+                    &self.http_config,
+                    self.admin_config.is_none(),
+                )
+                .await?;
+
+            tracing::info!(
+                http_endpoint = format!("{http_scheme}://{local_addr}"),
+                "Serving HTTP traffic"
             );
 
-            std::fs::write(e2e_output_data_path, e2e_file_content).unwrap();
-        }
+            (Some((http_server, local_addr)), maybe_shutdown_notify)
+        } else {
+            (None, None)
+        };
 
-        let background_agents = system_catalog
-            .get::<dill::AllOf<dyn BackgroundAgent>>()
-            .unwrap();
+        // Instances without the HTTP API still serve the system endpoints for health
+        // checks, falling back to the HTTP address when there is no dedicated admin
+        // listener
+        let admin_config = match &self.admin_config {
+            Some(admin_config) => Some((
+                admin_config.as_ref().clone(),
+                self.listen_config.admin.as_ref(),
+            )),
+            None if !self.roles.http => Some((
+                AdminServerConfig {
+                    address,
+                    port: self.e2e_http_port.or(self.http_port).unwrap_or(0),
+                    metrics_token: None,
+                },
+                self.listen_config
+                    .admin
+                    .as_ref()
+                    .or(self.listen_config.http.as_ref()),
+            )),
+            None => None,
+        };
 
-        // Ensure we have background agents registered
-        assert!(
-            !background_agents.is_empty(),
-            "No background agents found! This indicates a DI container configuration issue. Make \
-             sure all agent implementations are registered as both their specific trait (e.g., \
-             TaskAgent) AND as BackgroundAgent in the DI container."
-        );
+        let (admin_server, maybe_admin_shutdown_notify) = match &admin_config {
+            Some((admin_config, admin_listener_config)) => {
+                let (admin_server, admin_addr, maybe_shutdown_notify) =
+                    crate::admin_server::build_admin_server(
+                        admin_config,
+                        *admin_listener_config,
+                        self.catalog.clone(),
+                        self.e2e_output_data_path.is_some(),
+                        &self.http_config,
+                    )
+                    .await?;
 
-        tracing::info!(
-            http_endpoint = format!("http://{}", local_addr),
-            flightsql_endpoint = format!("flightsql://{}", flightsql_server.local_addr()),
-            "Serving traffic"
-        );
+                tracing::info!(
+                    admin_endpoint = format!("http://{admin_addr}"),
+                    "Serving admin endpoints"
+                );
+
+                (Some((admin_server, admin_addr)), maybe_shutdown_notify)
+            }
+            None => (None, None),
+        };
+
+        let flightsql_server = if self.roles.flightsql {
+            let flightsql_server = crate::flightsql_server::FlightSqlServer::new(
+                address,
+                self.flightsql_port,
+                self.listen_config.flightsql.as_ref(),
+                self.catalog.clone(),
+                flightsql_tls_acceptor,
+                self.read_only,
+            )
+            .await?;
+
+            tracing::info!(
+                flightsql_endpoint =
+                    format!("{flightsql_scheme}://{}", flightsql_server.local_addr()),
+                "Serving Flight SQL traffic"
+            );
+
+            Some(flightsql_server)
+        } else {
+            None
+        };
+
+        let background_agents = if self.roles.agents {
+            let background_agents = background_agent_names(&system_catalog).unwrap();
+
+            // Ensure we have background agents registered
+            assert!(
+                !background_agents.is_empty(),
+                "No background agents found! This indicates a DI container configuration issue. \
+                 Make sure all agent implementations are registered as both their specific trait \
+                 (e.g., TaskAgent) AND as BackgroundAgent in the DI container."
+            );
+
+            tracing::info!(
+                num_agents = background_agents.len(),
+                "Running background agents"
+            );
+            Some(background_agents)
+        } else {
+            None
+        };
 
         // TODO: Avoid using shutdown_notify in e2e and use signals instead
         let shutdown_future: Pin<Box<dyn Future<Output = ()> + Send>> =
-            if let Some(shutdown_notify) = maybe_shutdown_notify {
+            if let Some(shutdown_notify) = maybe_shutdown_notify.or(maybe_admin_shutdown_notify) {
                 let combined = async move {
                     tokio::select! {
                         _ = shutdown_requested => {}
@@ -146,60 +297,159 @@ impl Command for RunCommand {
                 Box::pin(shutdown_requested)
             };
 
-        let http_server = http_server
-            .with_graceful_shutdown(shutdown_future)
-            .into_future();
+        let mut coordinator = graceful_shutdown::ShutdownCoordinator::new();
+        let stop_accepting = coordinator.add_phase("stop-accepting", Duration::ZERO);
+        let drain_requests =
+            coordinator.add_phase("drain-requests", self.shutdown_config.drain_timeout.into());
+        // Supervisor aborts and reports the agents that did not stop within the
+        // timeout, the phase only has to give it a chance to do so
+        let stop_agents = coordinator.add_phase(
+            "stop-agents",
+            Duration::from(self.shutdown_config.agents_timeout) + AGENTS_ABORT_GRACE,
+        );
+        let flush_outbox = coordinator.add_phase(
+            "flush-outbox",
+            self.shutdown_config.outbox_flush_timeout.into(),
+        );
+
+        // E2E tests start sending requests as soon as the addresses are written,
+        // which happens only after the startup jobs
+        let e2e_output = self
+            .e2e_output_data_path
+            .as_ref()
+            .map(|e2e_output_data_path| {
+                let http_addr = http_server
+                    .as_ref()
+                    .or(admin_server.as_ref())
+                    .map(|(_, addr)| addr.clone());
+                let e2e_file_content = [
+                    http_addr,
+                    flightsql_server
+                        .as_ref()
+                        .map(|server| server.local_addr().clone()),
+                ]
+                .into_iter()
+                .flatten()
+                .map(|addr| format!("{http_scheme}://{addr}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+                (e2e_output_data_path, e2e_file_content)
+            });
 
-        // Start all background agents as tasks
+        // All listeners stop accepting connections at once and then wait for their
+        // in-flight requests
+        let mut http_task = http_server.map(|(http_server, _)| {
+            drain_requests.spawn(http_server.with_graceful_shutdown(stop_accepting.cancelled()))
+        });
+        let mut admin_task = admin_server.map(|(admin_server, _)| {
+            drain_requests.spawn(admin_server.with_graceful_shutdown(stop_accepting.cancelled()))
+        });
+        let mut flightsql_task = flightsql_server.map(|flightsql_server| {
+            drain_requests.spawn(flightsql_server.run(stop_accepting.cancelled()))
+        });
+
+        // Listeners are already accepting connections, so the readiness check reports
+        // the instance as not ready and the API requests are rejected until the
+        // startup jobs complete
+        if let Err(err) = self.run_startup_jobs(&system_catalog).await {
+            // Phases that timed out are already reported by the coordinator
+            coordinator.shutdown().await.ok();
+            return Err(err);
+        }
+
+        if let Some((e2e_output_data_path, e2e_file_content)) = e2e_output {
+            std::fs::write(e2e_output_data_path, e2e_file_content).unwrap();
+        }
+
+        // Start all background agents under supervision.
         // Note: Background agents are designed to run forever in event loops.
-        // If any agent completes normally, it's considered an unexpected event
-        // that should trigger a server shutdown.
-        let agent_tasks: Vec<_> = background_agents
-            .into_iter()
-            .map(|agent| {
-                let agent_name = agent.agent_name().to_string();
-                tokio::spawn(async move {
-                    tracing::info!("Starting background agent: {}", agent_name);
-                    let result = agent.run().await;
-                    match &result {
-                        Err(error) => {
-                            tracing::error!("Background agent {} failed: {}", agent_name, error);
-                        }
-                        Ok(()) => {
-                            tracing::warn!(
-                                "Background agent {} completed unexpectedly! This will cause \
-                                 server shutdown.",
-                                agent_name
-                            );
-                        }
-                    }
-                    result
-                })
+        // Failed agents are restarted with a backoff until one of them exceeds
+        // its failure budget, which triggers a server shutdown.
+        let mut agents_task = background_agents.map(|background_agents| {
+            let agent_supervisor = self.agent_supervisor.clone();
+            let catalog = system_catalog.clone();
+            let shutdown = stop_agents.token();
+            stop_agents.spawn(async move {
+                agent_supervisor
+                    .run(catalog, background_agents, shutdown)
+                    .await
             })
-            .collect();
-
-        // TODO: PERF: Do we need to spawn these into separate tasks?
-        if !self.read_only {
-            tokio::select! {
-                res = http_server => { res.int_err() },
-                res = flightsql_server.run() => { res.int_err() },
-                res = async {
-                    let (result, _index, _remaining) = futures::future::select_all(agent_tasks).await;
-                    match result {
-                        Ok(agent_result) => agent_result,
-                        Err(join_error) => {
-                            Err(InternalError::new(format!("Background agent task panicked: {join_error}")))
-                        },
-                    }
-                } => { res }
+        });
+
+        // Deliver messages produced by the requests and tasks that were completed
+        // during the previous phases
+        if self.roles.agents {
+            let outbox_agent = system_catalog
+                .get_one::<dyn messaging_outbox::OutboxAgent>()
+                .int_err()?;
+            let started = flush_outbox.cancelled();
+
+            flush_outbox.spawn(async move {
+                started.await;
+                if let Err(err) = outbox_agent.run_while_has_tasks().await {
+                    tracing::error!(error = ?err, error_msg = %err, "Failed to flush outbox");
+                }
+            });
+        }
+
+        let res = tokio::select! {
+            res = join_task(&mut http_task) => res,
+            res = join_task(&mut admin_task) => res,
+            res = join_task(&mut flightsql_task) => res,
+            res = join_task(&mut agents_task) => res,
+            () = shutdown_future => Ok(()),
+        };
+
+        // Remaining services are shut down gracefully even when one of them failed
+        let shutdown_res = coordinator.shutdown().await;
+
+        res?;
+        shutdown_res.int_err()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+impl RunCommand {
+    async fn run_startup_jobs(&self, system_catalog: &dill::Catalog) -> Result<(), InternalError> {
+        // Read-only replicas don't modify the shared state
+        if self.read_only {
+            self.startup_jobs_status.mark_skipped();
+            return Ok(());
+        }
+
+        match init_on_startup::run_startup_jobs(system_catalog)
+            .await
+            .int_err()
+        {
+            Ok(()) => {
+                self.startup_jobs_status.mark_completed();
+                Ok(())
             }
-        } else {
-            tokio::select! {
-                res = http_server => { res.int_err() },
-                res = flightsql_server.run() => { res.int_err() },
+            Err(err) => {
+                self.startup_jobs_status.mark_failed(&err);
+                Err(err)
             }
         }
     }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Waits for the task to complete, or forever if it was not started
+async fn join_task<E>(task: &mut Option<JoinHandle<Result<(), E>>>) -> Result<(), InternalError>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let Some(task) = task else {
+        return futures::future::pending().await;
+    };
+
+    match task.await {
+        Ok(res) => res.int_err(),
+        Err(join_error) => Err(InternalError::new(format!("Task panicked: {join_error}"))),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -10,6 +10,7 @@
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+use alloy::primitives::Address;
 use container_runtime::{ContainerRuntimeType, NetworkNamespaceType};
 use internal_error::*;
 use kamu_accounts::{AccountConfig, DidSecretEncryptionConfig};
@@ -48,6 +49,33 @@ pub struct ApiServerConfig {
     #[config(default)]
     pub protocol: ProtocolConfig,
 
+    /// Native TLS for the HTTP and FlightSQL listeners (plain text when not
+    /// specified)
+    pub tls: Option<TlsConfig>,
+
+    /// HTTP server policies
+    #[config(default)]
+    pub http: HttpConfig,
+
+    /// Listeners of the HTTP, Flight SQL, and admin servers (TCP on the
+    /// configured addresses and ports when not specified)
+    #[config(default)]
+    pub listen: ListenConfig,
+
+    /// Dedicated listener for the system and E2E endpoints, which are served
+    /// by the main HTTP listener when not specified
+    pub admin: Option<AdminServerConfig>,
+
+    /// Web UI served by the HTTP listener (not served when not specified)
+    pub ui: Option<WebUiConfig>,
+
+    /// Parts of the server run by this instance: `Api` serves HTTP and Flight
+    /// SQL, `FlightSql` serves Flight SQL only, and `Worker` runs background
+    /// agents that process flows, tasks, and outbox messages unless the server
+    /// is run in read-only mode
+    #[config(default = vec![ServerRole::Api, ServerRole::Worker])]
+    pub roles: Vec<ServerRole>,
+
     /// Tokio runtime
     #[config(default)]
     pub runtime: RuntimeConfig,
@@ -64,6 +92,11 @@ pub struct ApiServerConfig {
     #[config(default)]
     pub url: UrlConfig,
 
+    /// Custom domains of the accounts, serving their datasets without the
+    /// account name in the paths (multi-tenant mode only)
+    #[config(default)]
+    pub tenant_domains: Vec<TenantDomainConfig>,
+
     /// Configuration for the flow system
     #[config(default)]
     pub flow_system: FlowSystemConfig,
@@ -80,6 +113,22 @@ pub struct ApiServerConfig {
     #[config(default)]
     pub outbox: OutboxAgentConfig,
 
+    /// Supervision of background agents
+    #[config(default)]
+    pub background_agents: BackgroundAgentsConfig,
+
+    /// Election of the replica that runs the singleton background agents
+    #[config(default)]
+    pub leader_election: LeaderElectionConfig,
+
+    /// Timeouts of the graceful shutdown phases
+    #[config(default)]
+    pub shutdown: ShutdownConfig,
+
+    /// Maintenance mode toggled at runtime
+    #[config(default)]
+    pub maintenance: MaintenanceConfig,
+
     /// Email gateway configuration
     #[config(default = EmailConfig::dummy())]
     pub email: EmailConfig,
@@ -96,6 +145,9 @@ pub struct ApiServerConfig {
     #[config(default)]
     pub quota: QuotaConfig,
 
+    /// Embedded ODF oracle provider (disabled when not specified)
+    pub oracle: Option<OracleConfig>,
+
     /// Experimental and temporary module configuration
     #[config(default)]
     pub extra: ExtraConfig,
@@ -367,6 +419,28 @@ pub struct UrlConfig {
     pub base_url_flightsql: UrlOrPath,
 }
 
+/// Requests with the `Host` header matching the domain are routed to the
+/// datasets of the account, so that `{host}/{dataset_name}` resolves as
+/// `{account_name}/{dataset_name}` for the transfer protocol, data, and OData
+/// routes. Links in the responses to such requests and in the emails to the
+/// account use the URLs of the domain.
+#[derive(setty::Config)]
+pub struct TenantDomainConfig {
+    /// Host name of the domain, e.g. `data.acme.com`
+    pub host: String,
+
+    /// Name of the account whose datasets are served on the domain
+    pub account_name: String,
+
+    /// Base URL of the web platform on the domain (`https://{host}/` when not
+    /// specified)
+    pub base_url_platform: Option<UrlOrPath>,
+
+    /// Base URL of the API on the domain (`https://{host}/` when not
+    /// specified)
+    pub base_url_rest: Option<UrlOrPath>,
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 // Source
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -564,6 +638,378 @@ impl FlightSqlConfig {
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// TLS
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config)]
+pub struct TlsConfig {
+    /// Path to the PEM file with the server certificate chain
+    #[schemars(with = "String")]
+    pub cert_path: PathBuf,
+
+    /// Path to the PEM file with the private key of the certificate
+    #[schemars(with = "String")]
+    pub key_path: PathBuf,
+
+    /// Minimal version of the TLS protocol accepted from clients
+    #[config(default)]
+    pub min_version: TlsVersion,
+
+    /// How often to check the certificate and key files for changes to reload
+    /// them without a restart
+    #[config(default_str = "1m")]
+    pub reload_interval: DurationString,
+
+    /// Mutual TLS: authentication of clients by certificates (disabled when
+    /// not specified)
+    pub client_auth: Option<TlsClientAuthConfig>,
+}
+
+#[derive(setty::Config, setty::Default)]
+pub enum TlsVersion {
+    #[default]
+    Tls12,
+    Tls13,
+}
+
+#[derive(setty::Config)]
+pub struct TlsClientAuthConfig {
+    /// Path to the PEM file with CA certificates that client certificates
+    /// must be issued by
+    #[schemars(with = "String")]
+    pub ca_cert_path: PathBuf,
+
+    /// Whether to reject connections without a client certificate. Otherwise
+    /// such clients can still authenticate with a bearer token.
+    #[config(default = false)]
+    pub required: bool,
+
+    /// Accounts that clients presenting a matching certificate are
+    /// authenticated as
+    #[config(default)]
+    pub accounts: Vec<ClientCertAccountConfig>,
+}
+
+#[derive(setty::Config)]
+pub struct ClientCertAccountConfig {
+    /// Subject common name or one of the DNS, URI, or email subject
+    /// alternative names of the certificate
+    pub identity: String,
+
+    /// Name of the account to authenticate as
+    pub account_name: String,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// HTTP
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config, setty::Default)]
+pub struct HttpConfig {
+    /// Cross-origin resource sharing
+    #[config(default)]
+    pub cors: CorsConfig,
+
+    /// Security headers added to all responses
+    #[config(default)]
+    pub security_headers: SecurityHeadersConfig,
+
+    /// Rate limiting of requests per account, or per client IP for anonymous
+    /// requests (disabled when not specified)
+    pub rate_limit: Option<RateLimitConfig>,
+
+    /// Protection from slow clients and resource exhaustion
+    #[config(default)]
+    pub limits: HttpLimitsConfig,
+
+    /// Addresses or CIDR ranges of reverse proxies that are trusted to report
+    /// the client IP via `Forwarded` or `X-Forwarded-For` headers
+    #[config(default)]
+    pub trusted_proxies: Vec<String>,
+
+    /// Restrictions of client IPs allowed to access the API
+    #[config(default)]
+    pub ip_filter: IpFilterConfig,
+
+    /// Compression of the responses
+    #[config(default)]
+    pub compression: HttpCompressionConfig,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config, setty::Default)]
+pub struct CorsConfig {
+    /// Origins allowed to make cross-origin requests, e.g.
+    /// `https://platform.example.com` (`*` allows any origin)
+    #[config(default = vec!["*".to_string()])]
+    pub allowed_origins: Vec<String>,
+
+    /// Methods allowed in cross-origin requests (`*` allows any method)
+    #[config(default = vec!["GET".to_string(), "POST".to_string()])]
+    pub allowed_methods: Vec<String>,
+
+    /// Headers allowed in cross-origin requests (`*` allows any header)
+    #[config(default = vec!["*".to_string()])]
+    pub allowed_headers: Vec<String>,
+
+    /// Whether to allow requests with credentials (cookies, client
+    /// certificates, authorization headers). Cannot be combined with `*` in
+    /// any of the lists above.
+    #[config(default = false)]
+    pub allow_credentials: bool,
+
+    /// How long browsers can cache the results of preflight requests
+    pub max_age: Option<DurationString>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config, setty::Default)]
+pub struct SecurityHeadersConfig {
+    /// Value of `Strict-Transport-Security` max age (header is not sent when
+    /// not specified). Should only be used when the node is served over TLS.
+    pub hsts_max_age: Option<DurationString>,
+
+    /// Whether HSTS policy also applies to all subdomains
+    #[config(default = false)]
+    pub hsts_include_subdomains: bool,
+
+    /// Whether to send `X-Content-Type-Options: nosniff`
+    #[config(default = false)]
+    pub content_type_nosniff: bool,
+
+    /// Value of `X-Frame-Options` header (header is not sent when not
+    /// specified)
+    pub frame_options: Option<FrameOptions>,
+
+    /// Value of `Content-Security-Policy` header, e.g. `default-src 'self'`
+    /// (header is not sent when not specified)
+    pub content_security_policy: Option<String>,
+}
+
+#[derive(setty::Config)]
+pub enum FrameOptions {
+    Deny,
+    SameOrigin,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Limits are tracked separately for every route group. Groups without a limit
+/// are not throttled.
+#[derive(setty::Config, setty::Default)]
+pub struct RateLimitConfig {
+    /// GraphQL API
+    pub graphql: Option<RateLimitRuleConfig>,
+
+    /// Data queries via REST and OData APIs
+    pub query: Option<RateLimitRuleConfig>,
+
+    /// Push ingest and file uploads
+    pub ingest: Option<RateLimitRuleConfig>,
+
+    /// Smart and simple transfer protocols used by pull and push commands
+    pub transfer: Option<RateLimitRuleConfig>,
+}
+
+#[derive(setty::Config)]
+pub struct RateLimitRuleConfig {
+    /// Number of requests allowed per period
+    pub requests: u32,
+
+    /// Period over which the requests are allowed
+    #[config(default_str = "1m")]
+    pub period: DurationString,
+
+    /// Number of requests that can be made in a burst after being idle
+    /// (defaults to `requests`)
+    pub burst: Option<u32>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config, setty::Default)]
+pub struct HttpLimitsConfig {
+    /// Maximum time for a client to send the request headers
+    #[config(default_str = "30s")]
+    pub header_read_timeout: DurationString,
+
+    /// Time after which connections without any traffic in either direction
+    /// are closed (disabled when not specified). Also applies to WebSocket
+    /// connections and should exceed the request timeouts.
+    pub idle_timeout: Option<DurationString>,
+
+    /// Limits of requests outside of the route groups below
+    #[config(default)]
+    pub default: RequestLimitsConfig,
+
+    /// Limits of GraphQL API requests (unset values fall back to `default`)
+    pub graphql: Option<RequestLimitsConfig>,
+
+    /// Limits of data queries via REST and OData APIs (unset values fall back
+    /// to `default`)
+    pub query: Option<RequestLimitsConfig>,
+
+    /// Limits of push ingest and file uploads (unset values fall back to
+    /// `default`)
+    pub ingest: Option<RequestLimitsConfig>,
+
+    /// Limits of transfer protocol requests (unset values fall back to
+    /// `default`)
+    pub transfer: Option<RequestLimitsConfig>,
+}
+
+#[derive(setty::Config, setty::Default)]
+pub struct RequestLimitsConfig {
+    /// Maximum size of the request body in bytes (unlimited when not
+    /// specified)
+    pub max_body_size: Option<u64>,
+
+    /// Maximum time to process the request (unlimited when not specified)
+    pub timeout: Option<DurationString>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config, setty::Default)]
+pub struct IpFilterConfig {
+    /// Rule applied to all requests
+    pub global: Option<IpFilterRuleConfig>,
+
+    /// Rule applied to `/system/*` and `/e2e/*` routes, except for the
+    /// `/system/health` probes
+    pub admin: Option<IpFilterRuleConfig>,
+
+    /// Rule applied to requests that can modify data: transfer protocol
+    /// pushes and requests with methods other than `GET`, `HEAD`, and
+    /// `OPTIONS`, except for data queries. GraphQL requests are always
+    /// considered as writes as mutations are not distinguished from queries.
+    pub write: Option<IpFilterRuleConfig>,
+}
+
+#[derive(setty::Config, setty::Default)]
+pub struct IpFilterRuleConfig {
+    /// Addresses or CIDR ranges allowed to access the routes (any address is
+    /// allowed when empty)
+    #[config(default)]
+    pub allow: Vec<String>,
+
+    /// Addresses or CIDR ranges denied access to the routes (takes
+    /// precedence over `allow`)
+    #[config(default)]
+    pub deny: Vec<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config, setty::Default)]
+pub struct HttpCompressionConfig {
+    /// Whether to compress JSON and other textual responses with gzip, zstd,
+    /// or brotli, as negotiated via `Accept-Encoding`
+    #[config(default = true)]
+    pub enabled: bool,
+
+    /// Minimum size of the response body in bytes to compress (responses of
+    /// unknown size are always compressed)
+    #[config(default = 1024)]
+    pub min_size: u16,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Listen
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Peers connected over Unix domain sockets have no IP address. They are
+/// trusted to forward the client IP via the `Forwarded` or `X-Forwarded-For`
+/// headers, which is then matched by `http.ipFilter` and limited by
+/// `http.rateLimit` as for TCP connections. Requests without these headers
+/// bypass `http.ipFilter` and are rate limited per account only, so access to
+/// the socket has to be restricted by its permissions.
+#[derive(setty::Config, setty::Default)]
+pub struct ListenConfig {
+    /// Listener of the HTTP server
+    pub http: Option<ListenerConfig>,
+
+    /// Listener of the Flight SQL server
+    pub flightsql: Option<ListenerConfig>,
+
+    /// Listener of the admin server used instead of `admin.address` and
+    /// `admin.port`. Instances without the HTTP API serve the system endpoints
+    /// on it, or on the `http` one when not specified.
+    pub admin: Option<ListenerConfig>,
+}
+
+/// Unix domain socket or socket passed by systemd
+#[derive(setty::Config)]
+pub enum ListenerConfig {
+    Unix(UnixListenerConfig),
+    Systemd(SystemdListenerConfig),
+}
+
+/// Unix domain socket created by the server and removed on shutdown. A socket
+/// left by the previous run at the same path is replaced, unless it still
+/// accepts connections.
+#[derive(setty::Config)]
+pub struct UnixListenerConfig {
+    /// Path of the socket file
+    #[schemars(with = "String")]
+    pub path: PathBuf,
+
+    /// Octal permissions of the socket file, e.g. `660` to allow access to the
+    /// group only (determined by the umask when not specified)
+    pub mode: Option<String>,
+}
+
+/// Socket passed by systemd via socket activation
+#[derive(setty::Config)]
+pub struct SystemdListenerConfig {
+    /// `FileDescriptorName=` of the socket unit, required when more than one
+    /// socket is passed
+    pub name: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Admin
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config)]
+pub struct AdminServerConfig {
+    /// Network interface to expose the admin endpoints on
+    #[config(default_str = "127.0.0.1")]
+    pub address: std::net::IpAddr,
+
+    /// Port to expose the admin endpoints on
+    pub port: u16,
+
+    /// Bearer token required to access `/system/metrics` (no authentication
+    /// when not specified)
+    pub metrics_token: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Static files of the web UI build are served under `/` for the paths that
+/// don't match any of the API routes, with unknown page paths falling back to
+/// `index.html`. The `assets/runtime-config.json` of the build is generated to
+/// point the UI to `url.baseUrlRest`.
+#[derive(setty::Config)]
+pub struct WebUiConfig {
+    /// Directory or `.tar` / `.tar.gz` archive with the build of the web UI
+    #[schemars(with = "String")]
+    pub path: PathBuf,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config)]
+pub enum ServerRole {
+    Api,
+    FlightSql,
+    Worker,
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 // Database
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -678,6 +1124,98 @@ impl OutboxAgentConfig {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(setty::Config, setty::Default)]
+pub struct BackgroundAgentsConfig {
+    /// Delay before restarting a failed agent, doubled on every consecutive
+    /// failure
+    #[config(default_str = "1s")]
+    pub restart_min_delay: DurationString,
+
+    /// Maximum delay before restarting a failed agent. Agents that ran for
+    /// longer than this are considered recovered.
+    #[config(default_str = "5m")]
+    pub restart_max_delay: DurationString,
+
+    /// Number of failures of an agent tolerated within the
+    /// `failureBudgetWindow` before the server is shut down (`0` shuts down on
+    /// the first failure)
+    #[config(default = 5)]
+    pub failure_budget: u32,
+
+    /// Time window in which agent failures count against the budget
+    #[config(default_str = "10m")]
+    pub failure_budget_window: DurationString,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Replicas sharing a Postgres database elect the leader via an advisory lock.
+/// With SQLite and in-memory databases the only replica is always the leader.
+#[derive(setty::Config, setty::Default)]
+pub struct LeaderElectionConfig {
+    /// Names of background agents that run on the leader replica only (all
+    /// agents when not specified)
+    pub singleton_agents: Option<Vec<String>>,
+
+    /// Key of the Postgres advisory lock held by the leader
+    #[config(default = 1_801_547_125)]
+    pub lock_key: i64,
+
+    /// Interval between attempts to acquire the leadership and between checks
+    /// that the connection holding it is alive. Leadership is given up when a
+    /// check does not complete within the interval. It is also the TCP
+    /// keepalive interval of that connection, so that Postgres releases the
+    /// lock of a leader that is no longer reachable.
+    #[config(default_str = "5s")]
+    pub check_interval: DurationString,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Upon receiving a shutdown signal the server stops accepting new
+/// connections, waits for the in-flight requests, stops background agents, and
+/// flushes the outbox, in this order
+#[derive(setty::Config, setty::Default)]
+pub struct ShutdownConfig {
+    /// Time to wait for in-flight HTTP and Flight SQL requests to complete
+    #[config(default_str = "30s")]
+    pub drain_timeout: DurationString,
+
+    /// Time to wait for background agents to stop
+    #[config(default_str = "10s")]
+    pub agents_timeout: DurationString,
+
+    /// Time to wait for pending outbox messages to be delivered
+    #[config(default_str = "30s")]
+    pub outbox_flush_timeout: DurationString,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// While maintenance mode is enabled, writes are rejected with `503` and
+/// background agents are stopped, while queries keep working. It's toggled via
+/// `/system/maintenance` of the admin listener, or of the HTTP API when there
+/// is no admin listener and the `token` is specified.
+#[derive(setty::Config, setty::Default)]
+pub struct MaintenanceConfig {
+    /// Message returned to the clients when maintenance mode was enabled
+    /// without one
+    #[config(default = "Server is under maintenance, please try again later")]
+    pub message: String,
+
+    /// Bearer token required to enable and disable maintenance mode (no
+    /// authentication on the admin listener when not specified)
+    pub token: Option<String>,
+
+    /// Whether to stop the agents that can't finish their current iteration
+    /// on request, interrupting them in the middle of a task. Otherwise they
+    /// keep running in maintenance mode.
+    #[config(default = false)]
+    pub interrupt_agents: bool,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(setty::Config)]
 pub struct EmailConfig {
     pub sender_address: String,
@@ -905,6 +1443,10 @@ pub struct SearchRepositoryConfigElasticsearch {
     #[config(default = "http://localhost:9200")]
     pub url: String,
 
+    /// User to authenticate as by the readiness check
+    #[config(default = "elastic")]
+    pub username: String,
+
     pub password: Option<String>,
 
     #[schemars(with = "Option<String>")]
@@ -924,3 +1466,127 @@ pub struct SearchRepositoryConfigElasticsearch {
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Oracle
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(setty::Config)]
+pub struct OracleConfig {
+    /// Ethereum-compatible JSON-RPC address
+    pub rpc_url: Url,
+
+    /// ID of the chain used during signing to prevent replay attacks
+    pub chain_id: u64,
+
+    /// Address of the oracle contract to read logs from
+    #[config(combine(replace))]
+    #[schemars(with = "String")]
+    pub oracle_contract_address: Address,
+
+    /// Address of this provider's account to use when submitting transactions
+    #[config(combine(replace))]
+    #[schemars(with = "String")]
+    pub provider_address: Address,
+
+    /// Private key of the provider to use when signing transactions
+    pub provider_private_key: String,
+
+    /// Block number to start scanning from on startup (precedence:
+    /// scan_from_block, scan_last_blocks, scan_last_blocks_period)
+    pub scan_from_block: Option<u64>,
+
+    /// Number of last blocks to scan on startup (precedence: scan_from_block,
+    /// scan_last_blocks, scan_last_blocks_period)
+    pub scan_last_blocks: Option<u64>,
+
+    /// Time period in which blocks will be scanned on startup (precedence:
+    /// scan_from_block, scan_last_blocks, scan_last_blocks_period)
+    pub scan_last_blocks_period: Option<DurationString>,
+
+    /// Number of blocks to examine per one getLogs RPC request when catching up
+    #[config(default = 100_000)]
+    pub blocks_stride: u64,
+
+    /// Time to sleep while waiting for new blocks
+    #[config(default_str = "1s")]
+    pub loop_idle_time: DurationString,
+
+    /// Number of confirmations to await before considering transaction included
+    pub transaction_confirmations: u64,
+
+    /// Timeout when submitting a transaction
+    #[config(default_str = "1m")]
+    pub transaction_timeout: DurationString,
+
+    /// Maximum time to wait for in-flight requests to be executed and
+    /// submitted after receiving a shutdown signal (the agent is aborted
+    /// once `shutdown.agentsTimeout` elapses regardless)
+    #[config(default_str = "1m")]
+    pub shutdown_drain_timeout: DurationString,
+
+    /// Path to a file where provider persists its progress to resume from it
+    /// after a restart (takes precedence over the scan_* options)
+    #[schemars(with = "Option<String>")]
+    pub state_path: Option<PathBuf>,
+
+    /// Request IDs that provider should skip over (use as a disaster recovery
+    /// mechanism only)
+    #[config(default)]
+    pub ignore_requests: Vec<u64>,
+
+    /// Consumer addresses to ignore requests from (use as a disaster recovery
+    /// mechanism only)
+    #[config(default)]
+    #[schemars(with = "Vec<String>")]
+    pub ignore_consumers: Vec<Address>,
+
+    /// Consumer addresses that get their own `consumer_address` label in the
+    /// gas metrics, spend of the rest is aggregated under `other` to keep the
+    /// number of series bounded
+    #[config(default)]
+    #[schemars(with = "Vec<String>")]
+    pub gas_metrics_consumers: Vec<Address>,
+
+    /// Results that provider proactively pushes on-chain when datasets change
+    #[config(default)]
+    pub publications: Vec<kamu_oracle_provider::PublicationConfig>,
+
+    /// How often to check heads of the published datasets for changes
+    #[config(default_str = "30s")]
+    pub publications_check_interval: DurationString,
+}
+
+impl OracleConfig {
+    /// Converts into the config of a standalone provider, pointing it to the
+    /// hosting API server
+    pub fn into_provider_config(self, api_url: Url) -> kamu_oracle_provider::Config {
+        kamu_oracle_provider::Config {
+            // Embedded provider reuses the HTTP server of the host
+            http_address: "127.0.0.1".to_string(),
+            http_port: 0,
+            gas_spend_token: None,
+            rpc_url: self.rpc_url,
+            chain_id: self.chain_id,
+            oracle_contract_address: self.oracle_contract_address,
+            provider_address: self.provider_address,
+            provider_private_key: self.provider_private_key,
+            scan_from_block: self.scan_from_block,
+            scan_last_blocks: self.scan_last_blocks,
+            scan_last_blocks_period: self.scan_last_blocks_period,
+            blocks_stride: self.blocks_stride,
+            loop_idle_time: self.loop_idle_time,
+            transaction_confirmations: self.transaction_confirmations,
+            transaction_timeout: self.transaction_timeout,
+            shutdown_drain_timeout: self.shutdown_drain_timeout,
+            state_path: self.state_path,
+            api_url,
+            api_access_token: None,
+            ignore_requests: self.ignore_requests,
+            ignore_consumers: self.ignore_consumers,
+            gas_metrics_consumers: self.gas_metrics_consumers,
+            publications: self.publications,
+            publications_check_interval: self.publications_check_interval,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -87,6 +87,10 @@ pub(crate) fn configure_database_components(
             b.add::<kamu_auth_web3_postgres::PostgresWeb3AuthEip4361NonceRepository>();
 
             b.add::<kamu_search_cache_postgres::PostgresEmbeddingsCacheRepository>();
+
+            b.add::<crate::flow_request_id::PostgresFlowRequestIdRepository>();
+
+            b.add::<crate::leader_election::PostgresLeaderElection>();
         }
         DatabaseProvider::Sqlite => {
             SqlitePlugin::init_database_components(b);
@@ -124,6 +128,10 @@ pub(crate) fn configure_database_components(
             b.add::<kamu_auth_web3_sqlite::SqliteWeb3AuthEip4361NonceRepository>();
 
             b.add::<kamu_search_cache_sqlite::SqliteEmbeddingsCacheRepository>();
+
+            b.add::<crate::flow_request_id::SqliteFlowRequestIdRepository>();
+
+            b.add::<crate::leader_election::NoOpLeaderElection>();
         }
         DatabaseProvider::MySql | DatabaseProvider::MariaDB => {
             panic!(
@@ -174,6 +182,10 @@ pub(crate) fn configure_in_memory_components(b: &mut CatalogBuilder) {
 
     b.add::<kamu_search_cache_inmem::InMemoryEmbeddingsCacheRepository>();
 
+    b.add::<crate::flow_request_id::InMemoryFlowRequestIdRepository>();
+
+    b.add::<crate::leader_election::NoOpLeaderElection>();
+
     NoOpDatabasePlugin::init_database_components(b);
 }
 
@@ -26,6 +26,8 @@ use messaging_outbox::{
     MessageDeliveryMechanism,
 };
 
+use crate::tenant_domains::{TenantDomains, account_server_url_config};
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 pub const ACCESS_TOKEN_CREATED_SUBJECT: &str = "Access token created";
@@ -39,6 +41,7 @@ pub struct AccessTokenLifecycleNotifier {
     email_sender: Arc<dyn EmailSender>,
     account_service: Arc<dyn kamu_accounts::AccountService>,
     server_url_config: Arc<kamu::domain::ServerUrlConfig>,
+    tenant_domains: Option<Arc<TenantDomains>>,
 }
 
 #[component(pub)]
@@ -57,11 +60,13 @@ impl AccessTokenLifecycleNotifier {
         email_sender: Arc<dyn EmailSender>,
         account_service: Arc<dyn kamu_accounts::AccountService>,
         server_url_config: Arc<kamu::domain::ServerUrlConfig>,
+        tenant_domains: Option<Arc<TenantDomains>>,
     ) -> Self {
         Self {
             email_sender,
             account_service,
             server_url_config,
+            tenant_domains,
         }
     }
 
@@ -69,19 +74,19 @@ impl AccessTokenLifecycleNotifier {
         &self,
         created_token: &AccessTokenLifecycleMessageCreated,
     ) -> Result<(), InternalError> {
-        let token_list_url = self.format_access_token_list_url();
-        let access_token_email = AccessTokenCreatedEmail {
-            token_name: &created_token.token_name,
-            token_list_url: token_list_url.as_str(),
-        };
-        let rendered_access_token_body = access_token_email.render().unwrap();
-
         let owner_account = self
             .account_service
             .get_account_by_id(&created_token.owner_id)
             .await
             .int_err()?;
 
+        let token_list_url = self.format_access_token_list_url(&owner_account);
+        let access_token_email = AccessTokenCreatedEmail {
+            token_name: &created_token.token_name,
+            token_list_url: token_list_url.as_str(),
+        };
+        let rendered_access_token_body = access_token_email.render().unwrap();
+
         self.email_sender
             .send_email(
                 &owner_account.email,
@@ -92,10 +97,16 @@ impl AccessTokenLifecycleNotifier {
             .int_err()
     }
 
-    fn format_access_token_list_url(&self) -> String {
+    fn format_access_token_list_url(&self, owner_account: &kamu_accounts::Account) -> String {
+        let server_url_config = account_server_url_config(
+            self.tenant_domains.as_deref(),
+            &self.server_url_config,
+            &owner_account.account_name,
+        );
+
         format!(
             "{}v/settings/access-tokens",
-            self.server_url_config.protocols.base_url_platform,
+            server_url_config.protocols.base_url_platform,
         )
     }
 }
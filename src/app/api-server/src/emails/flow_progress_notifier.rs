@@ -25,6 +25,10 @@ use messaging_outbox::{
     MessageDeliveryMechanism,
 };
 
+use crate::flow_request_id::FlowRequestIdRepository;
+use crate::request_id::RequestId;
+use crate::tenant_domains::{TenantDomains, account_server_url_config};
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 pub const FLOW_FAILED_SUBJECT: &str = "Kamu Flow Run Failed";
@@ -41,6 +45,8 @@ pub struct FlowProgressNotifier {
     account_service: Arc<dyn kamu_accounts::AccountService>,
     server_url_config: Arc<kamu::domain::ServerUrlConfig>,
     tenancy_config: Arc<kamu::domain::TenancyConfig>,
+    flow_request_id_repository: Arc<dyn FlowRequestIdRepository>,
+    tenant_domains: Option<Arc<TenantDomains>>,
 }
 
 #[component(pub)]
@@ -62,6 +68,8 @@ impl FlowProgressNotifier {
         account_service: Arc<dyn kamu_accounts::AccountService>,
         server_url_config: Arc<kamu::domain::ServerUrlConfig>,
         tenancy_config: Arc<kamu::domain::TenancyConfig>,
+        flow_request_id_repository: Arc<dyn FlowRequestIdRepository>,
+        tenant_domains: Option<Arc<TenantDomains>>,
     ) -> Self {
         Self {
             email_sender,
@@ -70,6 +78,8 @@ impl FlowProgressNotifier {
             account_service,
             server_url_config,
             tenancy_config,
+            flow_request_id_repository,
+            tenant_domains,
         }
     }
 
@@ -106,6 +116,16 @@ impl FlowProgressNotifier {
                 .select_dataset_flow_recipient(&owner_account, &flow_state)
                 .await?;
 
+            // Request that triggered the flow manually
+            let request_id = match flow_state.primary_activation_cause() {
+                kamu_fs::FlowActivationCause::Manual(_) => {
+                    self.flow_request_id_repository
+                        .get_request_id(flow_id)
+                        .await?
+                }
+                _ => None,
+            };
+
             // Render email
             let rendered_email = self
                 .render_dataset_flow_failure_email(
@@ -125,6 +145,7 @@ impl FlowProgressNotifier {
                             .last_attempt_finished_at
                             .expect("Finish time should be defined"),
                         primary_activation_cause: flow_state.primary_activation_cause(),
+                        request_id: request_id.as_ref(),
                     },
                 )
                 .await?;
@@ -204,6 +225,7 @@ impl FlowProgressNotifier {
             start_time: start_time.as_str(),
             failure_time: failure_time.as_str(),
             flow_details_url: &flow_details_url,
+            request_id: flow_failure_data.request_id.map(RequestId::as_str),
         };
 
         email.render().int_err()
@@ -239,9 +261,15 @@ impl FlowProgressNotifier {
         owner_account: &kamu_accounts::Account,
         dataset_entry: &kamu_datasets::DatasetEntry,
     ) -> String {
+        let server_url_config = account_server_url_config(
+            self.tenant_domains.as_deref(),
+            &self.server_url_config,
+            &owner_account.account_name,
+        );
+
         format!(
             "{}{}",
-            self.server_url_config.protocols.base_url_platform,
+            server_url_config.protocols.base_url_platform,
             self.format_dataset_alias(owner_account, dataset_entry)
         )
     }
@@ -299,6 +327,7 @@ struct FlowFailureData<'a> {
     started_at: DateTime<Utc>,
     occurred_at: DateTime<Utc>,
     error: &'a str,
+    request_id: Option<&'a RequestId>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -314,6 +343,7 @@ struct FlowFailedEmail<'a> {
     failure_time: &'a str,
     failure_reason: &'a str,
     flow_details_url: &'a str,
+    request_id: Option<&'a str>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -13,42 +13,84 @@ use std::net::SocketAddr;
 
 use arrow_flight::flight_service_server::FlightServiceServer;
 use futures::Future;
+use internal_error::*;
 use kamu_accounts::AuthConfig;
 use kamu_adapter_flight_sql::{AuthPolicyLayer, AuthenticationLayer, KamuFlightSqlServiceWrapper};
-use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
 use tonic::transport::Server;
 
+use crate::client_cert_auth::ClientCertAuthenticationLayer;
+use crate::config::ListenerConfig;
+use crate::listener::{ListenerAddr, ServerListener};
+use crate::maintenance::MaintenanceMode;
+use crate::read_only::FlightSqlReadOnlyLayer;
+use crate::readiness::{STARTING_UP_MESSAGE, StartupJobsStatus};
+use crate::request_id::RequestIdLayer;
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 pub(crate) struct FlightSqlServer {
     catalog: dill::Catalog,
-    listener: TcpListener,
+    listener: ServerListener,
     allow_anonymous: bool,
+    read_only: bool,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 impl FlightSqlServer {
-    pub async fn new(address: std::net::IpAddr, port: Option<u16>, catalog: dill::Catalog) -> Self {
-        let listener = TcpListener::bind((address, port.unwrap_or_default()))
-            .await
-            .unwrap();
+    pub async fn new(
+        address: std::net::IpAddr,
+        port: Option<u16>,
+        listener_config: Option<&ListenerConfig>,
+        catalog: dill::Catalog,
+        tls_acceptor: Option<TlsAcceptor>,
+        read_only: bool,
+    ) -> Result<Self, InternalError> {
+        let listener = ServerListener::bind_configured(
+            SocketAddr::new(address, port.unwrap_or_default()),
+            listener_config,
+            tls_acceptor,
+        )
+        .await?;
         let auth_config = catalog.get_one::<AuthConfig>().unwrap();
 
-        Self {
+        Ok(Self {
             catalog,
             listener,
             allow_anonymous: auth_config.allow_anonymous,
-        }
+            read_only,
+        })
     }
 
-    pub fn local_addr(&self) -> SocketAddr {
-        self.listener.local_addr().unwrap()
+    pub fn local_addr(&self) -> &ListenerAddr {
+        self.listener.local_addr()
     }
 
-    pub fn run(self) -> impl Future<Output = Result<(), impl std::error::Error>> {
+    /// Serves requests until the `signal` completes and then waits for the
+    /// in-flight ones to finish
+    pub async fn run<F>(self, signal: F) -> Result<(), InternalError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let startup_jobs_status = self.catalog.get_one::<StartupJobsStatus>().ok();
+
         Server::builder()
+            .layer(RequestIdLayer::new())
             .layer(observability::tonic::grpc_layer())
+            // Queries are rejected until the startup jobs complete
+            .layer(tonic::service::interceptor::InterceptorLayer::new(
+                move |req: tonic::Request<()>| match &startup_jobs_status {
+                    Some(status) if !status.is_finished() => {
+                        Err(tonic::Status::unavailable(STARTING_UP_MESSAGE))
+                    }
+                    _ => Ok(req),
+                },
+            ))
+            .layer(FlightSqlReadOnlyLayer::new(
+                self.read_only,
+                self.catalog.get_one::<MaintenanceMode>().ok(),
+            ))
             .layer(tonic::service::interceptor::InterceptorLayer::new(
                 move |mut req: tonic::Request<()>| {
                     req.extensions_mut().insert(self.catalog.clone());
@@ -56,11 +98,17 @@ impl FlightSqlServer {
                 },
             ))
             .layer(AuthenticationLayer::new())
+            .layer(ClientCertAuthenticationLayer::new())
             .layer(AuthPolicyLayer::new(self.allow_anonymous))
             .add_service(FlightServiceServer::new(KamuFlightSqlServiceWrapper))
-            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(
-                self.listener,
-            ))
+            // Server stops accepting connections and waits for the in-flight
+            // requests once the incoming stream ends on the signal
+            .serve_with_incoming_shutdown(
+                self.listener.into_incoming(signal),
+                std::future::pending(),
+            )
+            .await
+            .int_err()
     }
 }
 
@@ -0,0 +1,293 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use database_common::{TransactionRef, TransactionRefT};
+use internal_error::*;
+use kamu_flow_system as kamu_fs;
+
+use crate::request_id::RequestId;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS flow_request_ids (flow_id TEXT PRIMARY \
+                            KEY, request_id TEXT NOT NULL)";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Stores the ID of the request that triggered a flow manually alongside the
+/// flow, so that notifications sent when the flow fails, possibly by another
+/// replica and after restarts, refer to that request
+#[async_trait::async_trait]
+pub trait FlowRequestIdRepository: Send + Sync {
+    /// Keeps the request that triggered the flow first when the flow was
+    /// already waiting to run
+    async fn save_request_id(
+        &self,
+        flow_id: kamu_fs::FlowID,
+        request_id: &RequestId,
+    ) -> Result<(), InternalError>;
+
+    async fn get_request_id(
+        &self,
+        flow_id: kamu_fs::FlowID,
+    ) -> Result<Option<RequestId>, InternalError>;
+}
+
+/// Creates the table of the request IDs in the database the repositories
+/// work with
+pub(crate) async fn init_flow_request_ids_table(
+    catalog: &dill::Catalog,
+) -> Result<(), InternalError> {
+    if let Ok(pool) = catalog.get_one::<sqlx::PgPool>() {
+        sqlx::query(CREATE_TABLE)
+            .execute(pool.as_ref())
+            .await
+            .int_err()?;
+    } else if let Ok(pool) = catalog.get_one::<sqlx::SqlitePool>() {
+        sqlx::query(CREATE_TABLE)
+            .execute(pool.as_ref())
+            .await
+            .int_err()?;
+    }
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default)]
+pub struct InMemoryFlowRequestIdRepository {
+    request_ids: Mutex<HashMap<kamu_fs::FlowID, RequestId>>,
+}
+
+#[dill::component(pub)]
+#[dill::interface(dyn FlowRequestIdRepository)]
+#[dill::scope(dill::Singleton)]
+impl InMemoryFlowRequestIdRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl FlowRequestIdRepository for InMemoryFlowRequestIdRepository {
+    async fn save_request_id(
+        &self,
+        flow_id: kamu_fs::FlowID,
+        request_id: &RequestId,
+    ) -> Result<(), InternalError> {
+        self.request_ids
+            .lock()
+            .unwrap()
+            .entry(flow_id)
+            .or_insert_with(|| request_id.clone());
+        Ok(())
+    }
+
+    async fn get_request_id(
+        &self,
+        flow_id: kamu_fs::FlowID,
+    ) -> Result<Option<RequestId>, InternalError> {
+        Ok(self.request_ids.lock().unwrap().get(&flow_id).cloned())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct PostgresFlowRequestIdRepository {
+    transaction: TransactionRefT<sqlx::Postgres>,
+}
+
+#[dill::component(pub)]
+#[dill::interface(dyn FlowRequestIdRepository)]
+impl PostgresFlowRequestIdRepository {
+    pub fn new(transaction: TransactionRef) -> Self {
+        Self {
+            transaction: transaction.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl FlowRequestIdRepository for PostgresFlowRequestIdRepository {
+    async fn save_request_id(
+        &self,
+        flow_id: kamu_fs::FlowID,
+        request_id: &RequestId,
+    ) -> Result<(), InternalError> {
+        let mut tr = self.transaction.lock().await;
+        let connection_mut = tr.connection_mut().await?;
+
+        sqlx::query(
+            "INSERT INTO flow_request_ids (flow_id, request_id) VALUES ($1, $2) ON CONFLICT \
+             (flow_id) DO NOTHING",
+        )
+        .bind(flow_id.to_string())
+        .bind(request_id.as_str())
+        .execute(connection_mut)
+        .await
+        .int_err()?;
+
+        Ok(())
+    }
+
+    async fn get_request_id(
+        &self,
+        flow_id: kamu_fs::FlowID,
+    ) -> Result<Option<RequestId>, InternalError> {
+        let mut tr = self.transaction.lock().await;
+        let connection_mut = tr.connection_mut().await?;
+
+        let request_id: Option<String> =
+            sqlx::query_scalar("SELECT request_id FROM flow_request_ids WHERE flow_id = $1")
+                .bind(flow_id.to_string())
+                .fetch_optional(connection_mut)
+                .await
+                .int_err()?;
+
+        Ok(request_id.map(RequestId::new_unchecked))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct SqliteFlowRequestIdRepository {
+    transaction: TransactionRefT<sqlx::Sqlite>,
+}
+
+#[dill::component(pub)]
+#[dill::interface(dyn FlowRequestIdRepository)]
+impl SqliteFlowRequestIdRepository {
+    pub fn new(transaction: TransactionRef) -> Self {
+        Self {
+            transaction: transaction.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl FlowRequestIdRepository for SqliteFlowRequestIdRepository {
+    async fn save_request_id(
+        &self,
+        flow_id: kamu_fs::FlowID,
+        request_id: &RequestId,
+    ) -> Result<(), InternalError> {
+        let mut tr = self.transaction.lock().await;
+        let connection_mut = tr.connection_mut().await?;
+
+        sqlx::query(
+            "INSERT INTO flow_request_ids (flow_id, request_id) VALUES ($1, $2) ON CONFLICT \
+             (flow_id) DO NOTHING",
+        )
+        .bind(flow_id.to_string())
+        .bind(request_id.as_str())
+        .execute(connection_mut)
+        .await
+        .int_err()?;
+
+        Ok(())
+    }
+
+    async fn get_request_id(
+        &self,
+        flow_id: kamu_fs::FlowID,
+    ) -> Result<Option<RequestId>, InternalError> {
+        let mut tr = self.transaction.lock().await;
+        let connection_mut = tr.connection_mut().await?;
+
+        let request_id: Option<String> =
+            sqlx::query_scalar("SELECT request_id FROM flow_request_ids WHERE flow_id = $1")
+                .bind(flow_id.to_string())
+                .fetch_optional(connection_mut)
+                .await
+                .int_err()?;
+
+        Ok(request_id.map(RequestId::new_unchecked))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Runs flows via the flow system and saves the ID of the request that
+/// triggered a flow manually. The request ID is available in the catalog of
+/// the GraphQL mutations.
+pub struct RequestIdFlowRunService {
+    inner: Arc<kamu_flow_system_services::FlowRunServiceImpl>,
+    repository: Arc<dyn FlowRequestIdRepository>,
+    request_id: Option<Arc<RequestId>>,
+}
+
+#[dill::component(pub)]
+#[dill::interface(dyn kamu_fs::FlowRunService)]
+impl RequestIdFlowRunService {
+    pub fn new(
+        inner: Arc<kamu_flow_system_services::FlowRunServiceImpl>,
+        repository: Arc<dyn FlowRequestIdRepository>,
+        request_id: Option<Arc<RequestId>>,
+    ) -> Self {
+        Self {
+            inner,
+            repository,
+            request_id,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl kamu_fs::FlowRunService for RequestIdFlowRunService {
+    async fn run_flow_automatically(
+        &self,
+        activation_time: DateTime<Utc>,
+        flow_binding: &kamu_fs::FlowBinding,
+        activation_causes: Vec<kamu_fs::FlowActivationCause>,
+        maybe_flow_trigger_rule: Option<kamu_fs::FlowTriggerRule>,
+        maybe_flow_config_snapshot: Option<kamu_fs::FlowConfigurationRule>,
+    ) -> Result<kamu_fs::FlowState, kamu_fs::RunFlowError> {
+        self.inner
+            .run_flow_automatically(
+                activation_time,
+                flow_binding,
+                activation_causes,
+                maybe_flow_trigger_rule,
+                maybe_flow_config_snapshot,
+            )
+            .await
+    }
+
+    async fn run_flow_manually(
+        &self,
+        activation_time: DateTime<Utc>,
+        flow_binding: &kamu_fs::FlowBinding,
+        initiator_account_id: odf::AccountID,
+        maybe_flow_config_snapshot: Option<kamu_fs::FlowConfigurationRule>,
+    ) -> Result<kamu_fs::FlowState, kamu_fs::RunFlowError> {
+        let flow_state = self
+            .inner
+            .run_flow_manually(
+                activation_time,
+                flow_binding,
+                initiator_account_id,
+                maybe_flow_config_snapshot,
+            )
+            .await?;
+
+        if let Some(request_id) = &self.request_id {
+            self.repository
+                .save_request_id(flow_state.flow_id, request_id)
+                .await?;
+        }
+
+        Ok(flow_state)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
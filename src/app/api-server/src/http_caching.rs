@@ -0,0 +1,167 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use database_common::DatabaseTransactionRunner;
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+use internal_error::*;
+use kamu::domain::ResolvedDataset;
+use kamu_accounts::CurrentAccountSubject;
+use kamu_auth_rebac::RebacService;
+
+use crate::route_group::matched_route;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Segments preceding the hash in the paths of the content-addressed objects
+/// served by the transfer protocol
+const CONTENT_ADDRESSED_SEGMENTS: [&str; 3] = ["blocks", "data", "checkpoints"];
+
+/// Objects of public datasets can be stored by shared caches
+const CACHE_CONTROL_IMMUTABLE_PUBLIC: &str = "public, max-age=31536000, immutable";
+const CACHE_CONTROL_IMMUTABLE_PRIVATE: &str = "private, max-age=31536000, immutable";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Returns the hash of the content-addressed object that the request path
+/// points to. The route is classified by its template, e.g.
+/// `/{account_name}/{dataset_name}/blocks/{block_hash}`, so that the accounts
+/// and datasets named like the segments are not mistaken for objects.
+pub fn content_address<'a>(route: &str, path: &'a str) -> Option<&'a str> {
+    let mut route_segments = route.trim_end_matches('/').rsplit('/');
+    let (Some(param), Some(kind)) = (route_segments.next(), route_segments.next()) else {
+        return None;
+    };
+    let is_object_route = CONTENT_ADDRESSED_SEGMENTS.contains(&kind)
+        && param.starts_with('{')
+        && param.ends_with('}')
+        && !param.starts_with("{*");
+    if !is_object_route {
+        return None;
+    }
+
+    let hash = path.trim_end_matches('/').rsplit('/').next()?;
+    (!hash.is_empty() && hash.bytes().all(|b| b.is_ascii_alphanumeric())).then_some(hash)
+}
+
+/// Whether any of the `If-None-Match` tags of the request matches the entity
+/// tag, using the weak comparison as required for this header
+pub fn is_none_match_satisfied(headers: &HeaderMap, etag: &str) -> bool {
+    if_none_match_tags(headers).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+fn if_none_match_tags(headers: &HeaderMap) -> impl Iterator<Item = &str> {
+    headers
+        .get_all(http::header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+}
+
+/// Whether the objects of the dataset can be stored by shared caches, i.e.
+/// the dataset is public and the server allows the anonymous access, which
+/// holds when the request is anonymous and passed the authorization
+async fn is_publicly_cacheable(request: &Request) -> Result<bool, InternalError> {
+    let extensions = request.extensions();
+    let (Some(catalog), Some(target)) = (
+        extensions.get::<dill::Catalog>(),
+        extensions.get::<ResolvedDataset>(),
+    ) else {
+        return Ok(false);
+    };
+
+    let is_anonymous = catalog
+        .get_one::<CurrentAccountSubject>()
+        .is_ok_and(|subject| matches!(subject.as_ref(), CurrentAccountSubject::Anonymous(_)));
+    if !is_anonymous {
+        return Ok(false);
+    }
+
+    let dataset_id = target.get_id().clone();
+    DatabaseTransactionRunner::new(catalog.clone())
+        .transactional(|transaction_catalog| async move {
+            let rebac_service = transaction_catalog
+                .get_one::<dyn RebacService>()
+                .int_err()?;
+            let properties = rebac_service
+                .get_dataset_properties(&dataset_id)
+                .await
+                .int_err()?;
+            Ok(properties.allows_anonymous_read && properties.allows_public_read)
+        })
+        .await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Adds a strong `ETag` and a long-lived `Cache-Control` to the successful
+/// responses with the content-addressed objects, which never change under the
+/// same hash, and answers the conditional requests with `304`.
+///
+/// Must be placed after the dataset authorization, so that the access is
+/// checked before answering with `304`. The requests with the tag of the hash
+/// in the path are answered without reading the object, as the client already
+/// holds its content. Only the objects of public datasets are marked as
+/// cacheable by shared caches. Redirects to the object storage are not cached,
+/// as their URLs expire.
+pub async fn immutable_object_middleware(request: Request, next: Next) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+    let Some(etag) = matched_route(&request)
+        .and_then(|route| content_address(route, request.uri().path()))
+        .map(|hash| format!("\"{hash}\""))
+    else {
+        return next.run(request).await;
+    };
+
+    let is_public = match is_publicly_cacheable(&request).await {
+        Ok(is_public) => is_public,
+        Err(err) => {
+            tracing::warn!(error = ?err, "Failed to check whether the dataset is public");
+            false
+        }
+    };
+
+    // The clients holding the object under the tag of its hash don't need it to
+    // be read, while `*` requires it to exist
+    let mut response =
+        if if_none_match_tags(request.headers()).any(|tag| tag.trim_start_matches("W/") == etag) {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            let is_not_modified = is_none_match_satisfied(request.headers(), &etag);
+            let response = next.run(request).await;
+            if response.status() != StatusCode::OK {
+                return response;
+            }
+
+            if is_not_modified {
+                StatusCode::NOT_MODIFIED.into_response()
+            } else {
+                response
+            }
+        };
+
+    let cache_control = HeaderValue::from_static(if is_public {
+        CACHE_CONTROL_IMMUTABLE_PUBLIC
+    } else {
+        CACHE_CONTROL_IMMUTABLE_PRIVATE
+    });
+
+    let headers = response.headers_mut();
+    // Hashes consist of alphanumeric characters only
+    headers.insert(http::header::ETAG, HeaderValue::from_str(&etag).unwrap());
+    headers.insert(http::header::CACHE_CONTROL, cache_control);
+    response
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
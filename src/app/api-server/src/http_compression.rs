@@ -0,0 +1,68 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use http::{Extensions, HeaderMap, StatusCode, Version};
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{Predicate, SizeAbove};
+
+use crate::config::HttpCompressionConfig;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Compresses the responses with gzip, zstd, or brotli, as negotiated via
+/// `Accept-Encoding`. Only textual responses, such as JSON of the REST and
+/// GraphQL APIs, are compressed, as data files and metadata blocks are either
+/// compressed already or are served as is to keep their hashes verifiable.
+pub fn build_compression_layer(config: &HttpCompressionConfig) -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new()
+        .gzip(true)
+        .zstd(true)
+        .br(true)
+        .compress_when(SizeAbove::new(config.min_size).and(is_compressible_response))
+}
+
+fn is_compressible_response(
+    _status: StatusCode,
+    _version: Version,
+    headers: &HeaderMap,
+    _extensions: &Extensions,
+) -> bool {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(is_compressible_content_type)
+}
+
+/// Whether the content type is textual, excluding the event streams that must
+/// be delivered without buffering
+pub fn is_compressible_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    if mime == "text/event-stream" {
+        return false;
+    }
+
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || [
+            "application/json",
+            "application/x-ndjson",
+            "application/javascript",
+            "application/xml",
+        ]
+        .contains(&mime.as_str())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,166 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::str::FromStr as _;
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{HeaderName, HeaderValue, Method};
+use internal_error::*;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use crate::config::{CorsConfig, FrameOptions, SecurityHeadersConfig};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const WILDCARD: &str = "*";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub fn build_cors_layer(config: &CorsConfig) -> Result<CorsLayer, InternalError> {
+    let is_wildcard = |values: &[String]| values.iter().any(|v| v == WILDCARD);
+
+    if config.allow_credentials
+        && (is_wildcard(&config.allowed_origins)
+            || is_wildcard(&config.allowed_methods)
+            || is_wildcard(&config.allowed_headers))
+    {
+        return InternalError::bail(
+            "CORS credentials cannot be allowed together with wildcard origins, methods, or \
+             headers"
+                .to_string(),
+        );
+    }
+
+    let allow_origin = if is_wildcard(&config.allowed_origins) {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .allowed_origins
+                .iter()
+                .map(|v| HeaderValue::from_str(v).int_err())
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+
+    let allow_methods = if is_wildcard(&config.allowed_methods) {
+        AllowMethods::any()
+    } else {
+        AllowMethods::list(
+            config
+                .allowed_methods
+                .iter()
+                .map(|v| Method::from_str(&v.to_ascii_uppercase()).int_err())
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+
+    let allow_headers = if is_wildcard(&config.allowed_headers) {
+        AllowHeaders::any()
+    } else {
+        AllowHeaders::list(
+            config
+                .allowed_headers
+                .iter()
+                .map(|v| HeaderName::from_str(v).int_err())
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+
+    let mut layer = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(allow_methods)
+        .allow_headers(allow_headers)
+        .allow_credentials(config.allow_credentials);
+
+    if let Some(max_age) = config.max_age {
+        layer = layer.max_age(Duration::from(max_age));
+    }
+
+    Ok(layer)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Headers added to every response unless the handler has set them already
+#[derive(Debug, Clone, Default)]
+pub struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl SecurityHeaders {
+    pub fn from_config(config: &SecurityHeadersConfig) -> Result<Self, InternalError> {
+        let mut headers = Vec::new();
+
+        if let Some(max_age) = config.hsts_max_age {
+            let max_age = Duration::from(max_age).as_secs();
+            let value = if config.hsts_include_subdomains {
+                format!("max-age={max_age}; includeSubDomains")
+            } else {
+                format!("max-age={max_age}")
+            };
+            headers.push((
+                http::header::STRICT_TRANSPORT_SECURITY,
+                HeaderValue::from_str(&value).int_err()?,
+            ));
+        }
+
+        if config.content_type_nosniff {
+            headers.push((
+                http::header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ));
+        }
+
+        if let Some(frame_options) = &config.frame_options {
+            let value = match frame_options {
+                FrameOptions::Deny => "DENY",
+                FrameOptions::SameOrigin => "SAMEORIGIN",
+            };
+            headers.push((
+                http::header::X_FRAME_OPTIONS,
+                HeaderValue::from_static(value),
+            ));
+        }
+
+        if let Some(csp) = &config.content_security_policy {
+            headers.push((
+                http::header::CONTENT_SECURITY_POLICY,
+                HeaderValue::from_str(csp).map_err(|err| {
+                    InternalError::new(format!("Invalid Content-Security-Policy: {err}"))
+                })?,
+            ));
+        }
+
+        Ok(Self { headers })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    pub fn apply(&self, headers: &mut http::HeaderMap) {
+        for (name, value) in &self.headers {
+            if !headers.contains_key(name) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+pub async fn security_headers_middleware(
+    axum::extract::State(security_headers): axum::extract::State<Arc<SecurityHeaders>>,
+    mut response: axum::response::Response,
+) -> axum::response::Response {
+    security_headers.apply(response.headers_mut());
+    response
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
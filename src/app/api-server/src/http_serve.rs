@@ -0,0 +1,250 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::extract::ConnectInfo;
+use futures::Future;
+use http_body_util::BodyExt as _;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::listener::{ListenerAddr, ServerListener};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy)]
+pub struct HttpServeOptions {
+    /// Maximum time for a client to send the request headers
+    pub header_read_timeout: Duration,
+    /// Time after which connections without any traffic are closed. Doesn't
+    /// apply while requests of the connection are being processed.
+    pub idle_timeout: Option<Duration>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serves the router on the listener similarly to `axum::serve`, but allows to
+/// configure connection-level timeouts
+pub struct HttpServer {
+    listener: ServerListener,
+    router: axum::Router,
+    options: HttpServeOptions,
+}
+
+impl HttpServer {
+    pub fn new(listener: ServerListener, router: axum::Router, options: HttpServeOptions) -> Self {
+        Self {
+            listener,
+            router,
+            options,
+        }
+    }
+
+    pub fn local_addr(&self) -> &ListenerAddr {
+        self.listener.local_addr()
+    }
+
+    /// Serves connections until the `signal` completes and then waits for the
+    /// open connections to finish their in-flight requests
+    pub async fn with_graceful_shutdown<F>(self, signal: F) -> std::io::Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let Self {
+            mut listener,
+            router,
+            options,
+        } = self;
+
+        let mut builder = auto::Builder::new(TokioExecutor::new());
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .header_read_timeout(options.header_read_timeout);
+
+        let graceful = GracefulShutdown::new();
+        let mut signal = std::pin::pin!(signal);
+
+        loop {
+            let (stream, _) = tokio::select! {
+                res = axum::serve::Listener::accept(&mut listener) => res,
+                () = &mut signal => break,
+            };
+
+            let connect_info = stream.to_connect_info();
+            let in_flight = InFlightRequests::default();
+            let service = tower::service_fn({
+                let router = router.clone();
+                let in_flight = in_flight.clone();
+                move |mut request: http::Request<hyper::body::Incoming>| {
+                    let guard = in_flight.start();
+                    request
+                        .extensions_mut()
+                        .insert(ConnectInfo(connect_info.clone()));
+                    let response = tower::ServiceExt::oneshot(router.clone(), request);
+
+                    async move {
+                        let response = response.await?;
+                        // Request is in progress until the response body is sent
+                        Ok::<_, Infallible>(response.map(|body| {
+                            axum::body::Body::new(body.map_frame(move |frame| {
+                                let _guard = &guard;
+                                frame
+                            }))
+                        }))
+                    }
+                }
+            });
+
+            let io = TokioIo::new(IdleTimeoutIo::new(stream, options.idle_timeout, in_flight));
+            let conn = builder
+                .serve_connection_with_upgrades(
+                    io,
+                    hyper_util::service::TowerToHyperService::new(service),
+                )
+                .into_owned();
+            let conn = graceful.watch(conn);
+
+            tokio::spawn(async move {
+                if let Err(err) = conn.await {
+                    tracing::debug!(error = %err, "HTTP connection closed with error");
+                }
+            });
+        }
+
+        // Stops the accept loop and closes the connections that were accepted but
+        // not picked up yet
+        drop(listener);
+        graceful.shutdown().await;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Number of requests of a connection that are being processed, including
+/// sending of their responses
+#[derive(Debug, Clone, Default)]
+struct InFlightRequests(Arc<AtomicUsize>);
+
+impl InFlightRequests {
+    fn start(&self) -> InFlightRequestGuard {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        InFlightRequestGuard(self.0.clone())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.load(Ordering::Relaxed) == 0
+    }
+}
+
+struct InFlightRequestGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightRequestGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Fails reads and writes of a connection that had no traffic in either
+/// direction and no requests in progress for the specified time, so that slow
+/// handlers don't get their connections closed
+struct IdleTimeoutIo<T> {
+    inner: T,
+    idle: Option<(Duration, Pin<Box<tokio::time::Sleep>>)>,
+    in_flight: InFlightRequests,
+}
+
+impl<T> IdleTimeoutIo<T> {
+    fn new(inner: T, timeout: Option<Duration>, in_flight: InFlightRequests) -> Self {
+        Self {
+            inner,
+            idle: timeout.map(|timeout| (timeout, Box::pin(tokio::time::sleep(timeout)))),
+            in_flight,
+        }
+    }
+
+    fn touch(&mut self) {
+        if let Some((timeout, sleep)) = &mut self.idle {
+            sleep.as_mut().reset(tokio::time::Instant::now() + *timeout);
+        }
+    }
+
+    fn poll_idle<R>(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<R>> {
+        let Some((timeout, sleep)) = &mut self.idle else {
+            return Poll::Pending;
+        };
+
+        while sleep.as_mut().poll(cx).is_ready() {
+            if self.in_flight.is_empty() {
+                return Poll::Ready(Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "Connection was idle for too long",
+                )));
+            }
+            // Check again later, as the guards don't wake up the connection
+            sleep.as_mut().reset(tokio::time::Instant::now() + *timeout);
+        }
+        Poll::Pending
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for IdleTimeoutIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.inner).poll_read(cx, buf) {
+            Poll::Pending => this.poll_idle(cx),
+            ready => {
+                this.touch();
+                ready
+            }
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for IdleTimeoutIo<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.inner).poll_write(cx, buf) {
+            Poll::Pending => this.poll_idle(cx),
+            ready => {
+                this.touch();
+                ready
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
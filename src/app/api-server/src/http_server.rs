@@ -13,43 +13,84 @@ use std::sync::Arc;
 
 use axum::Extension;
 use database_common_macros::transactional_handler;
-use internal_error::{InternalError, ResultIntoInternal};
+use internal_error::InternalError;
 use kamu::domain::TenancyConfig;
 use kamu_adapter_graphql::data_loader::{account_entity_data_loader, dataset_handle_data_loader};
 use kamu_adapter_http::DatasetAuthorizationLayer;
 use observability::axum::{panic_handler, unknown_fallback_handler};
-use tokio::net::TcpListener;
 use tokio::sync::Notify;
+use tokio_rustls::TlsAcceptor;
+use tower::Layer as _;
 use tower_http::catch_panic::CatchPanicLayer;
 use utoipa_axum::router::OpenApiRouter;
 
+use crate::admin_server::system_router;
+use crate::client_cert_auth::ClientCertAuthenticationLayer;
+use crate::client_ip::{TrustedProxies, client_ip_middleware};
+use crate::config::{HttpConfig, ListenerConfig, WebUiConfig};
+use crate::http_caching::immutable_object_middleware;
+use crate::http_compression::build_compression_layer;
+use crate::http_security::{SecurityHeaders, build_cors_layer, security_headers_middleware};
+use crate::http_serve::{HttpServeOptions, HttpServer};
+use crate::ip_filter::{ACCESS_DENIED_MESSAGE, IpFilter, WritesDenied, ip_filter_middleware};
+use crate::listener::{ListenerAddr, ServerListener};
+use crate::maintenance::{MaintenanceMode, maintenance_middleware, maintenance_router};
+use crate::rate_limit::{RateLimiter, rate_limit_middleware};
+use crate::read_only::{
+    READ_ONLY_MESSAGE,
+    is_graphql_mutation,
+    is_graphql_write,
+    read_only_middleware,
+};
+use crate::readiness::{ReadinessAudience, StartupJobsStatus, startup_jobs_middleware};
+use crate::request_id::{RequestId, RequestIdLayer};
+use crate::request_limits::{RouteLimits, request_limits_middleware};
+use crate::tenant_domains::{
+    TenantDomains,
+    tenant_domain_catalog_middleware,
+    tenant_domain_middleware,
+};
 use crate::ui_configuration::UIConfiguration;
+use crate::web_ui::{WebUiBundle, web_ui_handler};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 pub async fn build_server(
     address: std::net::IpAddr,
     http_port: Option<u16>,
+    listener_config: Option<&ListenerConfig>,
     catalog: dill::Catalog,
     tenancy_config: TenancyConfig,
     ui_config: UIConfiguration,
     e2e_http_port: Option<u16>,
     e2e_output_data_path: Option<&PathBuf>,
-) -> Result<
-    (
-        axum::serve::Serve<
-            tokio::net::TcpListener,
-            axum::routing::IntoMakeService<axum::Router>,
-            axum::Router,
-        >,
-        SocketAddr,
-        Option<Arc<Notify>>,
-    ),
-    InternalError,
-> {
+    tls_acceptor: Option<TlsAcceptor>,
+    http_config: &HttpConfig,
+    serve_admin_routes: bool,
+) -> Result<(HttpServer, ListenerAddr, Option<Arc<Notify>>), InternalError> {
     let addr = SocketAddr::from((address, e2e_http_port.or(http_port).unwrap_or(0)));
-    let listener = TcpListener::bind(addr).await.int_err()?;
-    let local_addr = listener.local_addr().unwrap();
+    let listener = ServerListener::bind_configured(addr, listener_config, tls_acceptor).await?;
+    let local_addr = listener.local_addr().clone();
+
+    let cors_layer = build_cors_layer(&http_config.cors)?;
+    let security_headers = Arc::new(SecurityHeaders::from_config(&http_config.security_headers)?);
+    let rate_limiter = catalog.get_one::<RateLimiter>().ok();
+    let maintenance = catalog.get_one::<MaintenanceMode>().ok();
+    let startup_jobs_status = catalog.get_one::<StartupJobsStatus>().ok();
+    // Without the admin listener maintenance mode can be toggled via the HTTP
+    // API, but only with the token
+    let maintenance_toggle = maintenance
+        .clone()
+        .filter(|maintenance| serve_admin_routes && maintenance.has_token());
+    let route_limits = Arc::new(RouteLimits::from_config(&http_config.limits));
+    let trusted_proxies = Arc::new(TrustedProxies::from_config(&http_config.trusted_proxies)?);
+    let ip_filter = Arc::new(IpFilter::from_config(&http_config.ip_filter)?);
+    let tenant_domains = catalog.get_one::<TenantDomains>().ok();
+    let web_ui = catalog
+        .get_one::<WebUiConfig>()
+        .ok()
+        .map(|config| WebUiBundle::load(&config.path).map(Arc::new))
+        .transpose()?;
 
     let graphql_router = OpenApiRouter::new()
         .route("/graphql", axum::routing::post(graphql_handler))
@@ -86,75 +127,110 @@ pub async fn build_server(
         )
         .build(),
     )
-    .merge(graphql_router)
-    .merge(server_console::router("Kamu API Server".to_string(), format!("v{}", crate::app::VERSION)).into())
-    .merge(kamu_adapter_http::data::root_router())
-    .merge(kamu_adapter_http::general::root_router())
-    .nest(
-        "/odata",
-        match tenancy_config {
-            TenancyConfig::MultiTenant => kamu_adapter_odata::router_multi_tenant(),
-            TenancyConfig::SingleTenant => kamu_adapter_odata::router_single_tenant(),
-        },
-    )
-    .nest(
-        match tenancy_config {
-            TenancyConfig::MultiTenant => "/{account_name}/{dataset_name}",
-            TenancyConfig::SingleTenant => "/{dataset_name}",
-        },
-        kamu_adapter_http::add_dataset_resolver_layer(
-            OpenApiRouter::new()
-                .merge(kamu_adapter_http::smart_transfer_protocol_router())
-                .merge(kamu_adapter_http::data::dataset_router())
-                .layer(DatasetAuthorizationLayer::default()),
-            tenancy_config,
-        ),
-    )
-    .route(
-        "/system/probe",
-        axum::routing::get(kamu_adapter_http::system::probe_handler),
-    );
+    .merge(graphql_router);
+
+    // The web UI takes over the root path from the console
+    if web_ui.is_none() {
+        open_api_router = open_api_router.merge(
+            server_console::router(
+                "Kamu API Server".to_string(),
+                format!("v{}", crate::app::VERSION),
+            )
+            .into(),
+        );
+    }
+
+    let mut open_api_router = open_api_router
+        .merge(kamu_adapter_http::data::root_router())
+        .merge(kamu_adapter_http::general::root_router())
+        .nest(
+            "/odata",
+            match tenancy_config {
+                TenancyConfig::MultiTenant => kamu_adapter_odata::router_multi_tenant(),
+                TenancyConfig::SingleTenant => kamu_adapter_odata::router_single_tenant(),
+            },
+        )
+        .nest(
+            match tenancy_config {
+                TenancyConfig::MultiTenant => "/{account_name}/{dataset_name}",
+                TenancyConfig::SingleTenant => "/{dataset_name}",
+            },
+            kamu_adapter_http::add_dataset_resolver_layer(
+                OpenApiRouter::new()
+                    .merge(kamu_adapter_http::smart_transfer_protocol_router())
+                    .merge(kamu_adapter_http::data::dataset_router())
+                    .layer(axum::middleware::from_fn(immutable_object_middleware))
+                    .layer(DatasetAuthorizationLayer::default()),
+                tenancy_config,
+            ),
+        )
+        .route(
+            "/system/probe",
+            axum::routing::get(kamu_adapter_http::system::probe_handler),
+        );
 
     if !ui_config.feature_flags.allow_anonymous {
         open_api_router = open_api_router.layer(kamu_adapter_http::AuthPolicyLayer::new());
     }
 
-    let (mut router, api) = open_api_router
+    let mut open_api_router = open_api_router
         .nest(
             "/platform",
             kamu_adapter_http::platform::root_router(ui_config.feature_flags.allow_anonymous),
         )
         .route("/ui-config", axum::routing::get(ui_configuration_handler))
+        .layer(axum::middleware::from_fn_with_state(
+            rate_limiter,
+            rate_limit_middleware,
+        ))
+        .layer(ClientCertAuthenticationLayer::new())
         .layer(kamu_adapter_http::AuthenticationLayer::new())
-        .layer(
-            tower_http::cors::CorsLayer::new()
-                .allow_origin(tower_http::cors::Any)
-                .allow_methods(vec![http::Method::GET, http::Method::POST])
-                .allow_headers(tower_http::cors::Any),
-        )
+        .layer(axum::middleware::from_fn_with_state(
+            route_limits,
+            request_limits_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            maintenance,
+            maintenance_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            ui_config.read_only,
+            read_only_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            startup_jobs_status,
+            startup_jobs_middleware,
+        ))
+        .layer(cors_layer)
         .layer(observability::axum::http_layer())
-        .layer(CatchPanicLayer::custom(panic_handler))
-        // Note: Healthcheck, metrics, and OpenAPI routes are placed before the tracing layer
-        // (layers execute bottom-up) to avoid spam in logs
-        .route(
-            "/system/health",
-            axum::routing::get(observability::health::health_handler),
-        )
-        .route(
-            "/system/metrics",
-            axum::routing::get(observability::metrics::metrics_handler),
-        )
-        .route(
-            "/system/info",
-            axum::routing::get(observability::build_info::build_info_handler),
-        )
-        .merge(kamu_adapter_http::openapi::router().into())
-        .fallback(unknown_fallback_handler)
+        .layer(CatchPanicLayer::custom(panic_handler));
+
+    // Note: Healthcheck, metrics, and OpenAPI routes are placed before the tracing
+    // layer (layers execute bottom-up) to avoid spam in logs
+    if serve_admin_routes {
+        open_api_router =
+            open_api_router.merge(system_router(None, ReadinessAudience::Public).into());
+    }
+    if let Some(maintenance) = maintenance_toggle {
+        open_api_router = open_api_router.merge(maintenance_router(maintenance).into());
+    }
+
+    let open_api_router = open_api_router.merge(kamu_adapter_http::openapi::router().into());
+
+    let open_api_router = match web_ui {
+        Some(web_ui) => open_api_router
+            .fallback(web_ui_handler)
+            .layer(axum::extract::Extension(web_ui)),
+        None => open_api_router.fallback(unknown_fallback_handler),
+    };
+
+    let (mut router, api) = open_api_router
+        .layer(axum::middleware::from_fn(tenant_domain_catalog_middleware))
         .layer(axum::extract::Extension(catalog))
         .layer(axum::extract::Extension(ui_config))
         .split_for_parts();
 
-    let maybe_shutdown_notify = if e2e_output_data_path.is_some() {
+    let maybe_shutdown_notify = if serve_admin_routes && e2e_output_data_path.is_some() {
         let shutdown_notify = Arc::new(Notify::new());
 
         router = router.nest(
@@ -167,9 +243,44 @@ pub async fn build_server(
         None
     };
 
-    let router = router.layer(axum::extract::Extension(std::sync::Arc::new(api)));
+    if http_config.compression.enabled {
+        router = router.layer(build_compression_layer(&http_config.compression));
+    }
+
+    let router = router
+        .layer(axum::extract::Extension(std::sync::Arc::new(api)))
+        .layer(axum::middleware::from_fn_with_state(
+            ip_filter,
+            ip_filter_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            trusted_proxies,
+            client_ip_middleware,
+        ))
+        .layer(axum::middleware::map_response_with_state(
+            security_headers,
+            security_headers_middleware,
+        ))
+        .layer(RequestIdLayer::new());
+
+    // Tenant domains are routed by rewriting the paths, which has to happen
+    // before the routing
+    let router = match tenant_domains {
+        Some(tenant_domains) => axum::Router::new().fallback_service(
+            axum::middleware::from_fn_with_state(tenant_domains, tenant_domain_middleware)
+                .layer(router),
+        ),
+        None => router,
+    };
 
-    let server = axum::serve(listener, router.into_make_service());
+    let server = HttpServer::new(
+        listener,
+        router,
+        HttpServeOptions {
+            header_read_timeout: http_config.limits.header_read_timeout.into(),
+            idle_timeout: http_config.limits.idle_timeout.map(Into::into),
+        },
+    );
     Ok((server, local_addr, maybe_shutdown_notify))
 }
 
@@ -178,8 +289,12 @@ pub async fn build_server(
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 async fn ui_configuration_handler(
-    axum::extract::Extension(ui_config): axum::extract::Extension<UIConfiguration>,
+    axum::extract::Extension(catalog): axum::extract::Extension<dill::Catalog>,
+    axum::extract::Extension(mut ui_config): axum::extract::Extension<UIConfiguration>,
 ) -> axum::Json<UIConfiguration> {
+    if let Ok(maintenance) = catalog.get_one::<MaintenanceMode>() {
+        ui_config.maintenance = maintenance.status();
+    }
     axum::Json(ui_config)
 }
 
@@ -226,18 +341,63 @@ impl axum::response::IntoResponse for GqlResponseError {
 async fn graphql_handler(
     Extension(schema): Extension<kamu_adapter_graphql::Schema>,
     Extension(catalog): Extension<dill::Catalog>,
+    Extension(ui_config): Extension<UIConfiguration>,
+    request_id: Option<Extension<RequestId>>,
+    writes_denied: Option<Extension<WritesDenied>>,
     req: async_graphql_axum::GraphQLRequest,
 ) -> Result<async_graphql_axum::GraphQLResponse, GqlResponseError> {
-    let graphql_request = req
-        .into_inner()
-        .data(account_entity_data_loader(&catalog))
-        .data(dataset_handle_data_loader(&catalog))
-        .data(catalog);
+    let graphql_request = req.into_inner();
+    let is_mutation = is_graphql_mutation(&graphql_request);
+
+    let rejection_message = if ui_config.read_only {
+        Some(READ_ONLY_MESSAGE.to_string())
+    } else if writes_denied.is_some() {
+        Some(ACCESS_DENIED_MESSAGE.to_string())
+    } else {
+        catalog
+            .get_one::<MaintenanceMode>()
+            .ok()
+            .and_then(|maintenance| maintenance.message())
+    };
 
-    tracing::debug!(?graphql_request, "Incoming GraphQL request");
+    let mut response = if let Some(message) = rejection_message
+        && is_graphql_write(&graphql_request)
+    {
+        tracing::debug!(?graphql_request, %message, "GraphQL mutation rejected");
+
+        async_graphql::Response::from_errors(vec![async_graphql::ServerError::new(message, None)])
+    } else {
+        // Flows triggered manually by the mutation are stored with the request ID
+        let catalog = match &request_id {
+            Some(Extension(request_id)) if is_mutation => {
+                dill::CatalogBuilder::new_chained(&catalog)
+                    .add_value(request_id.clone())
+                    .build()
+            }
+            _ => catalog.clone(),
+        };
+
+        let graphql_request = graphql_request
+            .data(account_entity_data_loader(&catalog))
+            .data(dataset_handle_data_loader(&catalog))
+            .data(catalog);
+
+        tracing::debug!(?graphql_request, "Incoming GraphQL request");
+
+        schema.execute(graphql_request).await
+    };
+
+    if let Some(Extension(request_id)) = request_id {
+        // Let users report the identifier of the failed request
+        for error in &mut response.errors {
+            error
+                .extensions
+                .get_or_insert_with(Default::default)
+                .set("requestId", request_id.as_str());
+        }
+    }
 
-    let graphql_response: async_graphql_axum::GraphQLResponse =
-        schema.execute(graphql_request).await.into();
+    let graphql_response: async_graphql_axum::GraphQLResponse = response.into();
 
     // Check if the response contains errors - if so, return Err to trigger
     // transaction rollback while still returning the GraphQL response to the client
@@ -0,0 +1,148 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http::Method;
+use internal_error::*;
+use ipnet::IpNet;
+
+use crate::client_ip::{ClientIp, parse_ip_nets};
+use crate::config::{IpFilterConfig, IpFilterRuleConfig};
+use crate::route_group::{is_write_route, matched_route};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const ACCESS_DENIED_MESSAGE: &str = "Access from this address is not allowed";
+
+/// Marks requests from client IPs that are not allowed to modify the state of
+/// the node, so that GraphQL mutations can be rejected by the handler
+#[derive(Debug, Clone, Copy)]
+pub struct WritesDenied;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Default)]
+struct IpFilterRule {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+impl IpFilterRule {
+    fn from_config(config: &IpFilterRuleConfig) -> Result<Self, InternalError> {
+        Ok(Self {
+            allow: parse_ip_nets(&config.allow)?,
+            deny: parse_ip_nets(&config.deny)?,
+        })
+    }
+
+    fn is_allowed(&self, ip: IpAddr) -> bool {
+        if self.deny.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|net| net.contains(&ip))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter {
+    global: Option<IpFilterRule>,
+    admin: Option<IpFilterRule>,
+    write: Option<IpFilterRule>,
+}
+
+impl IpFilter {
+    pub fn from_config(config: &IpFilterConfig) -> Result<Self, InternalError> {
+        let rule = |config: &Option<IpFilterRuleConfig>| {
+            config.as_ref().map(IpFilterRule::from_config).transpose()
+        };
+
+        Ok(Self {
+            global: rule(&config.global)?,
+            admin: rule(&config.admin)?,
+            write: rule(&config.write)?,
+        })
+    }
+
+    /// Checks the request `path` and the template of the `route` it was
+    /// routed to (see [`matched_route`])
+    pub fn is_allowed(&self, ip: IpAddr, method: &Method, path: &str, route: Option<&str>) -> bool {
+        let rules = [
+            (&self.global, true),
+            (&self.admin, is_admin_route(path)),
+            (&self.write, is_write_route(method, route)),
+        ];
+
+        rules
+            .into_iter()
+            .filter_map(|(rule, applies)| rule.as_ref().filter(|_| applies))
+            .all(|rule| rule.is_allowed(ip))
+    }
+
+    pub fn allows_writes(&self, ip: IpAddr) -> bool {
+        self.write.as_ref().is_none_or(|rule| rule.is_allowed(ip))
+    }
+}
+
+fn is_admin_route(path: &str) -> bool {
+    (path.starts_with("/system/") && path != "/system/health")
+        || path == "/e2e"
+        || path.starts_with("/e2e/")
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Rejects requests from client IPs that are not allowed to access the route
+/// with `403`. GraphQL requests of clients that are not allowed to write are
+/// marked with [`WritesDenied`].
+pub async fn ip_filter_middleware(
+    State(ip_filter): State<Arc<IpFilter>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(ip) = ClientIp::from_extensions(request.extensions()) else {
+        return next.run(request).await;
+    };
+
+    if !ip_filter.is_allowed(
+        ip,
+        request.method(),
+        request.uri().path(),
+        matched_route(&request),
+    ) {
+        tracing::debug!(
+            client_ip = %ip,
+            method = %request.method(),
+            path = request.uri().path(),
+            "Request rejected by IP filter",
+        );
+
+        return (
+            http::StatusCode::FORBIDDEN,
+            axum::Json(serde_json::json!({
+                "message": ACCESS_DENIED_MESSAGE,
+            })),
+        )
+            .into_response();
+    }
+
+    if !ip_filter.allows_writes(ip) {
+        request.extensions_mut().insert(WritesDenied);
+    }
+
+    next.run(request).await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
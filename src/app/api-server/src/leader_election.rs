@@ -0,0 +1,288 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use graceful_shutdown::CancellationToken;
+use internal_error::*;
+use tokio::task::JoinHandle;
+
+use crate::config::LeaderElectionConfig;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Unanswered keepalive probes after which Postgres drops the connection of the
+/// leader
+const KEEPALIVES_COUNT: u64 = 3;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Elects a single replica among the ones sharing the same database to run
+/// the singleton background agents
+#[async_trait::async_trait]
+pub trait LeaderElection: Send + Sync {
+    /// Waits until this replica becomes the leader
+    async fn acquire_leadership(&self) -> Result<LeadershipLease, InternalError>;
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Leadership held by this replica. It is released when the lease is dropped.
+pub struct LeadershipLease {
+    lost: CancellationToken,
+    keeper: Option<JoinHandle<()>>,
+    metrics: Arc<LeaderElectionMetrics>,
+}
+
+impl LeadershipLease {
+    /// Creates a lease that is considered lost once the `lost` token is
+    /// cancelled. The `keeper` task that watches the leadership is aborted
+    /// when the lease is dropped.
+    pub fn new(
+        lost: CancellationToken,
+        keeper: Option<JoinHandle<()>>,
+        metrics: Arc<LeaderElectionMetrics>,
+    ) -> Self {
+        metrics.is_leader.set(1);
+        metrics.acquired_num.inc();
+
+        Self {
+            lost,
+            keeper,
+            metrics,
+        }
+    }
+
+    /// Completes when the leadership was lost, e.g. due to a broken database
+    /// connection
+    pub async fn lost(&self) {
+        self.lost.cancelled().await;
+    }
+}
+
+impl Drop for LeadershipLease {
+    fn drop(&mut self) {
+        if let Some(keeper) = self.keeper.take() {
+            keeper.abort();
+        }
+        if self.lost.is_cancelled() {
+            self.metrics.lost_num.inc();
+        }
+        self.metrics.is_leader.set(0);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct LeaderElectionMetrics {
+    pub is_leader: prometheus::IntGauge,
+    pub acquired_num: prometheus::IntCounter,
+    pub lost_num: prometheus::IntCounter,
+}
+
+impl LeaderElectionMetrics {
+    pub fn new() -> Self {
+        use prometheus::*;
+
+        Self {
+            is_leader: IntGauge::with_opts(Opts::new(
+                "leader_election_is_leader",
+                "Whether this replica is the leader that runs the singleton background agents",
+            ))
+            .unwrap(),
+            acquired_num: IntCounter::with_opts(Opts::new(
+                "leader_election_acquired_total",
+                "Times this replica became the leader",
+            ))
+            .unwrap(),
+            lost_num: IntCounter::with_opts(Opts::new(
+                "leader_election_lost_total",
+                "Times this replica lost the leadership unexpectedly",
+            ))
+            .unwrap(),
+        }
+    }
+}
+
+impl Default for LeaderElectionMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl observability::metrics::MetricsProvider for LeaderElectionMetrics {
+    fn register(&self, reg: &prometheus::Registry) -> prometheus::Result<()> {
+        reg.register(Box::new(self.is_leader.clone()))?;
+        reg.register(Box::new(self.acquired_num.clone()))?;
+        reg.register(Box::new(self.lost_num.clone()))?;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Used with SQLite and in-memory databases that can't be shared by multiple
+/// replicas, so the only replica is always the leader
+pub struct NoOpLeaderElection {
+    metrics: Arc<LeaderElectionMetrics>,
+}
+
+#[dill::component(pub)]
+#[dill::interface(dyn LeaderElection)]
+impl NoOpLeaderElection {
+    pub fn new(metrics: Arc<LeaderElectionMetrics>) -> Self {
+        Self { metrics }
+    }
+}
+
+#[async_trait::async_trait]
+impl LeaderElection for NoOpLeaderElection {
+    async fn acquire_leadership(&self) -> Result<LeadershipLease, InternalError> {
+        Ok(LeadershipLease::new(
+            CancellationToken::new(),
+            None,
+            self.metrics.clone(),
+        ))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Leadership is represented by a session-level advisory lock held on a
+/// dedicated connection. The lock is released by Postgres as soon as the
+/// connection is closed, so when the leader dies another replica takes over
+/// on its next acquisition attempt.
+pub struct PostgresLeaderElection {
+    catalog: dill::Catalog,
+    config: Arc<LeaderElectionConfig>,
+    metrics: Arc<LeaderElectionMetrics>,
+}
+
+#[dill::component(pub)]
+#[dill::interface(dyn LeaderElection)]
+impl PostgresLeaderElection {
+    pub fn new(
+        catalog: dill::Catalog,
+        config: Arc<LeaderElectionConfig>,
+        metrics: Arc<LeaderElectionMetrics>,
+    ) -> Self {
+        Self {
+            catalog,
+            config,
+            metrics,
+        }
+    }
+
+    async fn try_acquire(
+        &self,
+        pool: &sqlx::PgPool,
+    ) -> Result<Option<sqlx::PgConnection>, InternalError> {
+        let mut conn = pool.acquire().await.int_err()?;
+
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(self.config.lock_key)
+            .fetch_one(&mut *conn)
+            .await
+            .int_err()?;
+
+        if !acquired {
+            return Ok(None);
+        }
+
+        // The connection holding the lock must not be returned to the pool, it's
+        // closed on errors below, which releases the lock
+        let mut conn = conn.detach();
+
+        // Postgres detects a leader that went away without closing the connection,
+        // e.g. due to a network partition, only via TCP keepalives, and releases
+        // the lock once they fail
+        let check_interval: Duration = self.config.check_interval.into();
+        let keepalive_secs = check_interval.as_secs().max(1);
+        for (name, value) in [
+            ("tcp_keepalives_idle", keepalive_secs),
+            ("tcp_keepalives_interval", keepalive_secs),
+            ("tcp_keepalives_count", KEEPALIVES_COUNT),
+        ] {
+            sqlx::query("SELECT set_config($1, $2, false)")
+                .bind(name)
+                .bind(value.to_string())
+                .execute(&mut conn)
+                .await
+                .int_err()?;
+        }
+
+        Ok(Some(conn))
+    }
+}
+
+#[async_trait::async_trait]
+impl LeaderElection for PostgresLeaderElection {
+    async fn acquire_leadership(&self) -> Result<LeadershipLease, InternalError> {
+        let pool = self.catalog.get_one::<sqlx::PgPool>().int_err()?;
+        let check_interval: Duration = self.config.check_interval.into();
+
+        let mut conn = loop {
+            match self.try_acquire(&pool).await {
+                Ok(Some(conn)) => break conn,
+                Ok(None) => {}
+                Err(err) => {
+                    tracing::warn!(error = ?err, error_msg = %err, "Failed to acquire leadership");
+                }
+            }
+            tokio::time::sleep(check_interval).await;
+        };
+
+        tracing::info!(lock_key = self.config.lock_key, "Acquired leadership");
+
+        let lost = CancellationToken::new();
+        let keeper = tokio::spawn({
+            let lost = lost.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(check_interval).await;
+
+                    // A check that hangs, e.g. due to a network partition, can't confirm that
+                    // the lock is still held, and another replica may take over once Postgres
+                    // notices the broken connection
+                    let check = sqlx::query("SELECT 1").execute(&mut conn);
+                    match tokio::time::timeout(check_interval, check).await {
+                        Ok(Ok(_)) => {}
+                        Ok(Err(err)) => {
+                            tracing::error!(
+                                error = ?err,
+                                error_msg = %err,
+                                "Lost connection holding the leadership lock",
+                            );
+                            lost.cancel();
+                            return;
+                        }
+                        Err(_) => {
+                            tracing::error!(
+                                timeout = ?check_interval,
+                                "Connection holding the leadership lock is not responding, \
+                                 giving up the leadership",
+                            );
+                            lost.cancel();
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(LeadershipLease::new(
+            lost,
+            Some(keeper),
+            self.metrics.clone(),
+        ))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
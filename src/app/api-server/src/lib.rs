@@ -7,17 +7,43 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+pub mod admin_server;
+pub mod agent_supervisor;
 pub mod app;
 pub mod cli;
+pub mod client_cert_auth;
+pub mod client_ip;
 pub mod commands;
 pub mod config;
 pub(crate) mod database;
 mod emails;
 pub(crate) mod flightsql_server;
+pub mod flow_request_id;
 pub(crate) mod gql_server;
+pub mod http_caching;
+pub mod http_compression;
+pub mod http_security;
+pub mod http_serve;
 pub mod http_server;
+pub mod ip_filter;
+pub mod leader_election;
+pub mod listener;
+pub mod maintenance;
+mod oracle;
+pub mod rate_limit;
+pub mod read_only;
+pub mod readiness;
+pub mod request_id;
+pub mod request_limits;
+pub mod route_group;
+#[cfg(unix)]
+pub mod socket_activation;
+pub mod tenant_domains;
+pub mod tls;
 pub mod ui_configuration;
+pub mod web_ui;
 
 pub use app::*;
 pub(crate) use database::*;
 pub use emails::*;
+pub use oracle::*;
@@ -0,0 +1,539 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use internal_error::*;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::ListenerConfig;
+use crate::tls::ClientCertIdentity;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Maximum time a client has to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of accepted connections waiting to be picked up by the server
+const ACCEPT_BACKLOG: usize = 128;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Address the listener is bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenerAddr {
+    Tcp(SocketAddr),
+    /// Path of the socket, which is unknown for the unnamed ones
+    Unix(Option<PathBuf>),
+}
+
+impl std::fmt::Display for ListenerAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{addr}"),
+            Self::Unix(Some(path)) => write!(f, "unix:{}", path.display()),
+            Self::Unix(None) => write!(f, "unix:<unnamed>"),
+        }
+    }
+}
+
+/// Address of the peer of an accepted connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerAddr {
+    Tcp(SocketAddr),
+    /// Local process connected over a Unix domain socket, which has no IP
+    /// address
+    Unix,
+}
+
+impl PeerAddr {
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self {
+            Self::Tcp(addr) => Some(addr.ip()),
+            Self::Unix => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PeerAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{addr}"),
+            Self::Unix => write!(f, "unix"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Socket accepting the connections of a listener
+enum AcceptSocket {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl AcceptSocket {
+    /// Binds the configured socket or a TCP one on `addr` by default, returning
+    /// the socket file to remove on shutdown for the Unix domain sockets
+    async fn bind(
+        addr: SocketAddr,
+        config: Option<&ListenerConfig>,
+    ) -> Result<(Self, Option<SocketFile>), InternalError> {
+        match config {
+            None => Ok((Self::Tcp(TcpListener::bind(addr).await.int_err()?), None)),
+            #[cfg(unix)]
+            Some(ListenerConfig::Unix(config)) => {
+                let mode = config
+                    .mode
+                    .as_deref()
+                    .map(|mode| {
+                        u32::from_str_radix(mode, 8).map_err(|err| {
+                            InternalError::new(format!("Invalid socket mode '{mode}': {err}"))
+                        })
+                    })
+                    .transpose()?;
+
+                let (listener, socket_file) = bind_unix_socket(&config.path, mode)?;
+                listener.set_nonblocking(true).int_err()?;
+                Ok((
+                    Self::Unix(tokio::net::UnixListener::from_std(listener).int_err()?),
+                    Some(socket_file),
+                ))
+            }
+            #[cfg(unix)]
+            Some(ListenerConfig::Systemd(config)) => {
+                let fd = crate::socket_activation::take_listen_fd(config.name.as_deref())?;
+                Ok((Self::from_fd(fd)?, None))
+            }
+            #[cfg(not(unix))]
+            Some(_) => InternalError::bail(
+                "Unix domain sockets and socket activation are not supported on this platform"
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Adopts a listener socket of either TCP or Unix family
+    #[cfg(unix)]
+    fn from_fd(fd: std::os::fd::OwnedFd) -> Result<Self, InternalError> {
+        use std::os::fd::AsFd as _;
+
+        crate::socket_activation::ensure_listening_stream(fd.as_fd())?;
+
+        let unix_listener = std::os::unix::net::UnixListener::from(fd);
+
+        // Address of a socket of another family fails to convert
+        if unix_listener.local_addr().is_ok() {
+            unix_listener.set_nonblocking(true).int_err()?;
+            return Ok(Self::Unix(
+                tokio::net::UnixListener::from_std(unix_listener).int_err()?,
+            ));
+        }
+
+        let tcp_listener = std::net::TcpListener::from(std::os::fd::OwnedFd::from(unix_listener));
+        tcp_listener.set_nonblocking(true).int_err()?;
+        Ok(Self::Tcp(TcpListener::from_std(tcp_listener).int_err()?))
+    }
+
+    fn local_addr(&self) -> Result<ListenerAddr, InternalError> {
+        match self {
+            Self::Tcp(listener) => Ok(ListenerAddr::Tcp(listener.local_addr().int_err()?)),
+            #[cfg(unix)]
+            Self::Unix(listener) => Ok(ListenerAddr::Unix(
+                listener
+                    .local_addr()
+                    .int_err()?
+                    .as_pathname()
+                    .map(ToOwned::to_owned),
+            )),
+        }
+    }
+
+    async fn accept(&self) -> std::io::Result<(Box<dyn ServerIo>, PeerAddr)> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, remote_addr) = listener.accept().await?;
+                Ok((Box::new(stream), PeerAddr::Tcp(remote_addr)))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Box::new(stream), PeerAddr::Unix))
+            }
+        }
+    }
+}
+
+/// Binds the socket in a private directory next to `path` and moves it into
+/// place once its permissions are set, so that it's never accessible with the
+/// ones determined by the umask
+#[cfg(unix)]
+fn bind_unix_socket(
+    path: &std::path::Path,
+    mode: Option<u32>,
+) -> Result<(std::os::unix::net::UnixListener, SocketFile), InternalError> {
+    use std::os::unix::fs::{
+        DirBuilderExt as _,
+        FileTypeExt as _,
+        MetadataExt as _,
+        PermissionsExt as _,
+    };
+
+    // Socket of the previous run that wasn't cleaned up prevents binding, while
+    // the one still accepting connections belongs to another running instance
+    if let Ok(metadata) = std::fs::symlink_metadata(path)
+        && metadata.file_type().is_socket()
+    {
+        match std::os::unix::net::UnixStream::connect(path) {
+            Ok(_) => {
+                return InternalError::bail(format!(
+                    "Socket '{}' is in use by another process",
+                    path.display()
+                ));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(path).int_err()?;
+            }
+            // Binding reports the problem
+            Err(_) => {}
+        }
+    }
+
+    let Some(file_name) = path.file_name() else {
+        return InternalError::bail(format!("Invalid socket path '{}'", path.display()));
+    };
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(std::path::Path::new("."));
+
+    let private_dir = parent.join(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    // Left by the previous run with the same PID, e.g. in a container
+    let _ = std::fs::remove_dir_all(&private_dir);
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private_dir)
+        .int_err()?;
+
+    let private_path = private_dir.join(file_name);
+    let res = std::os::unix::net::UnixListener::bind(&private_path)
+        .and_then(|listener| {
+            if let Some(mode) = mode {
+                std::fs::set_permissions(&private_path, std::fs::Permissions::from_mode(mode))?;
+            }
+            std::fs::rename(&private_path, path)?;
+            Ok(listener)
+        })
+        .and_then(|listener| {
+            let ino = std::fs::symlink_metadata(path)?.ino();
+            Ok((
+                listener,
+                SocketFile {
+                    path: path.to_path_buf(),
+                    ino,
+                },
+            ))
+        });
+
+    let _ = std::fs::remove_dir_all(&private_dir);
+    res.int_err()
+}
+
+/// Socket file created by the listener, which is removed when the listener is
+/// dropped unless it was replaced by another process in the meantime
+#[cfg_attr(not(unix), expect(dead_code))]
+struct SocketFile {
+    path: PathBuf,
+    ino: u64,
+}
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt as _;
+
+            if std::fs::symlink_metadata(&self.path).is_ok_and(|m| m.ino() == self.ino)
+                && let Err(err) = std::fs::remove_file(&self.path)
+            {
+                tracing::warn!(
+                    path = %self.path.display(),
+                    error = %err,
+                    "Failed to remove socket file",
+                );
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Listener shared by the HTTP and FlightSQL servers that accepts either plain
+/// or TLS connections over TCP or Unix domain sockets.
+///
+/// TLS handshakes are performed in separate tasks so that slow clients don't
+/// block accepting other connections.
+pub struct ServerListener {
+    local_addr: ListenerAddr,
+    incoming: mpsc::Receiver<ServerStream>,
+    socket_file: Option<SocketFile>,
+}
+
+impl ServerListener {
+    pub async fn bind(addr: SocketAddr, tls: Option<TlsAcceptor>) -> Result<Self, InternalError> {
+        Self::bind_configured(addr, None, tls).await
+    }
+
+    /// Binds the configured listener, or a TCP one on `addr` when it's not
+    /// specified
+    pub async fn bind_configured(
+        addr: SocketAddr,
+        config: Option<&ListenerConfig>,
+        tls: Option<TlsAcceptor>,
+    ) -> Result<Self, InternalError> {
+        let (socket, socket_file) = AcceptSocket::bind(addr, config).await?;
+        let local_addr = match &socket_file {
+            // Socket is bound under a temporary path
+            Some(socket_file) => ListenerAddr::Unix(Some(socket_file.path.clone())),
+            None => socket.local_addr()?,
+        };
+
+        let (tx, rx) = mpsc::channel(ACCEPT_BACKLOG);
+        tokio::spawn(accept_loop(socket, tls, tx));
+
+        Ok(Self {
+            local_addr,
+            incoming: rx,
+            socket_file,
+        })
+    }
+
+    pub fn local_addr(&self) -> &ListenerAddr {
+        &self.local_addr
+    }
+
+    /// Adapts listener to the form expected by `tonic`. The stream ends once
+    /// the `signal` completes, which stops accepting connections and drops the
+    /// ones that were accepted but not picked up by the server yet.
+    pub fn into_incoming<F>(
+        self,
+        signal: F,
+    ) -> impl futures::Stream<Item = Result<ServerStream, std::io::Error>> + Send + 'static
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        futures::stream::unfold(
+            (self, Box::pin(signal)),
+            |(mut listener, mut signal)| async move {
+                let stream = tokio::select! {
+                    // Dropping the listener stops the accept loop and removes the socket file
+                    () = &mut signal => return None,
+                    stream = listener.incoming.recv() => stream?,
+                };
+                Some((Ok(stream), (listener, signal)))
+            },
+        )
+    }
+}
+
+impl axum::serve::Listener for ServerListener {
+    type Io = ServerStream;
+    type Addr = PeerAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(stream) => {
+                let remote_addr = stream.remote_addr;
+                (stream, remote_addr)
+            }
+            // Accept loop never stops while the receiver is alive
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        match &self.local_addr {
+            ListenerAddr::Tcp(addr) => Ok(PeerAddr::Tcp(*addr)),
+            ListenerAddr::Unix(_) => Ok(PeerAddr::Unix),
+        }
+    }
+}
+
+async fn accept_loop(
+    socket: AcceptSocket,
+    tls: Option<TlsAcceptor>,
+    tx: mpsc::Sender<ServerStream>,
+) {
+    loop {
+        let (io, remote_addr) = tokio::select! {
+            res = socket.accept() => match res {
+                Ok(v) => v,
+                Err(err) => {
+                    // Errors like running out of file descriptors are transient
+                    tracing::warn!(error = %err, "Failed to accept connection");
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    continue;
+                }
+            },
+            () = tx.closed() => break,
+        };
+
+        let Some(tls) = &tls else {
+            let stream = ServerStream::new(io, remote_addr, None);
+            if tx.send(stream).await.is_err() {
+                break;
+            }
+            continue;
+        };
+
+        let tls = tls.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, tls.accept(io)).await {
+                Ok(Ok(tls_stream)) => {
+                    let client_cert =
+                        ClientCertIdentity::from_connection(tls_stream.get_ref().1).map(Arc::new);
+                    let _ = tx
+                        .send(ServerStream::new(
+                            Box::new(tls_stream),
+                            remote_addr,
+                            client_cert,
+                        ))
+                        .await;
+                }
+                Ok(Err(err)) => {
+                    tracing::debug!(%remote_addr, error = %err, "TLS handshake failed");
+                }
+                Err(_) => {
+                    tracing::debug!(%remote_addr, "TLS handshake timed out");
+                }
+            }
+        });
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub trait ServerIo: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T> ServerIo for T where T: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+/// Accepted connection with transport details erased
+pub struct ServerStream {
+    io: Box<dyn ServerIo>,
+    remote_addr: PeerAddr,
+    client_cert: Option<Arc<ClientCertIdentity>>,
+}
+
+impl ServerStream {
+    fn new(
+        io: Box<dyn ServerIo>,
+        remote_addr: PeerAddr,
+        client_cert: Option<Arc<ClientCertIdentity>>,
+    ) -> Self {
+        Self {
+            io,
+            remote_addr,
+            client_cert,
+        }
+    }
+
+    pub fn remote_addr(&self) -> PeerAddr {
+        self.remote_addr
+    }
+
+    pub fn to_connect_info(&self) -> ServerConnectInfo {
+        ServerConnectInfo {
+            remote_addr: self.remote_addr,
+            client_cert: self.client_cert.clone(),
+        }
+    }
+}
+
+impl AsyncRead for ServerStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ServerStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+impl tonic::transport::server::Connected for ServerStream {
+    type ConnectInfo = ServerConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.to_connect_info()
+    }
+}
+
+/// Details of the connection available to request handlers and middlewares
+#[derive(Debug, Clone)]
+pub struct ServerConnectInfo {
+    pub remote_addr: PeerAddr,
+    /// Identity of the client verified via mutual TLS
+    pub client_cert: Option<Arc<ClientCertIdentity>>,
+}
+
+impl ServerConnectInfo {
+    /// Looks up connection details in the request extensions populated by
+    /// either `axum` or `tonic`
+    pub fn from_extensions(extensions: &http::Extensions) -> Option<&Self> {
+        extensions
+            .get::<axum::extract::ConnectInfo<Self>>()
+            .map(|info| &info.0)
+            .or_else(|| extensions.get::<Self>())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -21,6 +21,11 @@ fn main() {
         .install_default()
         .expect("Could not install default TLS provider");
 
+    // SAFETY: No other threads are spawned yet
+    #[cfg(unix)]
+    unsafe { kamu_api_server::socket_activation::receive_listen_fds() }
+        .expect("Could not receive sockets passed by systemd");
+
     let args = Cli::parse();
 
     // Using unwrap as we don't have tracing initialized yet
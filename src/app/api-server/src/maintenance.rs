@@ -0,0 +1,193 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use time_source::SystemTimeSource;
+use tokio::sync::watch;
+
+use crate::admin_server::bearer_token_middleware;
+use crate::config::MaintenanceConfig;
+use crate::read_only::is_allowed_in_read_only;
+use crate::route_group::matched_route;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Maintenance mode toggled by the admin at runtime. While it's enabled, writes
+/// are rejected and background agents are stopped, while queries keep working.
+///
+/// The state is kept in memory of the node, so it has to be toggled on every
+/// replica and is reset on restart. The leader keeps the leadership while its
+/// agents are paused, so they are not taken over by other replicas.
+pub struct MaintenanceMode {
+    config: Arc<MaintenanceConfig>,
+    time_source: Arc<dyn SystemTimeSource>,
+    status: watch::Sender<MaintenanceStatus>,
+}
+
+#[dill::component(pub)]
+#[dill::scope(dill::Singleton)]
+impl MaintenanceMode {
+    pub fn new(config: Arc<MaintenanceConfig>, time_source: Arc<dyn SystemTimeSource>) -> Self {
+        Self {
+            config,
+            time_source,
+            status: watch::Sender::new(MaintenanceStatus::default()),
+        }
+    }
+
+    /// Enables maintenance mode with the specified message or the configured
+    /// one. Enabling it again only updates the message.
+    pub fn enable(&self, message: Option<String>) -> MaintenanceStatus {
+        let message = message.unwrap_or_else(|| self.config.message.clone());
+
+        self.status.send_modify(|status| {
+            status.message = Some(message);
+            if !status.enabled {
+                status.enabled = true;
+                status.since = Some(self.time_source.now());
+            }
+        });
+
+        let status = self.status();
+        tracing::warn!(?status, "Maintenance mode enabled");
+        status
+    }
+
+    pub fn disable(&self) -> MaintenanceStatus {
+        self.status.send_replace(MaintenanceStatus::default());
+
+        tracing::warn!("Maintenance mode disabled");
+        self.status()
+    }
+
+    /// Whether toggling requires authentication
+    pub fn has_token(&self) -> bool {
+        self.config.token.is_some()
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.borrow().clone()
+    }
+
+    /// Returns the message for the clients while maintenance mode is enabled
+    pub fn message(&self) -> Option<String> {
+        let status = self.status.borrow();
+        status.enabled.then(|| status.message.clone()).flatten()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.status.borrow().enabled
+    }
+
+    /// Completes when maintenance mode gets into the specified state
+    pub async fn wait_until(&self, enabled: bool) {
+        let mut receiver = self.status.subscribe();
+        // Can't fail as the sender is owned by `self`
+        let _ = receiver.wait_for(|status| status.enabled == enabled).await;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Rejects requests to the write routes with `503` while maintenance mode is
+/// enabled. GraphQL mutations are rejected by the handler.
+pub async fn maintenance_middleware(
+    State(maintenance): State<Option<Arc<MaintenanceMode>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(message) = maintenance.as_ref().and_then(|m| m.message())
+        && !is_allowed_in_read_only(request.method(), matched_route(&request))
+    {
+        tracing::debug!(
+            method = %request.method(),
+            path = request.uri().path(),
+            "Request rejected in maintenance mode",
+        );
+
+        return (
+            http::StatusCode::SERVICE_UNAVAILABLE,
+            axum::Json(serde_json::json!({
+                "message": message,
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnableMaintenanceRequest {
+    /// Message for the clients, falls back to the configured one
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Routes to inspect and toggle maintenance mode. Toggling requires the
+/// bearer token when it is specified in the config.
+pub fn maintenance_router(maintenance: Arc<MaintenanceMode>) -> axum::Router {
+    let mut toggle =
+        axum::routing::put(maintenance_enable_handler).delete(maintenance_disable_handler);
+
+    if let Some(token) = &maintenance.config.token {
+        toggle = toggle.route_layer(axum::middleware::from_fn_with_state(
+            Arc::new(token.clone()),
+            bearer_token_middleware,
+        ));
+    }
+
+    axum::Router::new()
+        .route(
+            "/system/maintenance",
+            axum::routing::get(maintenance_status_handler).merge(toggle),
+        )
+        .with_state(maintenance)
+}
+
+async fn maintenance_status_handler(
+    State(maintenance): State<Arc<MaintenanceMode>>,
+) -> axum::Json<MaintenanceStatus> {
+    axum::Json(maintenance.status())
+}
+
+async fn maintenance_enable_handler(
+    State(maintenance): State<Arc<MaintenanceMode>>,
+    axum::Json(request): axum::Json<EnableMaintenanceRequest>,
+) -> axum::Json<MaintenanceStatus> {
+    axum::Json(maintenance.enable(request.message))
+}
+
+async fn maintenance_disable_handler(
+    State(maintenance): State<Arc<MaintenanceMode>>,
+) -> axum::Json<MaintenanceStatus> {
+    axum::Json(maintenance.disable())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,176 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use database_common::DatabaseTransactionRunner;
+use internal_error::*;
+use kamu::domain::{QueryOptions, QueryOptionsDataset, QueryService};
+use kamu_oracle_provider::api_client::*;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Executes oracle queries directly via [`QueryService`] of the hosting API
+/// server instead of going through its REST API.
+///
+/// Queries are executed on behalf of the account subject present in the
+/// catalog, so the same access rules apply as for the REST clients.
+pub struct OdfApiClientInProcess {
+    catalog: dill::Catalog,
+}
+
+impl OdfApiClientInProcess {
+    pub fn new(catalog: dill::Catalog) -> Self {
+        Self { catalog }
+    }
+
+    async fn query_impl(
+        catalog: dill::Catalog,
+        request: QueryRequest,
+    ) -> Result<QueryResponse, QueryError> {
+        let query_svc = catalog.get_one::<dyn QueryService>().int_err()?;
+
+        let include_input = request.include.contains(&Include::Input);
+        let include_schema = request.include.contains(&Include::Schema);
+
+        let input_datasets = request.datasets.clone().map(|datasets| {
+            datasets
+                .into_iter()
+                .map(|ds| {
+                    (
+                        ds.id,
+                        QueryOptionsDataset {
+                            alias: ds.alias,
+                            block_hash: ds.block_hash,
+                            hints: Default::default(),
+                        },
+                    )
+                })
+                .collect()
+        });
+
+        let res = match query_svc
+            .sql_statement(&request.query, QueryOptions { input_datasets })
+            .await
+        {
+            Ok(res) => res,
+            Err(kamu::domain::QueryError::DatasetNotFound(err)) => {
+                return Err(QueryError::DatasetNotFound(err.to_string()));
+            }
+            Err(kamu::domain::QueryError::Access(err)) => {
+                // Don't disclose the existence of inaccessible datasets
+                return Err(QueryError::DatasetNotFound(err.to_string()));
+            }
+            Err(kamu::domain::QueryError::Internal(err)) => return Err(QueryError::Internal(err)),
+            Err(err) => return Err(QueryError::BadRequest(err.to_string())),
+        };
+
+        let schema = res.df.schema().as_arrow().clone();
+        let record_batches = match res.df.collect().await {
+            Ok(record_batches) => record_batches,
+            Err(err) => return Err(QueryError::BadRequest(err.to_string())),
+        };
+
+        let data = to_json_aoa(&schema, &record_batches)?;
+
+        let schema_json = if include_schema {
+            Some(serde_json::to_value(&schema).int_err()?)
+        } else {
+            None
+        };
+
+        let input = include_input.then(|| {
+            let datasets = res
+                .state
+                .input_datasets
+                .into_iter()
+                .map(|(id, ds)| DatasetState {
+                    id,
+                    alias: ds.alias,
+                    block_hash: Some(ds.block_hash),
+                })
+                .collect();
+
+            QueryRequest {
+                datasets: Some(datasets),
+                ..request
+            }
+        });
+
+        Ok(QueryResponse {
+            input,
+            output: Outputs {
+                data,
+                data_format: DataFormat::JsonAoa,
+                schema_format: schema_json.as_ref().map(|_| SchemaFormat::ArrowJson),
+                schema: schema_json,
+            },
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl OdfApiClient for OdfApiClientInProcess {
+    async fn query(&self, request: QueryRequest) -> Result<QueryResponse, QueryError> {
+        if !matches!(request.data_format, None | Some(DataFormat::JsonAoa)) {
+            return Err(QueryError::BadRequest(format!(
+                "Data format {:?} is not supported by the in-process client",
+                request.data_format
+            )));
+        }
+        if !matches!(request.schema_format, None | Some(SchemaFormat::ArrowJson)) {
+            return Err(QueryError::BadRequest(format!(
+                "Schema format {:?} is not supported by the in-process client",
+                request.schema_format
+            )));
+        }
+
+        DatabaseTransactionRunner::new(self.catalog.clone())
+            .transactional(|transaction_catalog| async move {
+                Self::query_impl(transaction_catalog, request).await
+            })
+            .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Lays out the record batches as an array of rows, each row being an array
+/// of column values in schema order
+fn to_json_aoa(
+    schema: &arrow::datatypes::Schema,
+    record_batches: &[arrow::array::RecordBatch],
+) -> Result<serde_json::Value, InternalError> {
+    let mut writer = arrow::json::ArrayWriter::new(Vec::new());
+    for batch in record_batches {
+        writer.write(batch).int_err()?;
+    }
+    writer.finish().int_err()?;
+
+    let buf = writer.into_inner();
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> = if buf.is_empty() {
+        Vec::new()
+    } else {
+        serde_json::from_slice(&buf).int_err()?
+    };
+
+    let rows = rows
+        .into_iter()
+        .map(|mut row| {
+            schema
+                .fields()
+                .iter()
+                .map(|field| row.remove(field.name()).unwrap_or_default())
+                .collect()
+        })
+        .map(serde_json::Value::Array)
+        .collect();
+
+    Ok(serde_json::Value::Array(rows))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,85 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use graceful_shutdown::CancellationToken;
+use internal_error::*;
+use kamu_accounts::{AnonymousAccountReason, CurrentAccountSubject};
+use kamu_oracle_provider::{OdfOracleProvider, OdfOracleProviderMetrics};
+
+use super::OdfApiClientInProcess;
+use crate::agent_supervisor::GracefulBackgroundAgent;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Hosts the ODF oracle provider inside the API server process.
+///
+/// Queries are executed in-process via [`OdfApiClientInProcess`] under an
+/// anonymous account, i.e. with the same visibility of datasets as a
+/// standalone provider without an access token would have.
+///
+/// On shutdown the provider stops scanning for new requests and drains the
+/// in-flight ones within the `shutdownDrainTimeout`.
+pub struct OracleProviderAgent {
+    catalog: dill::Catalog,
+    config: Arc<kamu_oracle_provider::Config>,
+    metrics: Arc<OdfOracleProviderMetrics>,
+}
+
+#[dill::component(pub)]
+#[dill::interface(dyn GracefulBackgroundAgent)]
+impl OracleProviderAgent {
+    pub fn new(
+        catalog: dill::Catalog,
+        config: Arc<kamu_oracle_provider::Config>,
+        metrics: Arc<OdfOracleProviderMetrics>,
+    ) -> Self {
+        Self {
+            catalog,
+            config,
+            metrics,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl GracefulBackgroundAgent for OracleProviderAgent {
+    fn agent_name(&self) -> &'static str {
+        "dev.kamu.api-server.OracleProviderAgent"
+    }
+
+    async fn run(&self, stop: CancellationToken) -> Result<(), InternalError> {
+        let config = self.config.as_ref().clone();
+
+        let rpc_client = kamu_oracle_provider::app::init_rpc_client(&config).await?;
+
+        let query_catalog = self
+            .catalog
+            .builder_chained()
+            .add_value(CurrentAccountSubject::anonymous(
+                AnonymousAccountReason::NoAuthenticationProvided,
+            ))
+            .build();
+        let api_client = Arc::new(OdfApiClientInProcess::new(query_catalog));
+
+        // Metrics are registered once via DI and shared between the restarts
+        let provider = OdfOracleProvider::new(
+            config,
+            rpc_client,
+            api_client,
+            self.metrics.as_ref().clone(),
+        );
+
+        tracing::info!("Entering embedded oracle provider loop");
+        provider.run(stop).await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,288 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use internal_error::*;
+use kamu_accounts::CurrentAccountSubject;
+
+use crate::client_ip::ClientIp;
+use crate::config::{RateLimitConfig, RateLimitRuleConfig};
+use crate::route_group::RouteGroup;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Number of checks after which buckets that are full again get evicted
+const PRUNE_EVERY_CHECKS: u64 = 10_000;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Identity that the limits are applied to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Account(odf::AccountID),
+    Ip(IpAddr),
+}
+
+impl RateLimitKey {
+    fn from_request(request: &Request) -> Option<Self> {
+        if let Some(catalog) = request.extensions().get::<dill::Catalog>()
+            && let Ok(subject) = catalog.get_one::<CurrentAccountSubject>()
+            && let CurrentAccountSubject::Logged(logged) = subject.as_ref()
+        {
+            return Some(Self::Account(logged.account_id.clone()));
+        }
+
+        ClientIp::from_extensions(request.extensions()).map(Self::Ip)
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Account(_) => "account",
+            Self::Ip(_) => "ip",
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy)]
+struct Rule {
+    capacity: f64,
+    refill_per_sec: f64,
+}
+
+impl Rule {
+    fn from_config(config: &RateLimitRuleConfig) -> Self {
+        let period: Duration = config.period.into();
+        Self {
+            capacity: f64::from(config.burst.unwrap_or(config.requests)),
+            refill_per_sec: f64::from(config.requests) / period.as_secs_f64(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, rule: &Rule, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * rule.refill_per_sec).min(rule.capacity);
+        self.updated_at = now;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub fn validate_config(config: &RateLimitConfig) -> Result<(), InternalError> {
+    for (name, rule) in [
+        ("graphql", &config.graphql),
+        ("query", &config.query),
+        ("ingest", &config.ingest),
+        ("transfer", &config.transfer),
+    ] {
+        let Some(rule) = rule else {
+            continue;
+        };
+        if rule.requests == 0 || rule.burst == Some(0) {
+            return InternalError::bail(format!(
+                "Rate limit for '{name}' must allow at least one request"
+            ));
+        }
+        if Duration::from(rule.period).is_zero() {
+            return InternalError::bail(format!("Rate limit period for '{name}' must be non-zero"));
+        }
+    }
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Token bucket rate limiter tracking a bucket per route group and identity
+pub struct RateLimiter {
+    rules: HashMap<RouteGroup, Rule>,
+    state: Mutex<RateLimiterState>,
+    metrics: Arc<RateLimitMetrics>,
+}
+
+#[derive(Default)]
+struct RateLimiterState {
+    buckets: HashMap<(RouteGroup, RateLimitKey), TokenBucket>,
+    checks: u64,
+}
+
+#[dill::component(pub)]
+#[dill::scope(dill::Singleton)]
+impl RateLimiter {
+    pub fn new(config: Arc<RateLimitConfig>, metrics: Arc<RateLimitMetrics>) -> Self {
+        let rules = [
+            (RouteGroup::GraphQL, &config.graphql),
+            (RouteGroup::Query, &config.query),
+            (RouteGroup::Ingest, &config.ingest),
+            (RouteGroup::Transfer, &config.transfer),
+        ]
+        .into_iter()
+        .filter_map(|(group, rule)| rule.as_ref().map(|rule| (group, Rule::from_config(rule))))
+        .collect();
+
+        Self {
+            rules,
+            state: Mutex::new(RateLimiterState::default()),
+            metrics,
+        }
+    }
+
+    /// Consumes a request from the bucket of the identity. Returns the time
+    /// after which the request can be retried if the limit is exceeded.
+    pub fn check(
+        &self,
+        group: RouteGroup,
+        key: &RateLimitKey,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let Some(rule) = self.rules.get(&group) else {
+            return Ok(());
+        };
+
+        let mut state = self.state.lock().unwrap();
+
+        state.checks += 1;
+        if state.checks.is_multiple_of(PRUNE_EVERY_CHECKS) {
+            self.prune(&mut state, now);
+        }
+
+        let bucket = state
+            .buckets
+            .entry((group, key.clone()))
+            .or_insert_with(|| TokenBucket {
+                tokens: rule.capacity,
+                updated_at: now,
+            });
+        bucket.refill(rule, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        self.metrics
+            .throttled_requests_num
+            .with_label_values(&[group.as_str(), key.kind()])
+            .inc();
+
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / rule.refill_per_sec,
+        ))
+    }
+
+    /// Evicts buckets that are full, as they are equivalent to new ones
+    fn prune(&self, state: &mut RateLimiterState, now: Instant) {
+        state.buckets.retain(|(group, _), bucket| {
+            let rule = &self.rules[group];
+            bucket.refill(rule, now);
+            bucket.tokens < rule.capacity
+        });
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub async fn rate_limit_middleware(
+    State(rate_limiter): State<Option<Arc<RateLimiter>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(rate_limiter) = rate_limiter else {
+        return next.run(request).await;
+    };
+
+    let Some(group) = RouteGroup::of_request(&request) else {
+        return next.run(request).await;
+    };
+
+    let Some(key) = RateLimitKey::from_request(&request) else {
+        return next.run(request).await;
+    };
+
+    match rate_limiter.check(group, &key, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            tracing::debug!(
+                route_group = group.as_str(),
+                ?key,
+                ?retry_after,
+                "Request throttled",
+            );
+
+            let retry_after_secs =
+                retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+
+            (
+                http::StatusCode::TOO_MANY_REQUESTS,
+                [(
+                    http::header::RETRY_AFTER,
+                    retry_after_secs.max(1).to_string(),
+                )],
+                axum::Json(serde_json::json!({
+                    "message": "Too many requests",
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct RateLimitMetrics {
+    pub throttled_requests_num: prometheus::IntCounterVec,
+}
+
+impl RateLimitMetrics {
+    pub fn new() -> Self {
+        use prometheus::*;
+
+        Self {
+            throttled_requests_num: IntCounterVec::new(
+                Opts::new(
+                    "http_requests_throttled_total",
+                    "HTTP requests rejected by the rate limiter",
+                ),
+                &["route_group", "key_type"],
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl Default for RateLimitMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl observability::metrics::MetricsProvider for RateLimitMetrics {
+    fn register(&self, reg: &prometheus::Registry) -> prometheus::Result<()> {
+        reg.register(Box::new(self.throttled_requests_num.clone()))?;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,228 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use async_graphql::parser::types::{
+    DocumentOperations,
+    OperationDefinition,
+    OperationType,
+    Selection,
+    SelectionSet,
+};
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures::Future;
+use http::Method;
+use tower::{Layer, Service};
+
+use crate::maintenance::MaintenanceMode;
+use crate::route_group::{is_write_route, matched_route};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const READ_ONLY_MESSAGE: &str = "Server is running in read-only mode";
+
+/// Routes accepting `POST` requests that don't modify the state of the node
+const NON_MODIFYING_ROUTES: [&str; 2] = ["/platform/login", "/verify"];
+
+/// Fields of the GraphQL `auth` mutation that don't modify the state of the
+/// node, so that users can still log in
+const NON_MODIFYING_AUTH_MUTATIONS: [&str; 1] = ["login"];
+
+/// Flight SQL methods that can modify data, i.e. statement updates and
+/// ingestion
+const FLIGHT_SQL_WRITE_METHODS: [&str; 2] = [
+    "/arrow.flight.protocol.FlightService/DoPut",
+    "/arrow.flight.protocol.FlightService/DoExchange",
+];
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Whether the HTTP route (see [`matched_route`]) remains available in
+/// read-only mode. GraphQL is allowed as its mutations are rejected by the
+/// handler.
+pub fn is_allowed_in_read_only(method: &Method, route: Option<&str>) -> bool {
+    let route = route.map(|route| route.trim_end_matches('/'));
+
+    !is_write_route(method, route)
+        || route.is_some_and(|route| NON_MODIFYING_ROUTES.contains(&route))
+}
+
+/// Whether the operation selected for execution in the GraphQL request is a
+/// mutation. Requests that fail to parse are left to the schema to report.
+pub fn is_graphql_mutation(request: &async_graphql::Request) -> bool {
+    selected_mutations(request).next().is_some()
+}
+
+/// Whether the GraphQL request is a mutation that can modify the state of the
+/// node, i.e. any mutation except logging in
+pub fn is_graphql_write(request: &async_graphql::Request) -> bool {
+    selected_mutations(request).any(|op| !is_login(&op.selection_set.node))
+}
+
+fn selected_mutations(
+    request: &async_graphql::Request,
+) -> impl Iterator<Item = OperationDefinition> {
+    let operations = match async_graphql::parser::parse_query(&request.query) {
+        Ok(document) => match document.operations {
+            DocumentOperations::Single(op) => vec![op.node],
+            DocumentOperations::Multiple(mut ops) => match &request.operation_name {
+                Some(name) => ops
+                    .remove(name.as_str())
+                    .map(|op| op.node)
+                    .into_iter()
+                    .collect(),
+                None => ops.into_values().map(|op| op.node).collect(),
+            },
+        },
+        Err(_) => Vec::new(),
+    };
+
+    operations
+        .into_iter()
+        .filter(|op| op.ty == OperationType::Mutation)
+}
+
+/// Fragments are not resolved, so mutations that use them are not recognized
+fn is_login(selection_set: &SelectionSet) -> bool {
+    selection_set.items.iter().all(|item| match &item.node {
+        Selection::Field(field) => match field.node.name.node.as_str() {
+            "__typename" => true,
+            "auth" => field.node.selection_set.node.items.iter().all(|item| {
+                matches!(
+                    &item.node,
+                    Selection::Field(field)
+                        if field.node.name.node.as_str() == "__typename"
+                            || NON_MODIFYING_AUTH_MUTATIONS
+                                .contains(&field.node.name.node.as_str())
+                )
+            }),
+            _ => false,
+        },
+        Selection::FragmentSpread(_) | Selection::InlineFragment(_) => false,
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Rejects requests to the write routes with `405` when the server is in
+/// read-only mode. The empty `Allow` header indicates that the resource was
+/// disabled by configuration.
+pub async fn read_only_middleware(
+    State(read_only): State<bool>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if read_only && !is_allowed_in_read_only(request.method(), matched_route(&request)) {
+        tracing::debug!(
+            method = %request.method(),
+            path = request.uri().path(),
+            "Request rejected in read-only mode",
+        );
+
+        return (
+            http::StatusCode::METHOD_NOT_ALLOWED,
+            [(http::header::ALLOW, "")],
+            axum::Json(serde_json::json!({
+                "message": READ_ONLY_MESSAGE,
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Limits Flight SQL to queries by rejecting the methods that can modify data
+/// with `FAILED_PRECONDITION` in read-only mode and with `UNAVAILABLE` while
+/// maintenance mode is enabled
+#[derive(Clone)]
+pub struct FlightSqlReadOnlyLayer {
+    read_only: bool,
+    maintenance: Option<Arc<MaintenanceMode>>,
+}
+
+impl FlightSqlReadOnlyLayer {
+    pub fn new(read_only: bool, maintenance: Option<Arc<MaintenanceMode>>) -> Self {
+        Self {
+            read_only,
+            maintenance,
+        }
+    }
+}
+
+impl<Svc> Layer<Svc> for FlightSqlReadOnlyLayer {
+    type Service = FlightSqlReadOnlyMiddleware<Svc>;
+
+    fn layer(&self, inner: Svc) -> Self::Service {
+        FlightSqlReadOnlyMiddleware {
+            inner,
+            read_only: self.read_only,
+            maintenance: self.maintenance.clone(),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+pub struct FlightSqlReadOnlyMiddleware<Svc> {
+    inner: Svc,
+    read_only: bool,
+    maintenance: Option<Arc<MaintenanceMode>>,
+}
+
+impl<Svc, ReqBody, ResBody> Service<http::Request<ReqBody>> for FlightSqlReadOnlyMiddleware<Svc>
+where
+    Svc: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    Svc::Error: Send + 'static,
+    Svc::Future: Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = Svc::Response;
+    type Error = Svc::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        if FLIGHT_SQL_WRITE_METHODS.contains(&request.uri().path()) {
+            let status = if self.read_only {
+                Some(tonic::Status::failed_precondition(READ_ONLY_MESSAGE))
+            } else {
+                self.maintenance
+                    .as_ref()
+                    .and_then(|maintenance| maintenance.message())
+                    .map(tonic::Status::unavailable)
+            };
+
+            if let Some(status) = status {
+                tracing::debug!(
+                    method = request.uri().path(),
+                    message = status.message(),
+                    "Flight SQL request rejected",
+                );
+
+                return Box::pin(std::future::ready(Ok(status.into_http())));
+            }
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,449 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::Extension;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use internal_error::*;
+use kamu::domain::ObjectStoreRegistry;
+use kamu_search_elasticsearch::ElasticsearchClientConfig;
+use url::Url;
+
+use crate::agent_supervisor::{AgentState, BackgroundAgentsStatus};
+use crate::route_group::matched_route;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Limits how often unauthenticated clients can trigger the checks
+const PUBLIC_REPORT_TTL: Duration = Duration::from_secs(5);
+
+const DEFAULT_ELASTICSEARCH_USERNAME: &str = "elastic";
+
+pub const STARTING_UP_MESSAGE: &str = "Server is starting up, try again later";
+
+/// Liveness probe that is served while the startup jobs are running
+const PROBE_ROUTE: &str = "/system/probe";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Progress of the jobs of `init_on_startup`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StartupJobsState {
+    #[default]
+    Running,
+    Completed,
+    /// Jobs are not run by the instance, e.g. in read-only mode
+    Skipped,
+    Failed(String),
+}
+
+/// Tracks whether the jobs of `init_on_startup` have completed
+#[derive(Debug, Default)]
+pub struct StartupJobsStatus {
+    state: Mutex<StartupJobsState>,
+}
+
+#[dill::component(pub)]
+#[dill::scope(dill::Singleton)]
+impl StartupJobsStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mark_completed(&self) {
+        self.set(StartupJobsState::Completed);
+    }
+
+    pub fn mark_skipped(&self) {
+        self.set(StartupJobsState::Skipped);
+    }
+
+    pub fn mark_failed(&self, error: &InternalError) {
+        self.set(StartupJobsState::Failed(error.to_string()));
+    }
+
+    pub fn state(&self) -> StartupJobsState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Whether the requests can be served, i.e. the jobs have either completed
+    /// or are not run by the instance
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            *self.state.lock().unwrap(),
+            StartupJobsState::Completed | StartupJobsState::Skipped
+        )
+    }
+
+    fn set(&self, state: StartupJobsState) {
+        *self.state.lock().unwrap() = state;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Ok,
+    /// Server keeps serving requests, but some of its functions are impaired
+    Degraded,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckReport {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub degraded: bool,
+    pub checks: Vec<CheckReport>,
+}
+
+impl ReadinessReport {
+    pub fn new(checks: Vec<CheckReport>) -> Self {
+        Self {
+            ready: checks.iter().all(|c| c.status != CheckStatus::Failed),
+            degraded: checks.iter().any(|c| c.status == CheckStatus::Degraded),
+            checks,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Outcome of a single check that didn't fail
+enum CheckOutcome {
+    Ok,
+    /// Dependency is not configured
+    Skipped,
+    Degraded(String),
+}
+
+type CheckResult = Result<CheckOutcome, InternalError>;
+
+/// Settings of the Elasticsearch check that are not part of
+/// [`ElasticsearchClientConfig`]
+#[derive(Debug, Clone)]
+pub struct ElasticsearchReadinessConfig {
+    /// User to authenticate as when the password is specified
+    pub username: String,
+}
+
+/// State that outlives individual readiness requests
+#[derive(Default)]
+pub struct ReadinessState {
+    elasticsearch_client: tokio::sync::OnceCell<reqwest::Client>,
+    public_report: tokio::sync::Mutex<Option<(Instant, ReadinessReport)>>,
+}
+
+#[dill::component(pub)]
+#[dill::scope(dill::Singleton)]
+impl ReadinessState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Verifies that the dependencies of the server are reachable
+#[dill::component(pub)]
+pub struct ReadinessChecker {
+    catalog: dill::Catalog,
+    state: Arc<ReadinessState>,
+    startup_jobs_status: Arc<StartupJobsStatus>,
+    background_agents_status: Arc<BackgroundAgentsStatus>,
+    elasticsearch_config: Option<Arc<ElasticsearchClientConfig>>,
+    elasticsearch_readiness_config: Option<Arc<ElasticsearchReadinessConfig>>,
+
+    #[dill::component(explicit)]
+    repo_url: Url,
+}
+
+impl ReadinessChecker {
+    pub async fn check(&self) -> ReadinessReport {
+        let (database, object_store, elasticsearch, startup_jobs, background_agents) = tokio::join!(
+            run_check("database", self.check_database()),
+            run_check("objectStore", self.check_object_store()),
+            run_check("elasticsearch", self.check_elasticsearch()),
+            run_check("startupJobs", self.check_startup_jobs()),
+            run_check("backgroundAgents", self.check_background_agents()),
+        );
+
+        ReadinessReport::new(vec![
+            database,
+            object_store,
+            elasticsearch,
+            startup_jobs,
+            background_agents,
+        ])
+    }
+
+    /// Report for unauthenticated clients: it doesn't disclose the details of
+    /// failures and is reused for a short time to keep the checks cheap
+    pub async fn check_public(&self) -> ReadinessReport {
+        let mut public_report = self.state.public_report.lock().await;
+
+        if let Some((checked_at, report)) = public_report.as_ref()
+            && checked_at.elapsed() < PUBLIC_REPORT_TTL
+        {
+            return report.clone();
+        }
+
+        let mut report = self.check().await;
+        for check in &mut report.checks {
+            check.message = None;
+        }
+
+        *public_report = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    async fn check_database(&self) -> CheckResult {
+        if let Ok(pool) = self.catalog.get_one::<sqlx::PgPool>() {
+            sqlx::query("SELECT 1")
+                .execute(pool.as_ref())
+                .await
+                .int_err()?;
+        } else if let Ok(pool) = self.catalog.get_one::<sqlx::SqlitePool>() {
+            sqlx::query("SELECT 1")
+                .execute(pool.as_ref())
+                .await
+                .int_err()?;
+        } else {
+            // In-memory repositories
+            return Ok(CheckOutcome::Skipped);
+        }
+        Ok(CheckOutcome::Ok)
+    }
+
+    async fn check_object_store(&self) -> CheckResult {
+        if self.repo_url.scheme() == "file" {
+            let path = self
+                .repo_url
+                .to_file_path()
+                .map_err(|_| InternalError::new(format!("Invalid path: {}", self.repo_url)))?;
+
+            let metadata = tokio::fs::metadata(&path).await.int_err()?;
+            if !metadata.is_dir() {
+                return InternalError::bail(format!("Not a directory: {}", path.display()));
+            }
+            return Ok(CheckOutcome::Ok);
+        }
+
+        let registry = self
+            .catalog
+            .get_one::<dyn ObjectStoreRegistry>()
+            .int_err()?;
+        let store = registry.get_store(&self.repo_url)?;
+        let prefix = object_store::path::Path::from(self.repo_url.path().trim_matches('/'));
+
+        store.list_with_delimiter(Some(&prefix)).await.int_err()?;
+        Ok(CheckOutcome::Ok)
+    }
+
+    async fn check_elasticsearch(&self) -> CheckResult {
+        let Some(config) = &self.elasticsearch_config else {
+            return Ok(CheckOutcome::Skipped);
+        };
+
+        let client = self
+            .state
+            .elasticsearch_client
+            .get_or_try_init(|| async {
+                let mut client_builder = reqwest::Client::builder().timeout(CHECK_TIMEOUT);
+                if let Some(ca_cert_pem_path) = &config.ca_cert_pem_path {
+                    let pem = tokio::fs::read(ca_cert_pem_path).await.int_err()?;
+                    client_builder = client_builder
+                        .add_root_certificate(reqwest::Certificate::from_pem(&pem).int_err()?);
+                }
+                client_builder.build().int_err()
+            })
+            .await?;
+
+        let mut request = client.get(config.url.join("_cluster/health").int_err()?);
+        if let Some(password) = &config.password {
+            let username = self
+                .elasticsearch_readiness_config
+                .as_ref()
+                .map_or(DEFAULT_ELASTICSEARCH_USERNAME, |c| c.username.as_str());
+            request = request.basic_auth(username, Some(password));
+        }
+
+        let response = request
+            .send()
+            .await
+            .int_err()?
+            .error_for_status()
+            .int_err()?;
+        let health: serde_json::Value =
+            serde_json::from_slice(&response.bytes().await.int_err()?).int_err()?;
+
+        match health.get("status").and_then(|s| s.as_str()) {
+            Some("green" | "yellow") => Ok(CheckOutcome::Ok),
+            status => InternalError::bail(format!("Cluster status is {status:?}")),
+        }
+    }
+
+    async fn check_startup_jobs(&self) -> CheckResult {
+        match self.startup_jobs_status.state() {
+            StartupJobsState::Running => {
+                InternalError::bail("Startup jobs have not completed yet".to_string())
+            }
+            StartupJobsState::Completed => Ok(CheckOutcome::Ok),
+            StartupJobsState::Skipped => Ok(CheckOutcome::Skipped),
+            StartupJobsState::Failed(error) => {
+                InternalError::bail(format!("Startup jobs failed: {error}"))
+            }
+        }
+    }
+
+    async fn check_background_agents(&self) -> CheckResult {
+        let mut restarting = Vec::new();
+
+        for (agent_name, state) in self.background_agents_status.snapshot() {
+            match state {
+                AgentState::Running | AgentState::Standby | AgentState::Paused => {}
+                AgentState::Restarting { last_error, .. } => {
+                    restarting.push(format!("{agent_name} ({last_error})"));
+                }
+                AgentState::Failed { last_error } => {
+                    return InternalError::bail(format!(
+                        "Agent {agent_name} exceeded its failure budget: {last_error}"
+                    ));
+                }
+            }
+        }
+
+        if restarting.is_empty() {
+            Ok(CheckOutcome::Ok)
+        } else {
+            Ok(CheckOutcome::Degraded(format!(
+                "Agents are restarting: {}",
+                restarting.join(", ")
+            )))
+        }
+    }
+}
+
+async fn run_check(name: &'static str, check: impl Future<Output = CheckResult>) -> CheckReport {
+    let start = Instant::now();
+
+    let (status, message) = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(CheckOutcome::Ok)) => (CheckStatus::Ok, None),
+        Ok(Ok(CheckOutcome::Skipped)) => (CheckStatus::Skipped, None),
+        Ok(Ok(CheckOutcome::Degraded(message))) => (CheckStatus::Degraded, Some(message)),
+        Ok(Err(err)) => {
+            tracing::warn!(check = name, error = ?err, error_msg = %err, "Readiness check failed");
+            (CheckStatus::Failed, Some(err.to_string()))
+        }
+        Err(_) => (
+            CheckStatus::Failed,
+            Some(format!("Timed out after {}s", CHECK_TIMEOUT.as_secs())),
+        ),
+    };
+
+    CheckReport {
+        name,
+        status,
+        message,
+        duration_ms: u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Who the readiness report is served to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadinessAudience {
+    /// Clients of the dedicated admin listener that get the full report
+    Internal,
+    /// Anyone who can reach the public HTTP listener
+    Public,
+}
+
+/// Reports readiness of the server and its dependencies with `200` or `503`
+pub async fn readiness_handler(
+    State(audience): State<ReadinessAudience>,
+    Extension(catalog): Extension<dill::Catalog>,
+) -> Response {
+    let report = match catalog.get_one::<ReadinessChecker>() {
+        Ok(checker) => match audience {
+            ReadinessAudience::Internal => checker.check().await,
+            ReadinessAudience::Public => checker.check_public().await,
+        },
+        Err(err) => {
+            tracing::error!(error = ?err, error_msg = %err, "Failed to build readiness checker");
+            ReadinessReport::new(vec![CheckReport {
+                name: "readinessChecker",
+                status: CheckStatus::Failed,
+                message: (audience == ReadinessAudience::Internal).then(|| err.to_string()),
+                duration_ms: 0,
+            }])
+        }
+    };
+
+    let status = if report.ready {
+        http::StatusCode::OK
+    } else {
+        http::StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, axum::Json(report)).into_response()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Rejects requests with `503` until the jobs of `init_on_startup` complete, as
+/// the listeners start accepting connections before the jobs run. The liveness
+/// probe is served regardless.
+pub async fn startup_jobs_middleware(
+    State(startup_jobs_status): State<Option<Arc<StartupJobsStatus>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(startup_jobs_status) = startup_jobs_status
+        && !startup_jobs_status.is_finished()
+        && matched_route(&request) != Some(PROBE_ROUTE)
+    {
+        tracing::debug!(
+            method = %request.method(),
+            path = request.uri().path(),
+            "Request rejected until startup jobs complete",
+        );
+
+        return (
+            http::StatusCode::SERVICE_UNAVAILABLE,
+            [(http::header::RETRY_AFTER, "5")],
+            axum::Json(serde_json::json!({
+                "message": STARTING_UP_MESSAGE,
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
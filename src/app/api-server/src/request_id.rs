@@ -0,0 +1,145 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Future;
+use http::HeaderValue;
+use tower::{Layer, Service};
+use tracing::Instrument as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Identifier used to correlate a request with logs, responses, and
+/// notifications
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn new_random() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Accepts identifiers provided by clients as long as they are short and
+    /// consist of safe characters only
+    pub fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?;
+
+        let is_valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+
+        is_valid.then(|| Self(value.to_string()))
+    }
+
+    /// Restores the identifier that was validated before it was stored
+    pub fn new_unchecked(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Accepts the `X-Request-Id` header of the request or generates a new
+/// identifier, makes it available via the [`RequestId`] extension and the
+/// root tracing span, and echoes it in the response.
+///
+/// Works for both HTTP and gRPC services and should be the outermost layer.
+#[derive(Debug, Clone, Default)]
+pub struct RequestIdLayer {}
+
+impl RequestIdLayer {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<Svc> Layer<Svc> for RequestIdLayer {
+    type Service = RequestIdMiddleware<Svc>;
+
+    fn layer(&self, inner: Svc) -> Self::Service {
+        RequestIdMiddleware { inner }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct RequestIdMiddleware<Svc> {
+    inner: Svc,
+}
+
+impl<Svc, B, ResBody> Service<http::Request<B>> for RequestIdMiddleware<Svc>
+where
+    Svc: Service<http::Request<B>, Response = http::Response<ResBody>> + Send + Clone + 'static,
+    Svc::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = Svc::Response;
+    type Error = Svc::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // Inspired by https://github.com/tower-rs/tower/issues/547#issuecomment-767629149
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::new_random);
+
+        request.extensions_mut().insert(request_id.clone());
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            client_ip = tracing::field::Empty,
+        );
+
+        Box::pin(
+            async move {
+                let mut response = inner.call(request).await?;
+
+                if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+                    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+                }
+
+                Ok(response)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
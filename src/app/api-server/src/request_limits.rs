@@ -0,0 +1,163 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http_body_util::{BodyExt as _, LengthLimitError};
+
+use crate::config::{HttpLimitsConfig, RequestLimitsConfig};
+use crate::route_group::RouteGroup;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestLimits {
+    pub max_body_size: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+impl RequestLimits {
+    fn from_config(config: &RequestLimitsConfig, default: &RequestLimitsConfig) -> Self {
+        Self {
+            max_body_size: config.max_body_size.or(default.max_body_size),
+            timeout: config.timeout.or(default.timeout).map(Into::into),
+        }
+    }
+}
+
+/// Request limits of every route group with the defaults applied
+#[derive(Debug, Clone, Default)]
+pub struct RouteLimits {
+    default: RequestLimits,
+    graphql: RequestLimits,
+    query: RequestLimits,
+    ingest: RequestLimits,
+    transfer: RequestLimits,
+}
+
+impl RouteLimits {
+    pub fn from_config(config: &HttpLimitsConfig) -> Self {
+        let default = &config.default;
+        let resolve = |group: &Option<RequestLimitsConfig>| {
+            RequestLimits::from_config(group.as_ref().unwrap_or(default), default)
+        };
+
+        Self {
+            default: RequestLimits::from_config(default, default),
+            graphql: resolve(&config.graphql),
+            query: resolve(&config.query),
+            ingest: resolve(&config.ingest),
+            transfer: resolve(&config.transfer),
+        }
+    }
+
+    pub fn get(&self, group: Option<RouteGroup>) -> &RequestLimits {
+        match group {
+            None => &self.default,
+            Some(RouteGroup::GraphQL) => &self.graphql,
+            Some(RouteGroup::Query) => &self.query,
+            Some(RouteGroup::Ingest) => &self.ingest,
+            Some(RouteGroup::Transfer) => &self.transfer,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Rejects requests with bodies over the limit with `413` and requests taking
+/// longer than the timeout with `408`.
+///
+/// Bodies without `Content-Length` are limited while being read. Handlers fail
+/// once they consume more than allowed, and their responses are replaced with
+/// `413`, as they may report the failure to read the body differently.
+pub async fn request_limits_middleware(
+    State(route_limits): State<Arc<RouteLimits>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limits = *route_limits.get(RouteGroup::of_request(&request));
+    let body_limit_exceeded = Arc::new(AtomicBool::new(false));
+
+    let request = match limits.max_body_size {
+        None => request,
+        Some(max_body_size) => {
+            let content_length = request
+                .headers()
+                .get(http::header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok());
+
+            if content_length.is_some_and(|len| len > max_body_size) {
+                return body_too_large_response(max_body_size);
+            }
+
+            let limit = usize::try_from(max_body_size).unwrap_or(usize::MAX);
+            let body_limit_exceeded = body_limit_exceeded.clone();
+            request.map(|body| {
+                Body::new(
+                    http_body_util::Limited::new(body, limit).map_err(move |err| {
+                        if err.is::<LengthLimitError>() {
+                            body_limit_exceeded.store(true, Ordering::Relaxed);
+                        }
+                        err
+                    }),
+                )
+            })
+        }
+    };
+
+    let response = match limits.timeout {
+        None => next.run(request).await,
+        Some(timeout) => match tokio::time::timeout(timeout, next.run(request)).await {
+            Ok(response) => response,
+            Err(_) => {
+                return error_response(
+                    http::StatusCode::REQUEST_TIMEOUT,
+                    format!(
+                        "Request was not processed within {}s",
+                        timeout.as_secs_f64()
+                    ),
+                );
+            }
+        },
+    };
+
+    if body_limit_exceeded.load(Ordering::Relaxed)
+        && let Some(max_body_size) = limits.max_body_size
+    {
+        return body_too_large_response(max_body_size);
+    }
+
+    response
+}
+
+fn body_too_large_response(max_body_size: u64) -> Response {
+    error_response(
+        http::StatusCode::PAYLOAD_TOO_LARGE,
+        format!("Request body exceeds the limit of {max_body_size} bytes"),
+    )
+}
+
+fn error_response(status: http::StatusCode, message: String) -> Response {
+    (
+        status,
+        axum::Json(serde_json::json!({
+            "message": message,
+        })),
+    )
+        .into_response()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
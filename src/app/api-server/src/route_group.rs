@@ -0,0 +1,102 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use axum::extract::MatchedPath;
+use http::Method;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Groups of HTTP routes that share limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    GraphQL,
+    Query,
+    Ingest,
+    Transfer,
+}
+
+impl RouteGroup {
+    /// Determines the group by the path template of a route, e.g.
+    /// `/{account_name}/{dataset_name}/query`. Templates are used instead of
+    /// request paths, so that accounts and datasets named like the route
+    /// segments are not mistaken for them.
+    pub fn classify(route: &str) -> Option<Self> {
+        let path = route.trim_end_matches('/');
+
+        if path == "/graphql" {
+            Some(Self::GraphQL)
+        } else if path == "/query"
+            || path.starts_with("/odata")
+            || path.ends_with("/query")
+            || path.ends_with("/tail")
+        {
+            Some(Self::Query)
+        } else if path.ends_with("/ingest") || path.starts_with("/platform/file/upload") {
+            Some(Self::Ingest)
+        } else if path.ends_with("/pull")
+            || path.ends_with("/push")
+            || ["/refs/", "/blocks/", "/data/", "/checkpoints/"]
+                .iter()
+                .any(|segment| path.contains(segment))
+        {
+            Some(Self::Transfer)
+        } else {
+            None
+        }
+    }
+
+    /// Group of the route that the request was routed to. Requests that didn't
+    /// match any route are outside of the groups.
+    pub fn of_request<B>(request: &http::Request<B>) -> Option<Self> {
+        matched_route(request).and_then(Self::classify)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GraphQL => "graphql",
+            Self::Query => "query",
+            Self::Ingest => "ingest",
+            Self::Transfer => "transfer",
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Path template of the route that the request was routed to. Only available
+/// in the middleware applied to the routes via `Router::layer`.
+pub fn matched_route<B>(request: &http::Request<B>) -> Option<&str> {
+    request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+}
+
+/// Whether the request to the route may modify datasets or other state of the
+/// node. GraphQL requests are not included, as only their mutations do that,
+/// which the handler checks with [`crate::read_only::is_graphql_write`].
+/// Requests that didn't match any route are considered writes unless they are
+/// reads by method.
+pub fn is_write_route(method: &Method, route: Option<&str>) -> bool {
+    let route = route.unwrap_or_default();
+    let group = RouteGroup::classify(route);
+
+    if group == Some(RouteGroup::GraphQL) {
+        return false;
+    }
+    if route.trim_end_matches('/').ends_with("/push") {
+        return true;
+    }
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return false;
+    }
+    group != Some(RouteGroup::Query)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,217 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::os::fd::{AsRawFd as _, BorrowedFd, FromRawFd as _, OwnedFd, RawFd};
+use std::sync::{Mutex, OnceLock};
+
+use internal_error::*;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// First descriptor passed by systemd, following stdin, stdout, and stderr
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Variables describing the passed descriptors
+const LISTEN_ENV_VARS: [&str; 3] = ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"];
+
+/// Sockets received by [`receive_listen_fds`]
+static RECEIVED_FDS: OnceLock<Option<ListenFds>> = OnceLock::new();
+
+/// Descriptors that were already taken, as every one of them must be owned
+/// only once
+static TAKEN_FDS: Mutex<Vec<RawFd>> = Mutex::new(Vec::new());
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Sockets passed to the process via the `LISTEN_PID`, `LISTEN_FDS`, and
+/// `LISTEN_FDNAMES` environment variables as described in `sd_listen_fds(3)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenFds {
+    /// Names of the descriptors in the order they were passed
+    pub names: Vec<String>,
+}
+
+impl ListenFds {
+    /// Returns `None` when no sockets were passed to this process
+    pub fn from_env() -> Result<Option<Self>, InternalError> {
+        Self::parse(
+            std::env::var("LISTEN_PID").ok().as_deref(),
+            std::env::var("LISTEN_FDS").ok().as_deref(),
+            std::env::var("LISTEN_FDNAMES").ok().as_deref(),
+            std::process::id(),
+        )
+    }
+
+    pub fn parse(
+        listen_pid: Option<&str>,
+        listen_fds: Option<&str>,
+        listen_fdnames: Option<&str>,
+        pid: u32,
+    ) -> Result<Option<Self>, InternalError> {
+        let (Some(listen_pid), Some(listen_fds)) = (listen_pid, listen_fds) else {
+            return Ok(None);
+        };
+
+        // Variables were meant for another process, e.g. the parent one
+        if listen_pid.trim().parse::<u32>().int_err()? != pid {
+            return Ok(None);
+        }
+
+        let num_fds = listen_fds.trim().parse::<usize>().int_err()?;
+        if num_fds == 0 {
+            return Ok(None);
+        }
+
+        // Unnamed descriptors get the default name of systemd
+        let mut names: Vec<String> = listen_fdnames
+            .map(|names| names.split(':').map(ToString::to_string).collect())
+            .unwrap_or_default();
+        names.resize(num_fds, "unknown".to_string());
+
+        Ok(Some(Self { names }))
+    }
+
+    /// Index of the descriptor with the specified name or of the only passed
+    /// descriptor when the name is not specified
+    pub fn position(&self, name: Option<&str>) -> Result<usize, InternalError> {
+        match name {
+            Some(name) => self.names.iter().position(|n| n == name).ok_or_else(|| {
+                InternalError::new(format!(
+                    "Socket '{name}' was not passed by systemd, passed sockets: {}",
+                    self.names.join(", ")
+                ))
+            }),
+            None if self.names.len() == 1 => Ok(0),
+            None => InternalError::bail(format!(
+                "Multiple sockets were passed by systemd, specify the name of the one to listen \
+                 on: {}",
+                self.names.join(", ")
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Receives the sockets passed by systemd, marking them as close-on-exec and
+/// unsetting the environment variables, so that neither is inherited by the
+/// child processes
+///
+/// # Safety
+///
+/// Must be called before spawning any threads, as modifying the environment
+/// is not thread-safe
+pub unsafe fn receive_listen_fds() -> Result<(), InternalError> {
+    if RECEIVED_FDS.get().is_some() {
+        return Ok(());
+    }
+
+    let listen_fds = ListenFds::from_env()?;
+    if let Some(listen_fds) = &listen_fds {
+        for i in 0..listen_fds.names.len() {
+            set_cloexec(LISTEN_FDS_START + RawFd::try_from(i).int_err()?)?;
+        }
+    }
+
+    for var in LISTEN_ENV_VARS {
+        // SAFETY: Guaranteed by the caller
+        unsafe { std::env::remove_var(var) };
+    }
+
+    RECEIVED_FDS.get_or_init(|| listen_fds);
+    Ok(())
+}
+
+/// Takes ownership of the listener socket passed by systemd
+pub fn take_listen_fd(name: Option<&str>) -> Result<OwnedFd, InternalError> {
+    let listen_fds = match RECEIVED_FDS.get() {
+        Some(listen_fds) => listen_fds.clone(),
+        // Not received on startup, e.g. when the server is embedded
+        None => ListenFds::from_env()?,
+    };
+    let Some(listen_fds) = listen_fds else {
+        return InternalError::bail(
+            "Listener is configured to use socket activation, but no sockets were passed by \
+             systemd"
+                .to_string(),
+        );
+    };
+
+    let fd = LISTEN_FDS_START + RawFd::try_from(listen_fds.position(name)?).int_err()?;
+
+    let mut taken_fds = TAKEN_FDS.lock().unwrap();
+    if taken_fds.contains(&fd) {
+        return InternalError::bail(format!(
+            "Socket passed by systemd is used by multiple listeners: {}",
+            name.unwrap_or_default()
+        ));
+    }
+    taken_fds.push(fd);
+    set_cloexec(fd)?;
+
+    // SAFETY: The descriptor was passed to this process by systemd and is taken
+    // only once
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// Fails unless the descriptor is a stream socket in the listening state, as
+/// systemd can also pass datagram sockets, FIFOs, and regular files
+pub fn ensure_listening_stream(fd: BorrowedFd<'_>) -> Result<(), InternalError> {
+    let socket_type = socket_option(fd, libc::SO_TYPE).map_err(|err| {
+        InternalError::new(format!(
+            "Descriptor passed by systemd is not a socket: {err}"
+        ))
+    })?;
+    let is_listening = socket_option(fd, libc::SO_ACCEPTCONN).int_err()? != 0;
+
+    if socket_type != libc::SOCK_STREAM || !is_listening {
+        return InternalError::bail(
+            "Socket passed by systemd is not a listening stream socket".to_string(),
+        );
+    }
+    Ok(())
+}
+
+fn socket_option(fd: BorrowedFd<'_>, option: libc::c_int) -> std::io::Result<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = libc::socklen_t::try_from(std::mem::size_of::<libc::c_int>()).unwrap();
+
+    // SAFETY: The buffer is valid for writes of the specified length
+    let res = unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_SOCKET,
+            option,
+            (&raw mut value).cast(),
+            &raw mut len,
+        )
+    };
+    if res != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(value)
+}
+
+fn set_cloexec(fd: RawFd) -> Result<(), InternalError> {
+    // SAFETY: Only the flags of the descriptor are read and updated
+    let res = unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFD);
+        if flags < 0 {
+            flags
+        } else {
+            libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC)
+        }
+    };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error()).int_err();
+    }
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
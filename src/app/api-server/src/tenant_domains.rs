@@ -0,0 +1,298 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::str::FromStr as _;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use internal_error::*;
+use kamu::domain::{Protocols, ServerUrlConfig};
+use url::Url;
+
+use crate::config::TenantDomainConfig;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Segments that follow the dataset name in the paths of the smart transfer
+/// protocol and data routes
+const DATASET_ROUTE_SEGMENTS: [&str; 9] = [
+    "refs",
+    "blocks",
+    "data",
+    "checkpoints",
+    "pull",
+    "push",
+    "ingest",
+    "metadata",
+    "tail",
+];
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Custom domain serving the datasets of a single account
+#[derive(Clone)]
+pub struct TenantDomain {
+    pub host: String,
+    pub account_name: odf::AccountName,
+    /// External URLs of the node when accessed via the domain
+    pub server_url_config: ServerUrlConfig,
+}
+
+/// Maps `Host` headers of the requests to the accounts owning the domains
+#[derive(Clone, Default)]
+pub struct TenantDomains {
+    domains: Vec<TenantDomain>,
+}
+
+impl TenantDomains {
+    /// Builds the domains, defaulting their platform and REST URLs to
+    /// `https://{host}/` and sharing the Flight SQL URL of the node
+    pub fn from_config(
+        domains: Vec<TenantDomainConfig>,
+        protocols: &Protocols,
+    ) -> Result<Self, InternalError> {
+        let mut result: Vec<TenantDomain> = Vec::with_capacity(domains.len());
+
+        for domain in domains {
+            let host = normalize_host(&domain.host);
+            if result.iter().any(|d| d.host == host) {
+                return InternalError::bail(format!("Tenant domain '{host}' is specified twice"));
+            }
+
+            let account_name = odf::AccountName::from_str(&domain.account_name).map_err(|err| {
+                InternalError::new(format!(
+                    "Invalid account name '{}' for tenant domain '{host}': {err}",
+                    domain.account_name
+                ))
+            })?;
+
+            let default_url = Url::parse(&format!("https://{host}/")).int_err()?;
+            let server_url_config = ServerUrlConfig::new(Protocols {
+                base_url_platform: domain
+                    .base_url_platform
+                    .map_or_else(|| default_url.clone(), Into::into),
+                base_url_rest: domain.base_url_rest.map_or(default_url, Into::into),
+                base_url_flightsql: protocols.base_url_flightsql.clone(),
+            });
+
+            result.push(TenantDomain {
+                host,
+                account_name,
+                server_url_config,
+            });
+        }
+
+        Ok(Self { domains: result })
+    }
+
+    /// Finds the domain by the value of the `Host` header, ignoring the port
+    /// and the case
+    pub fn by_host(&self, host: &str) -> Option<&TenantDomain> {
+        let host = normalize_host(host);
+        self.domains.iter().find(|d| d.host == host)
+    }
+
+    /// Finds the first domain of the account, used to generate the links to
+    /// its resources outside of the requests, e.g. in emails
+    pub fn by_account(&self, account_name: &odf::AccountName) -> Option<&TenantDomain> {
+        self.domains
+            .iter()
+            .find(|d| d.account_name == *account_name)
+    }
+}
+
+/// URLs for the links to the resources of the account, which point to its
+/// domain if it has one
+pub fn account_server_url_config<'a>(
+    tenant_domains: Option<&'a TenantDomains>,
+    server_url_config: &'a ServerUrlConfig,
+    account_name: &odf::AccountName,
+) -> &'a ServerUrlConfig {
+    tenant_domains
+        .and_then(|domains| domains.by_account(account_name))
+        .map_or(server_url_config, |domain| &domain.server_url_config)
+}
+
+fn normalize_host(host: &str) -> String {
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => name,
+        _ => host,
+    };
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Result of mapping the path of a request to a tenant domain
+#[derive(Debug, PartialEq, Eq)]
+pub enum TenantPath {
+    /// Routed as is, e.g. non-dataset routes and the paths already qualified
+    /// with the account of the domain
+    Unchanged,
+    /// Qualified with the account of the domain
+    Rewritten(String),
+    /// Qualified with an account other than the one of the domain
+    OtherAccount,
+}
+
+/// Qualifies the path of a request to a tenant domain with the account name,
+/// so that `/{dataset_name}/...` and `/odata/...` are routed as
+/// `/{account_name}/{dataset_name}/...` and `/odata/{account_name}/...`.
+///
+/// The paths starting with the account name of the domain are treated as
+/// already qualified, so the dataset named like the account is addressed as
+/// `/{account_name}/{account_name}/...` there. The datasets of the other
+/// accounts are not reachable via the domain.
+pub fn rewrite_path(path: &str, account_name: &str) -> TenantPath {
+    let is_account = |segment: &str| segment.eq_ignore_ascii_case(account_name);
+    let is_route_segment = |segment: Option<&str>| {
+        segment.is_some_and(|segment| DATASET_ROUTE_SEGMENTS.contains(&segment))
+    };
+
+    if path == "/odata" || path == "/odata/" {
+        return TenantPath::Rewritten(format!("/odata/{account_name}"));
+    }
+    if let Some(rest) = path.strip_prefix("/odata/") {
+        let first = rest.split('/').next().unwrap_or_default();
+        return if is_account(first) {
+            TenantPath::Unchanged
+        } else {
+            TenantPath::Rewritten(format!("/odata/{account_name}/{rest}"))
+        };
+    }
+
+    let mut segments = path.trim_start_matches('/').split('/');
+    let (first, second, third) = (segments.next(), segments.next(), segments.next());
+    let Some(first) = first.filter(|s| !s.is_empty()) else {
+        return TenantPath::Unchanged;
+    };
+
+    // `/{account_name}/{dataset_name}/{segment}/...`
+    if second.is_some_and(|s| !s.is_empty()) && is_route_segment(third) {
+        if is_account(first) {
+            return TenantPath::Unchanged;
+        }
+        if !is_route_segment(second) {
+            return TenantPath::OtherAccount;
+        }
+    }
+
+    // `/{dataset_name}/{segment}/...`
+    if is_route_segment(second) {
+        TenantPath::Rewritten(format!("/{account_name}{path}"))
+    } else {
+        TenantPath::Unchanged
+    }
+}
+
+fn request_host(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(http::header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| request.uri().host())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Routes the requests to the tenant domains to the datasets of their
+/// accounts and puts the [`TenantDomain`] into the extensions.
+///
+/// Must wrap the router, as the layers of the router run after the routing.
+/// Relies on the `Host` header, so the proxies have to preserve it.
+pub async fn tenant_domain_middleware(
+    State(domains): State<Arc<TenantDomains>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(domain) = request_host(&request)
+        .and_then(|host| domains.by_host(host))
+        .cloned()
+    else {
+        return next.run(request).await;
+    };
+
+    let path = match rewrite_path(request.uri().path(), domain.account_name.as_str()) {
+        TenantPath::Unchanged => None,
+        TenantPath::Rewritten(path) => Some(path),
+        TenantPath::OtherAccount => {
+            tracing::debug!(
+                host = domain.host,
+                path = request.uri().path(),
+                "Request to tenant domain for dataset of another account rejected",
+            );
+            return (
+                http::StatusCode::NOT_FOUND,
+                axum::Json(serde_json::json!({
+                    "message": "Not Found",
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    if let Some(path) = path {
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{path}?{query}"),
+            None => path,
+        };
+
+        let mut parts = request.uri().clone().into_parts();
+        match path_and_query.parse() {
+            Ok(path_and_query) => {
+                parts.path_and_query = Some(path_and_query);
+                if let Ok(uri) = http::Uri::from_parts(parts) {
+                    tracing::debug!(
+                        host = domain.host,
+                        original_uri = %request.uri(),
+                        %uri,
+                        "Request to tenant domain rewritten",
+                    );
+                    *request.uri_mut() = uri;
+                }
+            }
+            Err(err) => {
+                tracing::debug!(error = %err, "Failed to rewrite request to tenant domain");
+            }
+        }
+    }
+
+    request.extensions_mut().insert(domain);
+    next.run(request).await
+}
+
+/// Replaces the [`ServerUrlConfig`] in the request catalog with the one of the
+/// tenant domain, so that the links generated in responses use the domain.
+///
+/// Must be placed after the layer that puts the catalog into the extensions.
+pub async fn tenant_domain_catalog_middleware(mut request: Request, next: Next) -> Response {
+    let extensions = request.extensions();
+    let catalog = match (
+        extensions.get::<TenantDomain>(),
+        extensions.get::<dill::Catalog>(),
+    ) {
+        (Some(domain), Some(catalog)) => Some(
+            catalog
+                .builder_chained()
+                .add_value(domain.server_url_config.clone())
+                .build(),
+        ),
+        _ => None,
+    };
+
+    if let Some(catalog) = catalog {
+        request.extensions_mut().insert(catalog);
+    }
+
+    next.run(request).await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,298 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use internal_error::*;
+use rustls::pki_types::pem::PemObject as _;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::{TlsClientAuthConfig, TlsConfig, TlsVersion};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const ALPN_HTTP1: &[u8] = b"http/1.1";
+pub const ALPN_H2: &[u8] = b"h2";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serves the certificate loaded from PEM files and allows to replace it
+/// without restarting the listeners
+#[derive(Debug)]
+pub struct ReloadableCertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadableCertResolver {
+    pub fn load(
+        cert_path: impl Into<PathBuf>,
+        key_path: impl Into<PathBuf>,
+    ) -> Result<Self, InternalError> {
+        let cert_path = cert_path.into();
+        let key_path = key_path.into();
+        let certified_key = load_certified_key(&cert_path, &key_path)?;
+
+        Ok(Self {
+            cert_path,
+            key_path,
+            current: RwLock::new(Arc::new(certified_key)),
+        })
+    }
+
+    /// Re-reads the files. On error the previously loaded certificate stays in
+    /// use.
+    pub fn reload(&self) -> Result<(), InternalError> {
+        let certified_key = load_certified_key(&self.cert_path, &self.key_path)?;
+        *self.current.write().unwrap() = Arc::new(certified_key);
+        Ok(())
+    }
+
+    /// Periodically checks modification times of the files and reloads the
+    /// certificate when they change
+    pub fn spawn_reload_watcher(self: &Arc<Self>, interval: Duration) {
+        let resolver = Arc::downgrade(self);
+        let mut last_modified = self.modified_times();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let Some(resolver) = resolver.upgrade() else {
+                    break;
+                };
+
+                let modified = resolver.modified_times();
+                if modified == last_modified {
+                    continue;
+                }
+
+                match resolver.reload() {
+                    Ok(()) => {
+                        tracing::info!(
+                            cert_path = %resolver.cert_path.display(),
+                            "Reloaded TLS certificate",
+                        );
+                        last_modified = modified;
+                    }
+                    // Files may be caught in the middle of being replaced, so we keep retrying
+                    Err(err) => tracing::error!(
+                        cert_path = %resolver.cert_path.display(),
+                        key_path = %resolver.key_path.display(),
+                        error = ?err,
+                        error_msg = %err,
+                        "Failed to reload TLS certificate, keeping the previous one",
+                    ),
+                }
+            }
+        });
+    }
+
+    fn modified_times(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        (modified(&self.cert_path), modified(&self.key_path))
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, InternalError> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| {
+            InternalError::new(format!(
+                "Failed to read TLS certificate from '{}': {err}",
+                cert_path.display()
+            ))
+        })?;
+
+    if certs.is_empty() {
+        return InternalError::bail(format!(
+            "No certificates found in '{}'",
+            cert_path.display()
+        ));
+    }
+
+    let key = PrivateKeyDer::from_pem_file(key_path).map_err(|err| {
+        InternalError::new(format!(
+            "Failed to read TLS private key from '{}': {err}",
+            key_path.display()
+        ))
+    })?;
+
+    let signing_key = rustls::crypto::aws_lc_rs::sign::any_supported_type(&key).int_err()?;
+
+    let certified_key = CertifiedKey::new(certs, signing_key);
+
+    // Keys that can't expose their public part are accepted as rustls does
+    match certified_key.keys_match() {
+        Ok(()) | Err(rustls::Error::InconsistentKeys(rustls::InconsistentKeys::Unknown)) => {
+            Ok(certified_key)
+        }
+        Err(err) => InternalError::bail(format!(
+            "TLS private key from '{}' does not match the certificate from '{}': {err}",
+            key_path.display(),
+            cert_path.display()
+        )),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Builds a TLS acceptor that shares the certificate `resolver` with other
+/// listeners and negotiates the specified ALPN protocols
+pub fn build_tls_acceptor(
+    config: &TlsConfig,
+    resolver: Arc<ReloadableCertResolver>,
+    alpn_protocols: &[&[u8]],
+) -> Result<TlsAcceptor, InternalError> {
+    let protocol_versions: &[&rustls::SupportedProtocolVersion] = match config.min_version {
+        TlsVersion::Tls12 => &[&rustls::version::TLS13, &rustls::version::TLS12],
+        TlsVersion::Tls13 => &[&rustls::version::TLS13],
+    };
+
+    let crypto_provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+
+    let builder = rustls::ServerConfig::builder_with_provider(crypto_provider.clone())
+        .with_protocol_versions(protocol_versions)
+        .int_err()?;
+
+    let builder = match &config.client_auth {
+        None => builder.with_no_client_auth(),
+        Some(client_auth) => {
+            builder.with_client_cert_verifier(build_client_verifier(client_auth, crypto_provider)?)
+        }
+    };
+
+    let mut server_config = builder.with_cert_resolver(resolver);
+
+    server_config.alpn_protocols = alpn_protocols.iter().map(|p| p.to_vec()).collect();
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn build_client_verifier(
+    config: &TlsClientAuthConfig,
+    crypto_provider: Arc<rustls::crypto::CryptoProvider>,
+) -> Result<Arc<dyn rustls::server::danger::ClientCertVerifier>, InternalError> {
+    let mut roots = rustls::RootCertStore::empty();
+
+    for cert in CertificateDer::pem_file_iter(&config.ca_cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| {
+            InternalError::new(format!(
+                "Failed to read client CA certificates from '{}': {err}",
+                config.ca_cert_path.display()
+            ))
+        })?
+    {
+        roots.add(cert).int_err()?;
+    }
+
+    if roots.is_empty() {
+        return InternalError::bail(format!(
+            "No certificates found in '{}'",
+            config.ca_cert_path.display()
+        ));
+    }
+
+    let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), crypto_provider);
+    let builder = if config.required {
+        builder
+    } else {
+        builder.allow_unauthenticated()
+    };
+
+    builder.build().int_err()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Identity of a client that presented a certificate verified during the
+/// handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertIdentity {
+    pub common_name: Option<String>,
+    pub subject_alt_names: Vec<String>,
+}
+
+impl ClientCertIdentity {
+    /// Extracts the identity from the end-entity certificate of the peer
+    pub fn from_connection(conn: &rustls::ServerConnection) -> Option<Self> {
+        let cert = conn.peer_certificates()?.first()?;
+        match Self::from_der(cert) {
+            Ok(identity) => Some(identity),
+            Err(err) => {
+                tracing::warn!(error = %err, "Failed to parse client certificate");
+                None
+            }
+        }
+    }
+
+    pub fn from_der(cert: &CertificateDer<'_>) -> Result<Self, InternalError> {
+        use x509_parser::extensions::GeneralName;
+
+        let (_, cert) = x509_parser::parse_x509_certificate(cert).int_err()?;
+
+        let common_name = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .map(str::to_string);
+
+        let subject_alt_names = cert
+            .subject_alternative_name()
+            .int_err()?
+            .map(|ext| {
+                ext.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(s)
+                        | GeneralName::URI(s)
+                        | GeneralName::RFC822Name(s) => Some(s.to_string()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            common_name,
+            subject_alt_names,
+        })
+    }
+
+    /// Common name followed by the alternative names
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.common_name
+            .iter()
+            .chain(self.subject_alt_names.iter())
+            .map(String::as_str)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -10,6 +10,8 @@
 use kamu_accounts_services::PasswordPolicyConfig;
 use serde::Serialize;
 
+use crate::maintenance::MaintenanceStatus;
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Clone, Serialize)]
@@ -19,6 +21,11 @@ pub struct UIConfiguration {
     pub feature_flags: UIFeatureFlags,
     pub semantic_search_threshold_score: f32,
     pub min_new_password_length: usize,
+    /// Server rejects all modifications, so the write actions should be hidden
+    pub read_only: bool,
+    /// Maintenance mode is toggled at runtime, so the UI should show a banner
+    /// while it's enabled
+    pub maintenance: MaintenanceStatus,
 }
 
 impl Default for UIConfiguration {
@@ -28,6 +35,8 @@ impl Default for UIConfiguration {
             feature_flags: UIFeatureFlags::default(),
             semantic_search_threshold_score: 0.0,
             min_new_password_length: PasswordPolicyConfig::default().min_new_password_length,
+            read_only: false,
+            maintenance: MaintenanceStatus::default(),
         }
     }
 }
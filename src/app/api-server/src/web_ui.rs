@@ -0,0 +1,289 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::Extension;
+use axum::extract::Request;
+use axum::handler::Handler;
+use axum::response::{IntoResponse, Response};
+use bytes::Bytes;
+use http::{HeaderValue, Method};
+use internal_error::*;
+use kamu::domain::ServerUrlConfig;
+use observability::axum::unknown_fallback_handler;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const INDEX_FILE: &str = "index.html";
+
+/// Configuration of the UI that is generated by the server, so that the UI
+/// talks to the node that serves it
+const RUNTIME_CONFIG_FILE: &str = "assets/runtime-config.json";
+
+const CACHE_CONTROL_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const CACHE_CONTROL_NO_CACHE: &str = "no-cache";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+pub struct WebUiFile {
+    pub content: Bytes,
+    pub content_type: HeaderValue,
+    pub cache_control: HeaderValue,
+}
+
+impl WebUiFile {
+    fn new(path: &str, content: Bytes) -> Self {
+        let content_type = mime_guess::from_path(path).first_or_octet_stream();
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+
+        Self {
+            content,
+            content_type: HeaderValue::from_str(content_type.as_ref())
+                .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+            cache_control: HeaderValue::from_static(if is_hashed_asset(file_name) {
+                CACHE_CONTROL_IMMUTABLE
+            } else {
+                CACHE_CONTROL_NO_CACHE
+            }),
+        }
+    }
+}
+
+impl IntoResponse for WebUiFile {
+    fn into_response(self) -> Response {
+        (
+            [
+                (http::header::CONTENT_TYPE, self.content_type),
+                (http::header::CACHE_CONTROL, self.cache_control),
+            ],
+            self.content,
+        )
+            .into_response()
+    }
+}
+
+/// Whether the file name contains a content hash added by the bundler, e.g.
+/// `main-5RVBRN3K.js` or `styles.3ff695c00d717f2d.css`, so that the file never
+/// changes under the same name
+pub fn is_hashed_asset(file_name: &str) -> bool {
+    let Some((stem, _extension)) = file_name.rsplit_once('.') else {
+        return false;
+    };
+
+    stem.rsplit(['.', '-']).next().is_some_and(|hash| {
+        hash.len() >= 8
+            && hash != stem
+            && (hash
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+                || hash
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase()))
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Files of the web UI build, loaded into memory upon startup
+pub struct WebUiBundle {
+    files: HashMap<String, WebUiFile>,
+}
+
+impl WebUiBundle {
+    /// Loads the build from a directory or a `.tar` / `.tar.gz` archive. The
+    /// directory containing the top-most `index.html` becomes the root, so
+    /// the build can be nested, e.g. under `dist/browser/`.
+    pub fn load(path: &Path) -> Result<Self, InternalError> {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+
+        let files = if path.is_dir() {
+            read_directory(path)?
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            read_archive(flate2::read::GzDecoder::new(
+                std::fs::File::open(path).int_err()?,
+            ))?
+        } else if file_name.ends_with(".tar") {
+            read_archive(std::fs::File::open(path).int_err()?)?
+        } else {
+            return InternalError::bail(format!(
+                "Web UI must be a directory or a .tar / .tar.gz archive: {}",
+                path.display()
+            ));
+        };
+
+        let bundle = Self::from_files(files)?;
+        tracing::info!(
+            path = %path.display(),
+            files = bundle.files.len(),
+            "Loaded web UI",
+        );
+        Ok(bundle)
+    }
+
+    pub fn from_files(files: Vec<(String, Bytes)>) -> Result<Self, InternalError> {
+        let Some(root) = files
+            .iter()
+            .filter_map(|(path, _)| {
+                path.strip_suffix(INDEX_FILE)
+                    .filter(|root| root.is_empty() || root.ends_with('/'))
+            })
+            .min_by_key(|root| root.matches('/').count())
+            .map(ToString::to_string)
+        else {
+            return InternalError::bail(format!("Web UI doesn't contain {INDEX_FILE}"));
+        };
+
+        let files = files
+            .into_iter()
+            .filter_map(|(path, content)| {
+                let path = path.strip_prefix(&root)?.to_string();
+                let file = WebUiFile::new(&path, content);
+                Some((path, file))
+            })
+            .collect();
+
+        Ok(Self { files })
+    }
+
+    pub fn get(&self, path: &str) -> Option<&WebUiFile> {
+        self.files.get(path)
+    }
+
+    /// Resolves the file to serve for the request path. Unknown paths of the
+    /// page requests are routed by the UI itself, so they get `index.html`.
+    pub fn resolve(&self, path: &str, is_page_request: bool) -> Option<&WebUiFile> {
+        let path = path.trim_start_matches('/');
+
+        if path.is_empty() {
+            return self.get(INDEX_FILE);
+        }
+        self.get(path)
+            .or_else(|| is_page_request.then(|| self.get(INDEX_FILE)).flatten())
+    }
+}
+
+fn read_directory(dir: &Path) -> Result<Vec<(String, Bytes)>, InternalError> {
+    let mut files = Vec::new();
+
+    for entry in walkdir::WalkDir::new(dir).follow_links(true) {
+        let entry = entry.int_err()?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path().strip_prefix(dir).int_err()?;
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((path, Bytes::from(std::fs::read(entry.path()).int_err()?)));
+    }
+
+    Ok(files)
+}
+
+fn read_archive(reader: impl Read) -> Result<Vec<(String, Bytes)>, InternalError> {
+    let mut archive = tar::Archive::new(reader);
+    let mut files = Vec::new();
+
+    for entry in archive.entries().int_err()? {
+        let mut entry = entry.int_err()?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path().int_err()?.to_string_lossy().into_owned();
+        let path = path.trim_start_matches("./").to_string();
+
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).int_err()?;
+        files.push((path, Bytes::from(content)));
+    }
+
+    Ok(files)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serves the web UI for requests that didn't match any of the API routes
+pub async fn web_ui_handler(
+    Extension(bundle): Extension<Arc<WebUiBundle>>,
+    Extension(catalog): Extension<dill::Catalog>,
+    request: Request,
+) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return Handler::<_, ()>::call(unknown_fallback_handler, request, ()).await;
+    }
+
+    let path = request.uri().path();
+
+    if path == format!("/{RUNTIME_CONFIG_FILE}") {
+        return match runtime_config(&bundle, &catalog) {
+            Ok(config) => config.into_response(),
+            Err(err) => {
+                tracing::error!(error = ?err, error_msg = %err, "Failed to build UI runtime config");
+                http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        };
+    }
+
+    // Navigation requests of browsers accept HTML, unlike the requests of
+    // scripts and styles
+    let is_page_request = request
+        .headers()
+        .get(http::header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/html"));
+
+    match bundle.resolve(path, is_page_request) {
+        Some(file) => file.clone().into_response(),
+        None => Handler::<_, ()>::call(unknown_fallback_handler, request, ()).await,
+    }
+}
+
+/// Points the UI to the API of this node, preserving other settings of the
+/// runtime config of the build
+fn runtime_config(
+    bundle: &WebUiBundle,
+    catalog: &dill::Catalog,
+) -> Result<WebUiFile, InternalError> {
+    let server_url_config = catalog.get_one::<ServerUrlConfig>().int_err()?;
+    let base_url_rest = &server_url_config.protocols.base_url_rest;
+
+    let mut config = bundle
+        .get(RUNTIME_CONFIG_FILE)
+        .and_then(|file| serde_json::from_slice::<serde_json::Value>(&file.content).ok())
+        .and_then(|value| value.as_object().cloned())
+        .unwrap_or_default();
+
+    config.insert(
+        "apiServerGqlUrl".to_string(),
+        base_url_rest.join("graphql").int_err()?.to_string().into(),
+    );
+    config.insert(
+        "apiServerHttpUrl".to_string(),
+        base_url_rest.as_str().trim_end_matches('/').into(),
+    );
+
+    Ok(WebUiFile::new(
+        RUNTIME_CONFIG_FILE,
+        Bytes::from(serde_json::to_vec(&config).int_err()?),
+    ))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
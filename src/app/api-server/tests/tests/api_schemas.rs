@@ -8,6 +8,7 @@
 // by the Apache License, Version 2.0.
 
 use kamu::domain::TenancyConfig;
+use kamu_api_server::config::HttpConfig;
 use kamu_api_server::ui_configuration::UIConfiguration;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -37,11 +38,15 @@ async fn get_openapi_schema(tenancy_config: TenancyConfig) -> String {
     let (server, addr, _) = kamu_api_server::http_server::build_server(
         "127.0.0.1".parse().unwrap(),
         None,
+        None,
         dill::Catalog::builder().build(),
         tenancy_config,
         UIConfiguration::default(),
         None,
         None,
+        None,
+        &HttpConfig::default(),
+        true,
     )
     .await
     .unwrap();
@@ -9,5 +9,24 @@
 
 mod api_schemas;
 mod notifiers;
+mod test_admin_server;
+mod test_agent_supervisor;
+mod test_client_cert_auth;
+mod test_client_ip;
 mod test_config;
 mod test_di_graph;
+mod test_http_caching;
+mod test_http_security;
+mod test_http_serve;
+mod test_listener;
+mod test_maintenance;
+mod test_oracle;
+mod test_rate_limit;
+mod test_read_only;
+mod test_readiness;
+mod test_request_id;
+mod test_request_limits;
+mod test_run_roles;
+mod test_tenant_domains;
+mod test_tls;
+mod test_web_ui;
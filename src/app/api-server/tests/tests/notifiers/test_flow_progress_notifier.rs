@@ -37,6 +37,8 @@ use kamu_accounts_services::{
     PredefinedAccountsRegistrator,
     UpdateAccountUseCaseImpl,
 };
+use kamu_api_server::flow_request_id::{FlowRequestIdRepository, InMemoryFlowRequestIdRepository};
+use kamu_api_server::request_id::RequestId;
 use kamu_api_server::{FLOW_FAILED_SUBJECT, FlowProgressNotifier};
 use kamu_auth_rebac_inmem::InMemoryRebacRepository;
 use kamu_auth_rebac_services::{
@@ -86,6 +88,43 @@ async fn test_failed_flow_sends_email() {
             .body
             .contains("href=\"http://platform.example.com/test-dataset/flow-details/0/history\"")
     );
+    assert!(!flow_failed_email.body.contains("Request ID"));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_failed_manual_flow_email_contains_request_id() {
+    let harness = FlowProgressNotifierHarness::new().await;
+
+    let dataset_id = odf::DatasetID::new_seeded_ed25519(b"test-dataset");
+    let dataset_name = odf::DatasetName::new_unchecked("test-dataset");
+    harness.make_dataset(&dataset_id, &dataset_name).await;
+
+    let initiator_account_id =
+        odf::AccountID::new_seeded_ed25519(DEFAULT_ACCOUNT_NAME_STR.as_bytes());
+    let request_id = RequestId::new_random();
+
+    harness
+        .send_flow_failed_with_request_id(
+            &dataset_id,
+            FlowActivationCause::Manual(FlowActivationCauseManual {
+                activation_time: Utc::now(),
+                initiator_account_id,
+            }),
+            Some(&request_id),
+        )
+        .await;
+
+    let emails = harness.fake_email_sender.get_recorded_emails();
+    assert_eq!(emails.len(), 1);
+
+    let body = &emails.first().unwrap().body;
+    assert!(body.contains("Request ID"));
+    assert!(body.contains(&format!(
+        "<span class=\"code\">{}</span>",
+        request_id.as_str()
+    )));
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -106,6 +145,7 @@ impl FlowProgressNotifierHarness {
         std::fs::create_dir(&datasets_dir).unwrap();
 
         b.add::<FlowProgressNotifier>()
+            .add::<InMemoryFlowRequestIdRepository>()
             .add_value(TenancyConfig::SingleTenant)
             .add_builder(messaging_outbox::OutboxImmediateImpl::builder(
                 messaging_outbox::ConsumerFilter::AllConsumers,
@@ -188,10 +228,37 @@ impl FlowProgressNotifierHarness {
     }
 
     async fn send_flow_failed(&self, dataset_id: &DatasetID) {
+        self.send_flow_failed_with_request_id(
+            dataset_id,
+            FlowActivationCause::AutoPolling(FlowActivationCauseAutoPolling {
+                activation_time: Utc::now(),
+            }),
+            None,
+        )
+        .await;
+    }
+
+    /// Fails the flow that was triggered by the request with the specified ID
+    async fn send_flow_failed_with_request_id(
+        &self,
+        dataset_id: &DatasetID,
+        activation_cause: FlowActivationCause,
+        request_id: Option<&RequestId>,
+    ) {
         let flow_event_store = self.catalog.get_one::<dyn FlowEventStore>().unwrap();
         let flow_id = flow_event_store.new_flow_id().await.unwrap();
 
-        let (mut flow, task_id) = self.create_and_prepare_flow(dataset_id, flow_id);
+        if let Some(request_id) = request_id {
+            self.catalog
+                .get_one::<dyn FlowRequestIdRepository>()
+                .unwrap()
+                .save_request_id(flow_id, request_id)
+                .await
+                .unwrap();
+        }
+
+        let (mut flow, task_id) =
+            self.create_and_prepare_flow(dataset_id, flow_id, activation_cause);
 
         flow.on_task_finished(
             Utc::now(),
@@ -217,14 +284,17 @@ impl FlowProgressNotifierHarness {
             .unwrap();
     }
 
-    fn create_and_prepare_flow(&self, dataset_id: &DatasetID, flow_id: FlowID) -> (Flow, TaskID) {
+    fn create_and_prepare_flow(
+        &self,
+        dataset_id: &DatasetID,
+        flow_id: FlowID,
+        activation_cause: FlowActivationCause,
+    ) -> (Flow, TaskID) {
         let mut flow = Flow::new(
             Utc::now(),
             flow_id,
             kamu_adapter_flow_dataset::ingest_dataset_binding(dataset_id),
-            kamu_flow_system::FlowActivationCause::AutoPolling(FlowActivationCauseAutoPolling {
-                activation_time: Utc::now(),
-            }),
+            activation_cause,
             None,
             None,
         );
@@ -0,0 +1,49 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use kamu_api_server::admin_server::system_router;
+use kamu_api_server::readiness::ReadinessAudience;
+use tower::ServiceExt as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_metrics_require_bearer_token() {
+    let catalog = dill::CatalogBuilder::new()
+        .add_value(prometheus::Registry::new())
+        .build();
+    let router = system_router(Some("s3cr3t"), ReadinessAudience::Internal)
+        .layer(axum::extract::Extension(catalog));
+
+    for (authorization, expected_status) in [
+        (None, http::StatusCode::UNAUTHORIZED),
+        (Some("Bearer wrong"), http::StatusCode::UNAUTHORIZED),
+        (
+            Some("Bearer s3cr3t-but-longer"),
+            http::StatusCode::UNAUTHORIZED,
+        ),
+        (Some("s3cr3t"), http::StatusCode::UNAUTHORIZED),
+        (Some("Bearer s3cr3t"), http::StatusCode::OK),
+    ] {
+        let mut request = http::Request::get("/system/metrics");
+        if let Some(authorization) = authorization {
+            request = request.header(http::header::AUTHORIZATION, authorization);
+        }
+
+        let response = router
+            .clone()
+            .oneshot(request.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), expected_status, "{authorization:?}");
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,645 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_utils::BackgroundAgent;
+use graceful_shutdown::CancellationToken;
+use internal_error::InternalError;
+use kamu_api_server::agent_supervisor::*;
+use kamu_api_server::config::{
+    BackgroundAgentsConfig,
+    LeaderElectionConfig,
+    MaintenanceConfig,
+    ShutdownConfig,
+};
+use kamu_api_server::leader_election::{
+    LeaderElection,
+    LeaderElectionMetrics,
+    LeadershipLease,
+    NoOpLeaderElection,
+};
+use kamu_api_server::maintenance::MaintenanceMode;
+use time_source::SystemTimeSourceDefault;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_restart_delay_grows_exponentially() {
+    let mut policy = RestartPolicy::new(&BackgroundAgentsConfig {
+        restart_min_delay: "1s".parse().unwrap(),
+        restart_max_delay: "5s".parse().unwrap(),
+        failure_budget: 100,
+        failure_budget_window: "600s".parse().unwrap(),
+    });
+
+    let now = Instant::now();
+    let delays: Vec<_> = (0..5)
+        .map(|_| policy.on_failure(now, now).unwrap().as_secs())
+        .collect();
+
+    assert_eq!(delays, [1, 2, 4, 5, 5]);
+
+    // Agent that ran longer than the max delay starts from scratch
+    let later = now + Duration::from_secs(10);
+    assert_eq!(policy.on_failure(now, later), Some(Duration::from_secs(1)));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_failure_budget() {
+    let mut policy = RestartPolicy::new(&BackgroundAgentsConfig {
+        restart_min_delay: "1s".parse().unwrap(),
+        restart_max_delay: "60s".parse().unwrap(),
+        failure_budget: 2,
+        failure_budget_window: "600s".parse().unwrap(),
+    });
+
+    let start = Instant::now();
+    assert!(policy.on_failure(start, start).is_some());
+    assert!(
+        policy
+            .on_failure(start, start + Duration::from_secs(1))
+            .is_some()
+    );
+
+    // Failures outside of the window no longer count
+    let later = start + Duration::from_secs(700);
+    assert!(policy.on_failure(later, later).is_some());
+    assert!(policy.on_failure(later, later).is_some());
+    assert!(policy.on_failure(later, later).is_none());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_failed_agent_is_restarted_as_new_instance() {
+    let catalog = supervisor_catalog_builder(Some(Vec::new()), MaintenanceConfig::default())
+        .add::<NoOpLeaderElection>()
+        .add_value(AgentProbe::default())
+        .add::<FailingAgent>()
+        .add::<IdleAgent>()
+        .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+
+    let res = tokio::time::timeout(
+        Duration::from_secs(5),
+        supervisor.run(
+            catalog.clone(),
+            vec![FAILING_AGENT, IDLE_AGENT],
+            CancellationToken::new(),
+        ),
+    )
+    .await
+    .unwrap();
+    assert!(res.is_err());
+
+    // Initial run and two restarts within the budget, each with a new instance
+    let mut runs = probe.failing_runs.lock().unwrap().clone();
+    assert_eq!(runs.len(), 3);
+    runs.dedup();
+    assert_eq!(runs.len(), 3);
+
+    // Agent that exceeded its budget stops the rest
+    assert!(probe.idle_stopped.is_cancelled());
+    assert!(matches!(
+        catalog
+            .get_one::<BackgroundAgentsStatus>()
+            .unwrap()
+            .snapshot()
+            .get(FAILING_AGENT),
+        Some(AgentState::Failed { .. })
+    ));
+}
+
+#[test_log::test(tokio::test)]
+async fn test_graceful_agent_completes_iteration_on_shutdown() {
+    let catalog = supervisor_catalog_builder(Some(Vec::new()), MaintenanceConfig::default())
+        .add::<NoOpLeaderElection>()
+        .add_value(AgentProbe::default())
+        .add::<StoppableAgent>()
+        .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let run =
+        tokio::spawn(supervisor.run(catalog.clone(), vec![STOPPABLE_AGENT], shutdown.clone()));
+
+    probe.stoppable_started.cancelled().await;
+    shutdown.cancel();
+
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    // Agent was not interrupted in the middle of the iteration
+    assert!(probe.stoppable_finished.load(Ordering::SeqCst));
+    assert_eq!(
+        catalog
+            .get_one::<BackgroundAgentsStatus>()
+            .unwrap()
+            .snapshot()
+            .get(STOPPABLE_AGENT),
+        Some(&AgentState::Running)
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_graceful_agent_is_aborted_after_timeout() {
+    let catalog = supervisor_catalog_builder(Some(Vec::new()), MaintenanceConfig::default())
+        .add::<NoOpLeaderElection>()
+        .add_value(AgentProbe::default())
+        .add::<StuckAgent>()
+        .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let run = tokio::spawn(supervisor.run(catalog.clone(), vec![STUCK_AGENT], shutdown.clone()));
+
+    probe.stuck_started.cancelled().await;
+    shutdown.cancel();
+
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    assert!(probe.stuck_stopped.is_cancelled());
+    assert!(matches!(
+        catalog
+            .get_one::<BackgroundAgentsStatus>()
+            .unwrap()
+            .snapshot()
+            .get(STUCK_AGENT),
+        Some(AgentState::Failed { .. })
+    ));
+}
+
+#[test_log::test(tokio::test)]
+async fn test_singleton_agents_run_on_leader_only() {
+    let catalog = supervisor_catalog_builder(None, MaintenanceConfig::default())
+        .add::<FakeLeaderElection>()
+        .add_value(AgentProbe::default())
+        .add::<IdleAgent>()
+        .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let leader_election = catalog.get_one::<FakeLeaderElection>().unwrap();
+    let metrics = catalog.get_one::<LeaderElectionMetrics>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let run = tokio::spawn(supervisor.run(catalog.clone(), vec![IDLE_AGENT], shutdown.clone()));
+
+    wait_for_state(&catalog, IDLE_AGENT, &AgentState::Standby).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(probe.idle_runs.load(Ordering::SeqCst), 0);
+
+    leader_election.grant();
+    wait_for_state(&catalog, IDLE_AGENT, &AgentState::Running).await;
+    wait_until(|| probe.idle_runs.load(Ordering::SeqCst) == 1).await;
+    assert_eq!(metrics.is_leader.get(), 1);
+
+    // Agents are stopped when the leadership is lost and wait for the next term
+    leader_election.revoke();
+    wait_for_state(&catalog, IDLE_AGENT, &AgentState::Standby).await;
+    assert!(probe.idle_stopped.is_cancelled());
+    assert_eq!(metrics.is_leader.get(), 0);
+    assert_eq!(metrics.lost_num.get(), 1);
+
+    leader_election.grant();
+    wait_until(|| probe.idle_runs.load(Ordering::SeqCst) == 2).await;
+    assert_eq!(metrics.acquired_num.get(), 2);
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(metrics.is_leader.get(), 0);
+    assert_eq!(metrics.lost_num.get(), 1);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_singleton_agents_are_aborted_when_leadership_is_lost() {
+    let catalog = supervisor_catalog_builder(None, MaintenanceConfig::default())
+        .add::<FakeLeaderElection>()
+        .add_value(AgentProbe::default())
+        .add::<StuckAgent>()
+        .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let leader_election = catalog.get_one::<FakeLeaderElection>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let run = tokio::spawn(supervisor.run(catalog.clone(), vec![STUCK_AGENT], shutdown.clone()));
+
+    leader_election.grant();
+    probe.stuck_started.cancelled().await;
+
+    // Agent is not given the shutdown timeout to complete its iteration, as
+    // another replica may already be running it
+    leader_election.revoke();
+    tokio::time::timeout(Duration::from_millis(500), probe.stuck_stopped.cancelled())
+        .await
+        .unwrap();
+    wait_for_state(&catalog, STUCK_AGENT, &AgentState::Standby).await;
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_non_singleton_agents_run_without_leadership() {
+    let catalog = supervisor_catalog_builder(Some(Vec::new()), MaintenanceConfig::default())
+        .add::<FakeLeaderElection>()
+        .add_value(AgentProbe::default())
+        .add::<IdleAgent>()
+        .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let run = tokio::spawn(supervisor.run(catalog.clone(), vec![IDLE_AGENT], shutdown.clone()));
+
+    wait_for_state(&catalog, IDLE_AGENT, &AgentState::Running).await;
+    wait_until(|| probe.idle_runs.load(Ordering::SeqCst) == 1).await;
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maintenance_pauses_graceful_agents_only() {
+    let catalog = supervisor_catalog_builder(Some(Vec::new()), MaintenanceConfig::default())
+        .add::<NoOpLeaderElection>()
+        .add_value(AgentProbe::default())
+        .add::<StoppableAgent>()
+        .add::<IdleAgent>()
+        .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let maintenance = catalog.get_one::<MaintenanceMode>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let run = tokio::spawn(supervisor.run(
+        catalog.clone(),
+        vec![STOPPABLE_AGENT, IDLE_AGENT],
+        shutdown.clone(),
+    ));
+
+    probe.stoppable_started.cancelled().await;
+    wait_until(|| probe.idle_runs.load(Ordering::SeqCst) == 1).await;
+
+    maintenance.enable(None);
+    wait_for_state(&catalog, STOPPABLE_AGENT, &AgentState::Paused).await;
+    assert!(probe.stoppable_finished.load(Ordering::SeqCst));
+
+    // Agent that can't complete its iteration on request is not interrupted
+    wait_for_state(&catalog, IDLE_AGENT, &AgentState::Running).await;
+    assert!(!probe.idle_stopped.is_cancelled());
+
+    maintenance.disable();
+    wait_until(|| probe.stoppable_runs.load(Ordering::SeqCst) == 2).await;
+    wait_for_state(&catalog, STOPPABLE_AGENT, &AgentState::Running).await;
+    assert_eq!(probe.idle_runs.load(Ordering::SeqCst), 1);
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_maintenance_interrupts_agents_when_configured() {
+    let catalog = supervisor_catalog_builder(
+        Some(Vec::new()),
+        MaintenanceConfig {
+            interrupt_agents: true,
+            ..Default::default()
+        },
+    )
+    .add::<NoOpLeaderElection>()
+    .add_value(AgentProbe::default())
+    .add::<IdleAgent>()
+    .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let maintenance = catalog.get_one::<MaintenanceMode>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let run = tokio::spawn(supervisor.run(catalog.clone(), vec![IDLE_AGENT], shutdown.clone()));
+
+    wait_until(|| probe.idle_runs.load(Ordering::SeqCst) == 1).await;
+
+    maintenance.enable(None);
+    wait_for_state(&catalog, IDLE_AGENT, &AgentState::Paused).await;
+    assert!(probe.idle_stopped.is_cancelled());
+
+    maintenance.disable();
+    wait_until(|| probe.idle_runs.load(Ordering::SeqCst) == 2).await;
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_leader_keeps_leadership_in_maintenance() {
+    let catalog = supervisor_catalog_builder(None, MaintenanceConfig::default())
+        .add::<FakeLeaderElection>()
+        .add_value(AgentProbe::default())
+        .add::<StoppableAgent>()
+        .build();
+
+    let supervisor = catalog.get_one::<AgentSupervisor>().unwrap();
+    let leader_election = catalog.get_one::<FakeLeaderElection>().unwrap();
+    let metrics = catalog.get_one::<LeaderElectionMetrics>().unwrap();
+    let maintenance = catalog.get_one::<MaintenanceMode>().unwrap();
+    let probe = catalog.get_one::<AgentProbe>().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let run =
+        tokio::spawn(supervisor.run(catalog.clone(), vec![STOPPABLE_AGENT], shutdown.clone()));
+
+    leader_election.grant();
+    probe.stoppable_started.cancelled().await;
+
+    maintenance.enable(None);
+    wait_for_state(&catalog, STOPPABLE_AGENT, &AgentState::Paused).await;
+    assert_eq!(metrics.is_leader.get(), 1);
+
+    maintenance.disable();
+    wait_until(|| probe.stoppable_runs.load(Ordering::SeqCst) == 2).await;
+    assert_eq!(metrics.acquired_num.get(), 1);
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Helpers
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Supervisor dependencies without the leader election and the agents
+fn supervisor_catalog_builder(
+    singleton_agents: Option<Vec<String>>,
+    maintenance_config: MaintenanceConfig,
+) -> dill::CatalogBuilder {
+    let mut b = dill::CatalogBuilder::new();
+    b.add_value(BackgroundAgentsConfig {
+        restart_min_delay: "0s".parse().unwrap(),
+        restart_max_delay: "60s".parse().unwrap(),
+        failure_budget: 2,
+        failure_budget_window: "600s".parse().unwrap(),
+    })
+    .add_value(LeaderElectionConfig {
+        singleton_agents,
+        lock_key: 0,
+        check_interval: "1s".parse().unwrap(),
+    })
+    .add_value(ShutdownConfig {
+        drain_timeout: "1s".parse().unwrap(),
+        agents_timeout: "1s".parse().unwrap(),
+        outbox_flush_timeout: "1s".parse().unwrap(),
+    })
+    .add_value(LeaderElectionMetrics::new())
+    .add_value(maintenance_config)
+    .add::<SystemTimeSourceDefault>()
+    .add::<MaintenanceMode>()
+    .add_value(BackgroundAgentMetrics::new())
+    .add::<BackgroundAgentsStatus>()
+    .add::<AgentSupervisor>();
+    b
+}
+
+async fn wait_until(condition: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition() {
+        assert!(Instant::now() < deadline, "Condition was not met in time");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+async fn wait_for_state(catalog: &dill::Catalog, agent_name: &str, state: &AgentState) {
+    let status = catalog.get_one::<BackgroundAgentsStatus>().unwrap();
+    wait_until(|| status.snapshot().get(agent_name) == Some(state)).await;
+}
+
+/// Grants and revokes the leadership on request
+struct FakeLeaderElection {
+    metrics: Arc<LeaderElectionMetrics>,
+    granted: tokio::sync::Notify,
+    term: Mutex<CancellationToken>,
+}
+
+#[dill::component]
+#[dill::scope(dill::Singleton)]
+#[dill::interface(dyn LeaderElection)]
+impl FakeLeaderElection {
+    fn new(metrics: Arc<LeaderElectionMetrics>) -> Self {
+        Self {
+            metrics,
+            granted: tokio::sync::Notify::new(),
+            term: Mutex::new(CancellationToken::new()),
+        }
+    }
+
+    fn grant(&self) {
+        self.granted.notify_one();
+    }
+
+    fn revoke(&self) {
+        self.term.lock().unwrap().cancel();
+    }
+}
+
+#[async_trait::async_trait]
+impl LeaderElection for FakeLeaderElection {
+    async fn acquire_leadership(&self) -> Result<LeadershipLease, InternalError> {
+        self.granted.notified().await;
+
+        let lost = CancellationToken::new();
+        *self.term.lock().unwrap() = lost.clone();
+        Ok(LeadershipLease::new(lost, None, self.metrics.clone()))
+    }
+}
+
+const FAILING_AGENT: &str = "test.FailingAgent";
+const IDLE_AGENT: &str = "test.IdleAgent";
+const STOPPABLE_AGENT: &str = "test.StoppableAgent";
+const STUCK_AGENT: &str = "test.StuckAgent";
+
+#[derive(Default)]
+struct AgentProbe {
+    instances: AtomicUsize,
+    failing_runs: Mutex<Vec<usize>>,
+    idle_runs: AtomicUsize,
+    idle_stopped: CancellationToken,
+    stoppable_runs: AtomicUsize,
+    stoppable_started: CancellationToken,
+    stoppable_finished: AtomicBool,
+    stuck_started: CancellationToken,
+    stuck_stopped: CancellationToken,
+}
+
+/// Fails right away, remembering which instance was run
+struct FailingAgent {
+    probe: Arc<AgentProbe>,
+    instance: usize,
+}
+
+#[dill::component]
+#[dill::interface(dyn BackgroundAgent)]
+impl FailingAgent {
+    fn new(probe: Arc<AgentProbe>) -> Self {
+        let instance = probe.instances.fetch_add(1, Ordering::SeqCst);
+        Self { probe, instance }
+    }
+}
+
+#[async_trait::async_trait]
+impl BackgroundAgent for FailingAgent {
+    fn agent_name(&self) -> &'static str {
+        FAILING_AGENT
+    }
+
+    async fn run(&self) -> Result<(), InternalError> {
+        self.probe.failing_runs.lock().unwrap().push(self.instance);
+        Err(InternalError::new("Boom"))
+    }
+}
+
+/// Runs until it's interrupted
+struct IdleAgent {
+    probe: Arc<AgentProbe>,
+}
+
+#[dill::component]
+#[dill::interface(dyn BackgroundAgent)]
+impl IdleAgent {
+    fn new(probe: Arc<AgentProbe>) -> Self {
+        Self { probe }
+    }
+}
+
+#[async_trait::async_trait]
+impl BackgroundAgent for IdleAgent {
+    fn agent_name(&self) -> &'static str {
+        IDLE_AGENT
+    }
+
+    async fn run(&self) -> Result<(), InternalError> {
+        let _stopped = self.probe.idle_stopped.clone().drop_guard();
+        self.probe.idle_runs.fetch_add(1, Ordering::SeqCst);
+        futures::future::pending().await
+    }
+}
+
+/// Completes its current iteration once it's signalled to stop
+struct StoppableAgent {
+    probe: Arc<AgentProbe>,
+}
+
+#[dill::component]
+#[dill::interface(dyn GracefulBackgroundAgent)]
+impl StoppableAgent {
+    fn new(probe: Arc<AgentProbe>) -> Self {
+        Self { probe }
+    }
+}
+
+#[async_trait::async_trait]
+impl GracefulBackgroundAgent for StoppableAgent {
+    fn agent_name(&self) -> &'static str {
+        STOPPABLE_AGENT
+    }
+
+    async fn run(&self, stop: CancellationToken) -> Result<(), InternalError> {
+        self.probe.stoppable_runs.fetch_add(1, Ordering::SeqCst);
+        self.probe.stoppable_started.cancel();
+        stop.cancelled().await;
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        self.probe.stoppable_finished.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Ignores the signal to stop
+struct StuckAgent {
+    probe: Arc<AgentProbe>,
+}
+
+#[dill::component]
+#[dill::interface(dyn GracefulBackgroundAgent)]
+impl StuckAgent {
+    fn new(probe: Arc<AgentProbe>) -> Self {
+        Self { probe }
+    }
+}
+
+#[async_trait::async_trait]
+impl GracefulBackgroundAgent for StuckAgent {
+    fn agent_name(&self) -> &'static str {
+        STUCK_AGENT
+    }
+
+    async fn run(&self, _stop: CancellationToken) -> Result<(), InternalError> {
+        let _stopped = self.probe.stuck_stopped.clone().drop_guard();
+        self.probe.stuck_started.cancel();
+        futures::future::pending().await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,128 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use kamu_api_server::client_cert_auth::ClientCertAccountMapping;
+use kamu_api_server::config::ClientCertAccountConfig;
+use kamu_api_server::tls::ClientCertIdentity;
+use rustls::pki_types::CertificateDer;
+use rustls::pki_types::pem::PemObject as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn mapping(entries: &[(&str, &str)]) -> ClientCertAccountMapping {
+    ClientCertAccountMapping::from_config(
+        entries
+            .iter()
+            .map(|(identity, account_name)| ClientCertAccountConfig {
+                identity: identity.to_string(),
+                account_name: account_name.to_string(),
+            })
+            .collect(),
+    )
+    .unwrap()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_client_cert_mapping_resolve() {
+    let mapping = mapping(&[
+        ("etl.acme.com", "acme-etl"),
+        ("spiffe://acme.com/reporting", "acme-reporting"),
+    ]);
+
+    let by_cn = ClientCertIdentity {
+        common_name: Some("etl.acme.com".to_string()),
+        subject_alt_names: Vec::new(),
+    };
+    assert_eq!(
+        mapping.resolve(&by_cn).map(|name| name.as_str()),
+        Some("acme-etl")
+    );
+
+    let by_san = ClientCertIdentity {
+        common_name: Some("reporting".to_string()),
+        subject_alt_names: vec![
+            "reporting.acme.com".to_string(),
+            "spiffe://acme.com/reporting".to_string(),
+        ],
+    };
+    assert_eq!(
+        mapping.resolve(&by_san).map(|name| name.as_str()),
+        Some("acme-reporting")
+    );
+
+    let unknown = ClientCertIdentity {
+        common_name: Some("intruder".to_string()),
+        subject_alt_names: vec!["intruder.example.com".to_string()],
+    };
+    assert_eq!(mapping.resolve(&unknown), None);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_client_cert_mapping_invalid_account_name() {
+    let res = ClientCertAccountMapping::from_config(vec![ClientCertAccountConfig {
+        identity: "etl.acme.com".to_string(),
+        account_name: "not a valid name".to_string(),
+    }]);
+
+    assert!(res.is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_client_cert_identity_from_der() {
+    let mut cert_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    cert_path.push("tests/resources/tls/client.crt");
+    let cert = CertificateDer::from_pem_file(cert_path).unwrap();
+
+    let identity = ClientCertIdentity::from_der(&cert).unwrap();
+
+    // IP addresses are not supported as identities
+    assert_eq!(
+        identity,
+        ClientCertIdentity {
+            common_name: Some("etl.acme.com".to_string()),
+            subject_alt_names: vec![
+                "etl.acme.com".to_string(),
+                "spiffe://acme.com/etl".to_string(),
+                "etl@acme.com".to_string(),
+            ],
+        }
+    );
+    assert_eq!(
+        identity.names().collect::<Vec<_>>(),
+        [
+            "etl.acme.com",
+            "etl.acme.com",
+            "spiffe://acme.com/etl",
+            "etl@acme.com"
+        ]
+    );
+
+    let mapping = mapping(&[("spiffe://acme.com/etl", "acme-etl")]);
+    assert_eq!(
+        mapping.resolve(&identity).map(|name| name.as_str()),
+        Some("acme-etl")
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_client_cert_identity_from_invalid_der() {
+    let res = ClientCertIdentity::from_der(&CertificateDer::from(b"not a certificate".to_vec()));
+
+    assert!(res.is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
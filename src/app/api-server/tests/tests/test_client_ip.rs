@@ -0,0 +1,293 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use http::{HeaderMap, HeaderValue, Method};
+use http_body_util::BodyExt as _;
+use kamu_api_server::client_ip::{ClientIp, TrustedProxies, client_ip_middleware};
+use kamu_api_server::config::{IpFilterConfig, IpFilterRuleConfig};
+use kamu_api_server::ip_filter::{IpFilter, WritesDenied, ip_filter_middleware};
+use kamu_api_server::listener::{PeerAddr, ServerConnectInfo};
+use tower::ServiceExt as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn headers(values: &[(&'static str, &'static str)]) -> HeaderMap {
+    values
+        .iter()
+        .map(|(name, value)| {
+            (
+                http::HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_trusted_proxies_resolve() {
+    let proxies =
+        TrustedProxies::from_config(&["10.0.0.0/8".to_string(), "192.168.1.1".to_string()])
+            .unwrap();
+
+    let xff = headers(&[("x-forwarded-for", "203.0.113.7, 198.51.100.1, 10.1.2.3")]);
+
+    // Headers of untrusted peers are ignored
+    assert_eq!(
+        proxies.resolve("198.51.100.9".parse().unwrap(), &xff),
+        "198.51.100.9".parse::<std::net::IpAddr>().unwrap()
+    );
+
+    // Chain is walked until the first untrusted hop
+    assert_eq!(
+        proxies.resolve("192.168.1.1".parse().unwrap(), &xff),
+        "198.51.100.1".parse::<std::net::IpAddr>().unwrap()
+    );
+
+    // `Forwarded` takes precedence and supports quoted IPv6 with ports
+    let forwarded = headers(&[
+        (
+            "forwarded",
+            r#"for="[2001:db8::1]:4711";proto=https, for=10.0.0.2"#,
+        ),
+        ("x-forwarded-for", "203.0.113.7"),
+    ]);
+    assert_eq!(
+        proxies.resolve("10.0.0.1".parse().unwrap(), &forwarded),
+        "2001:db8::1".parse::<std::net::IpAddr>().unwrap()
+    );
+
+    // IPv6 and IPv4-mapped addresses with and without ports
+    let xff = headers(&[(
+        "x-forwarded-for",
+        "2001:db8::7, [2001:db8::8]:4711, [::ffff:198.51.100.1]:80, 10.1.2.3:8080",
+    )]);
+    assert_eq!(
+        proxies.resolve("10.0.0.1".parse().unwrap(), &xff),
+        "198.51.100.1".parse::<std::net::IpAddr>().unwrap()
+    );
+    let xff = headers(&[("x-forwarded-for", "2001:db8::7, [2001:db8::8]:4711")]);
+    assert_eq!(
+        proxies.resolve("::ffff:10.0.0.1".parse().unwrap(), &xff),
+        "2001:db8::8".parse::<std::net::IpAddr>().unwrap()
+    );
+    let forwarded = headers(&[("forwarded", r#"for="[2001:db8::9]:_port""#)]);
+    assert_eq!(
+        proxies.resolve("10.0.0.1".parse().unwrap(), &forwarded),
+        "2001:db8::9".parse::<std::net::IpAddr>().unwrap()
+    );
+
+    // Obfuscated identifiers stop at the last known hop
+    let obfuscated = headers(&[("forwarded", "for=_hidden, for=10.0.0.2")]);
+    assert_eq!(
+        proxies.resolve("10.0.0.1".parse().unwrap(), &obfuscated),
+        "10.0.0.2".parse::<std::net::IpAddr>().unwrap()
+    );
+
+    assert!(TrustedProxies::from_config(&["not-an-ip".to_string()]).is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_ip_filter() {
+    let filter = IpFilter::from_config(&IpFilterConfig {
+        global: Some(IpFilterRuleConfig {
+            allow: vec![],
+            deny: vec!["203.0.113.0/24".to_string()],
+        }),
+        admin: Some(IpFilterRuleConfig {
+            allow: vec!["10.0.0.0/8".to_string()],
+            deny: vec![],
+        }),
+        write: Some(IpFilterRuleConfig {
+            allow: vec!["10.0.0.0/8".to_string(), "198.51.100.1".to_string()],
+            deny: vec!["10.6.6.6".to_string()],
+        }),
+    })
+    .unwrap();
+
+    let internal = "10.0.0.1".parse().unwrap();
+    let partner = "198.51.100.1".parse().unwrap();
+    let public = "198.51.100.2".parse().unwrap();
+    let banned = "203.0.113.5".parse().unwrap();
+
+    let tail = ("/kamu/covid19/tail", "/{account_name}/{dataset_name}/tail");
+    let push = ("/kamu/covid19/push", "/{account_name}/{dataset_name}/push");
+    let ingest = (
+        "/kamu/covid19/ingest",
+        "/{account_name}/{dataset_name}/ingest",
+    );
+    let query = ("/query", "/query");
+    let graphql = ("/graphql", "/graphql");
+    let health = ("/system/health", "/system/health");
+    let metrics = ("/system/metrics", "/system/metrics");
+    // Dataset named like a write route
+    let metadata = (
+        "/kamu/push/metadata",
+        "/{account_name}/{dataset_name}/metadata",
+    );
+
+    let is_allowed = |ip, method: Method, (path, route): (&str, &str)| {
+        filter.is_allowed(ip, &method, path, Some(route))
+    };
+
+    assert!(is_allowed(public, Method::GET, tail));
+    assert!(is_allowed(public, Method::POST, query));
+    assert!(is_allowed(public, Method::GET, metadata));
+    assert!(!is_allowed(banned, Method::GET, tail));
+
+    assert!(is_allowed(public, Method::GET, health));
+    assert!(!is_allowed(public, Method::GET, metrics));
+    assert!(!is_allowed(partner, Method::GET, metrics));
+    assert!(is_allowed(internal, Method::GET, metrics));
+
+    assert!(!is_allowed(public, Method::GET, push));
+    assert!(!is_allowed(public, Method::POST, ingest));
+    assert!(is_allowed(partner, Method::POST, ingest));
+    assert!(is_allowed(internal, Method::POST, ingest));
+    assert!(!is_allowed(
+        "10.6.6.6".parse().unwrap(),
+        Method::POST,
+        ingest
+    ));
+
+    // GraphQL queries are allowed, while mutations are rejected by the handler
+    assert!(is_allowed(public, Method::POST, graphql));
+    assert!(!filter.allows_writes(public));
+    assert!(filter.allows_writes(partner));
+    assert!(filter.allows_writes(internal));
+    assert!(!filter.allows_writes("10.6.6.6".parse().unwrap()));
+
+    // Requests that were not routed are only reads by method
+    assert!(filter.is_allowed(public, &Method::GET, "/unknown", None));
+    assert!(!filter.is_allowed(public, &Method::POST, "/unknown", None));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_ip_filter_middleware_marks_graphql_requests() {
+    let ip_filter = IpFilter::from_config(&IpFilterConfig {
+        global: None,
+        admin: None,
+        write: Some(IpFilterRuleConfig {
+            allow: vec!["10.0.0.0/8".to_string()],
+            deny: vec![],
+        }),
+    })
+    .unwrap();
+
+    let writes_denied = |request: axum::extract::Request| async move {
+        request
+            .extensions()
+            .get::<WritesDenied>()
+            .is_some()
+            .to_string()
+    };
+    let router = axum::Router::new()
+        .route("/graphql", axum::routing::post(writes_denied))
+        .route(
+            "/{account_name}/{dataset_name}/ingest",
+            axum::routing::post(writes_denied),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(ip_filter),
+            ip_filter_middleware,
+        ));
+
+    let post = async |ip: &str, path: &str| {
+        let mut request = http::Request::post(path)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ClientIp(ip.parse().unwrap()));
+
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    };
+
+    assert_eq!(
+        post("198.51.100.1", "/graphql").await,
+        (http::StatusCode::OK, "true".to_string())
+    );
+    assert_eq!(
+        post("10.0.0.1", "/graphql").await,
+        (http::StatusCode::OK, "false".to_string())
+    );
+    assert_eq!(
+        post("198.51.100.1", "/kamu/covid19/ingest").await.0,
+        http::StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        post("10.0.0.1", "/kamu/covid19/ingest").await,
+        (http::StatusCode::OK, "false".to_string())
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_client_ip_of_unix_socket_peers() {
+    let router = axum::Router::new()
+        .route(
+            "/",
+            axum::routing::get(|request: axum::extract::Request| async move {
+                ClientIp::from_extensions(request.extensions())
+                    .map_or_else(|| "none".to_string(), |ip| ip.to_string())
+            }),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(TrustedProxies::default()),
+            client_ip_middleware,
+        ));
+
+    let get = async |peer: PeerAddr, forwarded_for: Option<&'static str>| {
+        let mut request = http::Request::get("/");
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header("x-forwarded-for", forwarded_for);
+        }
+        let mut request = request.body(axum::body::Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(axum::extract::ConnectInfo(ServerConnectInfo {
+                remote_addr: peer,
+                client_cert: None,
+            }));
+
+        let response = router.clone().oneshot(request).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    };
+
+    // Local processes are not reported as loopback clients
+    assert_eq!(get(PeerAddr::Unix, None).await, "none");
+
+    // Proxy in front of the socket is trusted to forward the client IP
+    assert_eq!(
+        get(PeerAddr::Unix, Some("198.51.100.1, 203.0.113.7")).await,
+        "203.0.113.7"
+    );
+
+    // Forwarding headers of the untrusted TCP peers are ignored
+    assert_eq!(
+        get(
+            PeerAddr::Tcp("127.0.0.1:4711".parse().unwrap()),
+            Some("203.0.113.7")
+        )
+        .await,
+        "127.0.0.1"
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,178 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use kamu_api_server::http_caching::{content_address, immutable_object_middleware};
+use kamu_api_server::http_compression::is_compressible_content_type;
+use tower::ServiceExt as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const BLOCK_HASH: &str = "f16203a5e1d4f6f1e8c6d5b4a3928170615243342516f708192a3b4c5d6e7f8091a";
+
+#[test]
+fn test_content_address() {
+    const BLOCK_ROUTE: &str = "/{account_name}/{dataset_name}/blocks/{block_hash}";
+
+    assert_eq!(
+        content_address(BLOCK_ROUTE, &format!("/kamu/covid19/blocks/{BLOCK_HASH}")),
+        Some(BLOCK_HASH)
+    );
+    assert_eq!(
+        content_address(
+            "/{dataset_name}/data/{physical_hash}",
+            &format!("/covid19/data/{BLOCK_HASH}")
+        ),
+        Some(BLOCK_HASH)
+    );
+    assert_eq!(
+        content_address(
+            "/checkpoints/{physical_hash}",
+            &format!("/checkpoints/{BLOCK_HASH}")
+        ),
+        Some(BLOCK_HASH)
+    );
+
+    assert_eq!(
+        content_address(
+            "/{account_name}/{dataset_name}/refs/{reference}",
+            "/kamu/covid19/refs/head"
+        ),
+        None
+    );
+    assert_eq!(
+        content_address(
+            "/{account_name}/{dataset_name}/metadata",
+            "/kamu/covid19/metadata"
+        ),
+        None
+    );
+    assert_eq!(
+        content_address(BLOCK_ROUTE, "/kamu/covid19/blocks/../secret"),
+        None
+    );
+
+    // Datasets and accounts named like the segments of the object routes
+    assert_eq!(
+        content_address("/{dataset_name}/tail", "/blocks/tail"),
+        None
+    );
+    assert_eq!(
+        content_address("/{account_name}/{dataset_name}/tail", "/data/blocks/tail"),
+        None
+    );
+    assert_eq!(
+        content_address(BLOCK_ROUTE, &format!("/blocks/blocks/blocks/{BLOCK_HASH}")),
+        Some(BLOCK_HASH)
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_compressible_content_types() {
+    assert!(is_compressible_content_type("application/json"));
+    assert!(is_compressible_content_type(
+        "application/graphql-response+json; charset=utf-8"
+    ));
+    assert!(is_compressible_content_type("text/csv"));
+    assert!(is_compressible_content_type("application/x-ndjson"));
+
+    assert!(!is_compressible_content_type("application/octet-stream"));
+    assert!(!is_compressible_content_type(
+        "application/vnd.apache.parquet"
+    ));
+    assert!(!is_compressible_content_type("text/event-stream"));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_immutable_object_conditional_request() {
+    let block_reads = Arc::new(AtomicUsize::new(0));
+    let router = axum::Router::new()
+        .route(
+            "/{account_name}/{dataset_name}/blocks/{block_hash}",
+            axum::routing::get({
+                let block_reads = block_reads.clone();
+                move || async move {
+                    block_reads.fetch_add(1, Ordering::SeqCst);
+                    "block"
+                }
+            }),
+        )
+        .route(
+            "/{account_name}/{dataset_name}/refs/{reference}",
+            axum::routing::get(|| async { "head" }),
+        )
+        .route(
+            "/{dataset_name}/tail",
+            axum::routing::get(|| async { "tail" }),
+        )
+        .layer(axum::middleware::from_fn(immutable_object_middleware));
+
+    let get = |path: String, if_none_match: Option<&str>| {
+        let mut request = http::Request::get(path);
+        if let Some(if_none_match) = if_none_match {
+            request = request.header(http::header::IF_NONE_MATCH, if_none_match);
+        }
+        router
+            .clone()
+            .oneshot(request.body(axum::body::Body::empty()).unwrap())
+    };
+    let block_path = format!("/kamu/covid19/blocks/{BLOCK_HASH}");
+    let etag = format!("\"{BLOCK_HASH}\"");
+
+    let response = get(block_path.clone(), None).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(response.headers()[http::header::ETAG], etag.as_str());
+    // Not known to belong to a public dataset
+    assert_eq!(
+        response.headers()[http::header::CACHE_CONTROL],
+        "private, max-age=31536000, immutable"
+    );
+    assert_eq!(block_reads.load(Ordering::SeqCst), 1);
+
+    // Answered without reading the object
+    let response = get(block_path.clone(), Some(&format!("\"other\", W/{etag}")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[http::header::ETAG], etag.as_str());
+    assert_eq!(
+        response.headers()[http::header::CACHE_CONTROL],
+        "private, max-age=31536000, immutable"
+    );
+    assert_eq!(block_reads.load(Ordering::SeqCst), 1);
+
+    // Requires the object to exist
+    let response = get(block_path.clone(), Some("*")).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_MODIFIED);
+    assert_eq!(block_reads.load(Ordering::SeqCst), 2);
+
+    let response = get(block_path, Some("\"other\"")).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(block_reads.load(Ordering::SeqCst), 3);
+
+    // References are mutable
+    let response = get("/kamu/covid19/refs/head".to_string(), Some("*"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert!(!response.headers().contains_key(http::header::ETAG));
+
+    // Dataset named like the segment of the object routes
+    let response = get("/blocks/tail".to_string(), Some("*")).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert!(!response.headers().contains_key(http::header::ETAG));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
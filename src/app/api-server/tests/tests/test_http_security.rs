@@ -0,0 +1,228 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use kamu_api_server::config::{CorsConfig, FrameOptions, SecurityHeadersConfig};
+use kamu_api_server::http_security::{
+    SecurityHeaders,
+    build_cors_layer,
+    security_headers_middleware,
+};
+use tower::ServiceExt as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const ORIGIN: &str = "https://platform.example.com";
+
+fn cors_router(config: &CorsConfig) -> axum::Router {
+    axum::Router::new()
+        .route("/graphql", axum::routing::post(|| async { "data" }))
+        .layer(build_cors_layer(config).unwrap())
+}
+
+fn preflight(origin: &str, method: &str) -> http::Request<axum::body::Body> {
+    http::Request::options("/graphql")
+        .header(http::header::ORIGIN, origin)
+        .header(http::header::ACCESS_CONTROL_REQUEST_METHOD, method)
+        .header(http::header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+        .body(axum::body::Body::empty())
+        .unwrap()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_cors_rejects_invalid_config() {
+    let res = build_cors_layer(&CorsConfig {
+        allowed_origins: vec![format!("{ORIGIN}\r\nX-Injected: 1")],
+        ..Default::default()
+    });
+    assert!(res.is_err());
+
+    let res = build_cors_layer(&CorsConfig {
+        allowed_methods: vec!["GET POST".to_string()],
+        ..Default::default()
+    });
+    assert!(res.is_err());
+
+    let res = build_cors_layer(&CorsConfig {
+        allowed_headers: vec!["x-header: value".to_string()],
+        ..Default::default()
+    });
+    assert!(res.is_err());
+
+    // Browsers ignore credentials with wildcards
+    let res = build_cors_layer(&CorsConfig {
+        allowed_origins: vec![ORIGIN.to_string()],
+        allowed_headers: vec!["*".to_string()],
+        allow_credentials: true,
+        ..Default::default()
+    });
+    let err = format!("{:?}", res.unwrap_err());
+    assert!(err.contains("cannot be allowed together with wildcard"));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_cors_preflight() {
+    let router = cors_router(&CorsConfig {
+        allowed_origins: vec![ORIGIN.to_string()],
+        allowed_methods: vec!["get".to_string(), "post".to_string()],
+        allowed_headers: vec!["content-type".to_string(), "authorization".to_string()],
+        allow_credentials: true,
+        max_age: Some("10m".parse().unwrap()),
+    });
+
+    let response = router
+        .clone()
+        .oneshot(preflight(ORIGIN, "POST"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(headers[http::header::ACCESS_CONTROL_ALLOW_ORIGIN], ORIGIN);
+    assert_eq!(
+        headers[http::header::ACCESS_CONTROL_ALLOW_METHODS],
+        "GET,POST"
+    );
+    assert_eq!(
+        headers[http::header::ACCESS_CONTROL_ALLOW_HEADERS],
+        "content-type,authorization"
+    );
+    assert_eq!(
+        headers[http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS],
+        "true"
+    );
+    assert_eq!(headers[http::header::ACCESS_CONTROL_MAX_AGE], "600");
+
+    // Unknown origins don't get permission to read the response
+    let response = router
+        .clone()
+        .oneshot(preflight("https://evil.example.com", "POST"))
+        .await
+        .unwrap();
+    assert!(
+        !response
+            .headers()
+            .contains_key(http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+    );
+
+    let response = router
+        .oneshot(
+            http::Request::post("/graphql")
+                .header(http::header::ORIGIN, ORIGIN)
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(
+        response.headers()[http::header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        ORIGIN
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_cors_preflight_defaults() {
+    let router = cors_router(&CorsConfig::default());
+
+    let response = router
+        .oneshot(preflight("https://any.example.com", "POST"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(headers[http::header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    assert_eq!(
+        headers[http::header::ACCESS_CONTROL_ALLOW_METHODS],
+        "GET,POST"
+    );
+    assert_eq!(headers[http::header::ACCESS_CONTROL_ALLOW_HEADERS], "*");
+    assert!(!headers.contains_key(http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_security_headers_reject_header_injection() {
+    let res = SecurityHeaders::from_config(&SecurityHeadersConfig {
+        content_security_policy: Some("default-src 'self'\r\nSet-Cookie: session=1".to_string()),
+        ..Default::default()
+    });
+
+    assert!(format!("{:?}", res.unwrap_err()).contains("Invalid Content-Security-Policy"));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_security_headers_middleware() {
+    let security_headers = SecurityHeaders::from_config(&SecurityHeadersConfig {
+        hsts_max_age: Some("8760h".parse().unwrap()),
+        hsts_include_subdomains: true,
+        content_type_nosniff: true,
+        frame_options: Some(FrameOptions::Deny),
+        content_security_policy: Some("default-src 'self'".to_string()),
+    })
+    .unwrap();
+
+    let router = axum::Router::new()
+        .route("/", axum::routing::get(|| async { "ui" }))
+        .route(
+            "/embed",
+            axum::routing::get(|| async {
+                ([(http::header::X_FRAME_OPTIONS, "SAMEORIGIN")], "widget")
+            }),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(security_headers),
+            security_headers_middleware,
+        ));
+
+    let get = |path: &str| {
+        router.clone().oneshot(
+            http::Request::get(path)
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+    };
+
+    let response = get("/").await.unwrap();
+    let headers = response.headers();
+    assert_eq!(
+        headers[http::header::STRICT_TRANSPORT_SECURITY],
+        "max-age=31536000; includeSubDomains"
+    );
+    assert_eq!(headers[http::header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+    assert_eq!(headers[http::header::X_FRAME_OPTIONS], "DENY");
+    assert_eq!(
+        headers[http::header::CONTENT_SECURITY_POLICY],
+        "default-src 'self'"
+    );
+
+    // Headers set by the handler take precedence
+    let response = get("/embed").await.unwrap();
+    assert_eq!(
+        response.headers()[http::header::X_FRAME_OPTIONS],
+        "SAMEORIGIN"
+    );
+
+    assert!(
+        SecurityHeaders::from_config(&SecurityHeadersConfig::default())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
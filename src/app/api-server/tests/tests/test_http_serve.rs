@@ -0,0 +1,75 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use kamu_api_server::http_serve::{HttpServeOptions, HttpServer};
+use kamu_api_server::listener::{ListenerAddr, ServerListener};
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const IDLE_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[test_log::test(tokio::test)]
+async fn test_idle_timeout_does_not_interrupt_requests() {
+    let router = axum::Router::new().route(
+        "/slow",
+        axum::routing::get(|| async {
+            tokio::time::sleep(IDLE_TIMEOUT * 3).await;
+            "done"
+        }),
+    );
+
+    let listener = ServerListener::bind("127.0.0.1:0".parse().unwrap(), None)
+        .await
+        .unwrap();
+    let ListenerAddr::Tcp(addr) = listener.local_addr().clone() else {
+        unreachable!()
+    };
+    let server = HttpServer::new(
+        listener,
+        router,
+        HttpServeOptions {
+            header_read_timeout: Duration::from_secs(5),
+            idle_timeout: Some(IDLE_TIMEOUT),
+        },
+    );
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let server_task = tokio::spawn(server.with_graceful_shutdown(async {
+        stop_rx.await.ok();
+    }));
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+
+    let mut response = Vec::new();
+    let mut buf = [0; 1024];
+    while !response.ends_with(b"done") {
+        let len = stream.read(&mut buf).await.unwrap();
+        assert_ne!(len, 0, "Connection closed before the response was sent");
+        response.extend_from_slice(&buf[..len]);
+    }
+    assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+
+    // Connection that stays idle between the requests is closed
+    let len = tokio::time::timeout(IDLE_TIMEOUT * 5, stream.read(&mut buf))
+        .await
+        .expect("Idle connection was not closed")
+        .unwrap_or(0);
+    assert_eq!(len, 0);
+
+    stop_tx.send(()).unwrap();
+    server_task.await.unwrap().unwrap();
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,178 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#![cfg(unix)]
+
+use std::os::fd::AsFd as _;
+use std::os::unix::fs::PermissionsExt as _;
+
+use kamu_api_server::config::{ListenerConfig, UnixListenerConfig};
+use kamu_api_server::listener::{ListenerAddr, PeerAddr, ServerListener};
+use kamu_api_server::socket_activation::{ListenFds, ensure_listening_stream};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_unix_listener() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("api-server.sock");
+
+    // Socket left by the previous run is replaced
+    std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+    let config = ListenerConfig::Unix(UnixListenerConfig {
+        path: path.clone(),
+        mode: Some("660".to_string()),
+    });
+    let mut listener =
+        ServerListener::bind_configured("127.0.0.1:0".parse().unwrap(), Some(&config), None)
+            .await
+            .unwrap();
+
+    assert_eq!(
+        listener.local_addr(),
+        &ListenerAddr::Unix(Some(path.clone()))
+    );
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+        0o660
+    );
+
+    let _client = tokio::net::UnixStream::connect(&path).await.unwrap();
+    let (stream, remote_addr) = axum::serve::Listener::accept(&mut listener).await;
+
+    assert_eq!(remote_addr, PeerAddr::Unix);
+    assert_eq!(stream.remote_addr(), remote_addr);
+
+    // Socket is bound in a private directory that doesn't outlive the binding
+    assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 1);
+
+    drop(listener);
+    assert!(!path.exists());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_unix_listener_socket_in_use() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("api-server.sock");
+
+    let config = ListenerConfig::Unix(UnixListenerConfig {
+        path: path.clone(),
+        mode: None,
+    });
+    let listener =
+        ServerListener::bind_configured("127.0.0.1:0".parse().unwrap(), Some(&config), None)
+            .await
+            .unwrap();
+
+    // Socket of the running instance is not replaced
+    assert!(
+        ServerListener::bind_configured("127.0.0.1:0".parse().unwrap(), Some(&config), None)
+            .await
+            .is_err()
+    );
+    assert!(path.exists());
+
+    // Socket that replaced the one of the listener is not removed
+    std::fs::remove_file(&path).unwrap();
+    let _other = std::os::unix::net::UnixListener::bind(&path).unwrap();
+    drop(listener);
+    assert!(path.exists());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_incoming_stops_accepting_on_signal() {
+    use futures::StreamExt as _;
+    use tokio::io::AsyncReadExt as _;
+
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("api-server.sock");
+
+    let config = ListenerConfig::Unix(UnixListenerConfig {
+        path: path.clone(),
+        mode: None,
+    });
+    let listener =
+        ServerListener::bind_configured("127.0.0.1:0".parse().unwrap(), Some(&config), None)
+            .await
+            .unwrap();
+
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let mut incoming = std::pin::pin!(listener.into_incoming(async {
+        stop_rx.await.ok();
+    }));
+
+    let _accepted = tokio::net::UnixStream::connect(&path).await.unwrap();
+    incoming.next().await.unwrap().unwrap();
+
+    // Connection that was not picked up by the server when shutdown begins
+    let mut pending = tokio::net::UnixStream::connect(&path).await.unwrap();
+
+    stop_tx.send(()).unwrap();
+    assert!(incoming.next().await.is_none());
+    assert!(!path.exists());
+
+    let mut buf = [0; 1];
+    let read = tokio::time::timeout(std::time::Duration::from_secs(5), pending.read(&mut buf))
+        .await
+        .expect("Pending connection was not closed");
+    assert!(!matches!(read, Ok(len) if len > 0));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_socket_activation_listening_stream() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let listener = std::os::unix::net::UnixListener::bind(tempdir.path().join("a.sock")).unwrap();
+    ensure_listening_stream(listener.as_fd()).unwrap();
+
+    let (stream, _) = std::os::unix::net::UnixStream::pair().unwrap();
+    assert!(ensure_listening_stream(stream.as_fd()).is_err());
+
+    let datagram = std::os::unix::net::UnixDatagram::unbound().unwrap();
+    assert!(ensure_listening_stream(datagram.as_fd()).is_err());
+
+    let file = std::fs::File::create(tempdir.path().join("file")).unwrap();
+    assert!(ensure_listening_stream(file.as_fd()).is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_socket_activation_env() {
+    // Not activated or activated for another process
+    assert_eq!(ListenFds::parse(None, None, None, 42).unwrap(), None);
+    assert_eq!(
+        ListenFds::parse(Some("7"), Some("1"), None, 42).unwrap(),
+        None
+    );
+
+    let fds = ListenFds::parse(Some("42"), Some("2"), Some("http:flightsql"), 42)
+        .unwrap()
+        .unwrap();
+    assert_eq!(fds.position(Some("http")).unwrap(), 0);
+    assert_eq!(fds.position(Some("flightsql")).unwrap(), 1);
+    assert!(fds.position(Some("admin")).is_err());
+    // Name is required to choose between multiple sockets
+    assert!(fds.position(None).is_err());
+
+    let fds = ListenFds::parse(Some("42"), Some("1"), None, 42)
+        .unwrap()
+        .unwrap();
+    assert_eq!(fds.names, vec!["unknown".to_string()]);
+    assert_eq!(fds.position(None).unwrap(), 0);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,136 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use kamu_api_server::config::MaintenanceConfig;
+use kamu_api_server::maintenance::{MaintenanceMode, maintenance_router};
+use time_source::SystemTimeSourceDefault;
+use tower::ServiceExt as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn maintenance_mode(config: MaintenanceConfig) -> std::sync::Arc<MaintenanceMode> {
+    dill::CatalogBuilder::new()
+        .add_value(config)
+        .add::<SystemTimeSourceDefault>()
+        .add::<MaintenanceMode>()
+        .build()
+        .get_one::<MaintenanceMode>()
+        .unwrap()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_maintenance_mode_toggle() {
+    let maintenance = maintenance_mode(MaintenanceConfig::default());
+    assert!(!maintenance.is_enabled());
+    assert_eq!(maintenance.message(), None);
+
+    let status = maintenance.enable(None);
+    assert!(status.enabled);
+    assert_eq!(
+        maintenance.message(),
+        Some(MaintenanceConfig::default().message)
+    );
+
+    // Enabling again only replaces the message
+    let updated = maintenance.enable(Some("Upgrading storage".to_string()));
+    assert_eq!(updated.since, status.since);
+    assert_eq!(maintenance.message().as_deref(), Some("Upgrading storage"));
+
+    let waiter = tokio::spawn({
+        let maintenance = maintenance.clone();
+        async move { maintenance.wait_until(false).await }
+    });
+
+    let status = maintenance.disable();
+    assert!(!status.enabled);
+    assert_eq!(status.since, None);
+    assert_eq!(maintenance.message(), None);
+
+    tokio::time::timeout(Duration::from_secs(5), waiter)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_maintenance_toggle_requires_token() {
+    let maintenance = maintenance_mode(MaintenanceConfig {
+        token: Some("s3cr3t".to_string()),
+        ..Default::default()
+    });
+    let router = maintenance_router(maintenance.clone());
+
+    let send = async |method: http::Method, authorization: Option<&str>| {
+        let mut request = http::Request::builder()
+            .method(method)
+            .uri("/system/maintenance")
+            .header(http::header::CONTENT_TYPE, "application/json");
+        if let Some(authorization) = authorization {
+            request = request.header(http::header::AUTHORIZATION, authorization);
+        }
+
+        router
+            .clone()
+            .oneshot(request.body(axum::body::Body::from("{}")).unwrap())
+            .await
+            .unwrap()
+            .status()
+    };
+
+    assert_eq!(
+        send(http::Method::PUT, None).await,
+        http::StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        send(http::Method::PUT, Some("Bearer wrong")).await,
+        http::StatusCode::UNAUTHORIZED
+    );
+    assert!(!maintenance.is_enabled());
+
+    assert_eq!(
+        send(http::Method::PUT, Some("Bearer s3cr3t")).await,
+        http::StatusCode::OK
+    );
+    assert!(maintenance.is_enabled());
+
+    // Status is available without the token
+    assert_eq!(send(http::Method::GET, None).await, http::StatusCode::OK);
+
+    assert_eq!(
+        send(http::Method::DELETE, None).await,
+        http::StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        send(http::Method::DELETE, Some("Bearer s3cr3t")).await,
+        http::StatusCode::OK
+    );
+    assert!(!maintenance.is_enabled());
+
+    // Admin listener without the token doesn't require authentication
+    let maintenance = maintenance_mode(MaintenanceConfig::default());
+    let response = maintenance_router(maintenance.clone())
+        .oneshot(
+            http::Request::put("/system/maintenance")
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(axum::body::Body::from("{}"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert!(maintenance.is_enabled());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
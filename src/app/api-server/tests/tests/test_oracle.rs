@@ -0,0 +1,277 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::future::IntoFuture as _;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use graceful_shutdown::CancellationToken;
+use kamu_api_server::OracleProviderAgent;
+use kamu_api_server::agent_supervisor::GracefulBackgroundAgent;
+use kamu_oracle_provider::OdfOracleProviderMetrics;
+use observability::metrics::MetricsProvider as _;
+use serde_json::json;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_oracle_config_into_provider_config() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let config_path = tempdir.path().join("config.yaml");
+    std::fs::write(
+        &config_path,
+        indoc::indoc!(
+            r#"
+            oracle:
+              rpcUrl: http://localhost:8545
+              chainId: 1
+              oracleContractAddress: "0x0000000000000000000000000000000000000001"
+              providerAddress: "0x0000000000000000000000000000000000000002"
+              providerPrivateKey: "0x00"
+              transactionConfirmations: 1
+              transactionTimeout: 2m
+              shutdownDrainTimeout: 15s
+              publicationsCheckInterval: 5s
+              publications:
+                - name: price
+                  datasetAlias: prices
+                  datasetId: did:odf:fed01dcda047d51fc88246c730db522d36791c9e2286af23d9f2b920f09c65952e3d0
+                  sql: select price from prices
+                  targetAddress: "0x0000000000000000000000000000000000000003"
+                  targetFunction: updateResult(bytes)
+            "#
+        ),
+    )
+    .unwrap();
+
+    let config = kamu_api_server::load_config(Some(&config_path)).unwrap();
+    let api_url = url::Url::parse("http://localhost:8080").unwrap();
+    let provider_config = config.oracle.unwrap().into_provider_config(api_url.clone());
+
+    assert_eq!(provider_config.api_url, api_url);
+    assert_eq!(provider_config.api_access_token, None);
+    assert_eq!(
+        Duration::from(provider_config.transaction_timeout),
+        Duration::from_secs(120)
+    );
+    assert_eq!(
+        Duration::from(provider_config.shutdown_drain_timeout),
+        Duration::from_secs(15)
+    );
+    assert_eq!(
+        Duration::from(provider_config.publications_check_interval),
+        Duration::from_secs(5)
+    );
+    assert_eq!(
+        provider_config
+            .publications
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>(),
+        ["price"]
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_oracle_config_defaults() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let config_path = tempdir.path().join("config.yaml");
+    std::fs::write(
+        &config_path,
+        indoc::indoc!(
+            r#"
+            oracle:
+              rpcUrl: http://localhost:8545
+              chainId: 1
+              oracleContractAddress: "0x0000000000000000000000000000000000000001"
+              providerAddress: "0x0000000000000000000000000000000000000002"
+              providerPrivateKey: "0x00"
+              transactionConfirmations: 1
+              transactionTimeout: 2m
+            "#
+        ),
+    )
+    .unwrap();
+
+    let config = kamu_api_server::load_config(Some(&config_path)).unwrap();
+    let provider_config = config
+        .oracle
+        .unwrap()
+        .into_provider_config(url::Url::parse("http://localhost:8080").unwrap());
+
+    // Drain timeout is not tied to the transaction timeout
+    assert_eq!(
+        Duration::from(provider_config.shutdown_drain_timeout),
+        Duration::from_secs(60)
+    );
+    assert_eq!(
+        Duration::from(provider_config.publications_check_interval),
+        Duration::from_secs(30)
+    );
+    assert!(provider_config.publications.is_empty());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_oracle_config_rejects_duplicate_publications() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let config_path = tempdir.path().join("config.yaml");
+    std::fs::write(
+        &config_path,
+        indoc::indoc!(
+            r#"
+            oracle:
+              rpcUrl: http://localhost:8545
+              chainId: 1
+              oracleContractAddress: "0x0000000000000000000000000000000000000001"
+              providerAddress: "0x0000000000000000000000000000000000000002"
+              providerPrivateKey: "0x00"
+              transactionConfirmations: 1
+              publications:
+                - name: price
+                  datasetAlias: prices
+                  datasetId: did:odf:fed01dcda047d51fc88246c730db522d36791c9e2286af23d9f2b920f09c65952e3d0
+                  sql: select price from prices
+                  targetAddress: "0x0000000000000000000000000000000000000003"
+                  targetFunction: updateResult(bytes)
+                - name: price
+                  datasetAlias: prices
+                  datasetId: did:odf:fed01dcda047d51fc88246c730db522d36791c9e2286af23d9f2b920f09c65952e3d0
+                  sql: select avg(price) from prices
+                  targetAddress: "0x0000000000000000000000000000000000000004"
+                  targetFunction: updateResult(bytes)
+            "#
+        ),
+    )
+    .unwrap();
+
+    let err = kamu_api_server::load_config(Some(&config_path)).unwrap_err();
+    assert!(
+        format!("{err:?}").contains("Duplicate publication name"),
+        "{err:?}"
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_oracle_agent_stops_on_signal() {
+    let (rpc_url, rpc_calls) = serve_rpc_mock().await;
+
+    let metrics_reg = prometheus::Registry::new();
+    let catalog = dill::CatalogBuilder::new()
+        .add_value(provider_config(rpc_url))
+        .add_value(OdfOracleProviderMetrics::new(1, "localhost"))
+        .add::<OracleProviderAgent>()
+        .build();
+    catalog
+        .get_one::<OdfOracleProviderMetrics>()
+        .unwrap()
+        .register(&metrics_reg)
+        .unwrap();
+
+    // Restarted agent reuses the metrics registered once
+    for _ in 0..2 {
+        rpc_calls.lock().unwrap().clear();
+
+        let agent = catalog.get_one::<OracleProviderAgent>().unwrap();
+        let stop = CancellationToken::new();
+        let run = tokio::spawn({
+            let stop = stop.clone();
+            async move { agent.run(stop).await }
+        });
+
+        // Provider is waiting for the authorization to provide results
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !rpc_calls.lock().unwrap().iter().any(|m| m == "eth_call") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        stop.cancel();
+        tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Helpers
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serves JSON-RPC of a chain where the provider is not authorized yet,
+/// recording the called methods
+async fn serve_rpc_mock() -> (url::Url, Arc<Mutex<Vec<String>>>) {
+    async fn rpc_handler(
+        axum::extract::State(calls): axum::extract::State<Arc<Mutex<Vec<String>>>>,
+        axum::Json(request): axum::Json<serde_json::Value>,
+    ) -> axum::Json<serde_json::Value> {
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        let result = match method.as_str() {
+            "eth_chainId" => json!("0x1"),
+            "eth_blockNumber" => json!("0x10"),
+            // canProvideResults() -> false
+            "eth_call" => json!(format!("0x{}", "0".repeat(64))),
+            "eth_getBalance" => json!("0x0"),
+            _ => serde_json::Value::Null,
+        };
+        calls.lock().unwrap().push(method);
+
+        axum::Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let router = axum::Router::new()
+        .route("/", axum::routing::post(rpc_handler))
+        .with_state(calls.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(axum::serve(listener, router).into_future());
+
+    (url::Url::parse(&format!("http://{addr}")).unwrap(), calls)
+}
+
+fn provider_config(rpc_url: url::Url) -> kamu_oracle_provider::Config {
+    kamu_oracle_provider::Config {
+        http_address: "127.0.0.1".to_string(),
+        http_port: 0,
+        gas_spend_token: None,
+        rpc_url,
+        chain_id: 1,
+        oracle_contract_address: alloy::primitives::Address::ZERO,
+        provider_address: alloy::primitives::Address::ZERO,
+        // Well-known development key
+        provider_private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            .to_string(),
+        scan_from_block: Some(0),
+        scan_last_blocks: None,
+        scan_last_blocks_period: None,
+        blocks_stride: 100_000,
+        loop_idle_time: "1s".parse().unwrap(),
+        transaction_confirmations: 1,
+        transaction_timeout: "1m".parse().unwrap(),
+        shutdown_drain_timeout: "1m".parse().unwrap(),
+        state_path: None,
+        api_url: url::Url::parse("http://localhost:8080").unwrap(),
+        api_access_token: None,
+        ignore_requests: Vec::new(),
+        ignore_consumers: Vec::new(),
+        gas_metrics_consumers: Vec::new(),
+        publications: Vec::new(),
+        publications_check_interval: "30s".parse().unwrap(),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,142 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use kamu_api_server::config::{RateLimitConfig, RateLimitRuleConfig};
+use kamu_api_server::rate_limit::*;
+use kamu_api_server::route_group::RouteGroup;
+use tower::ServiceExt as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_route_group_classify() {
+    assert_eq!(RouteGroup::classify("/graphql"), Some(RouteGroup::GraphQL));
+    assert_eq!(RouteGroup::classify("/query"), Some(RouteGroup::Query));
+    assert_eq!(
+        RouteGroup::classify("/odata/{account_name}/{dataset_name}"),
+        Some(RouteGroup::Query)
+    );
+    assert_eq!(
+        RouteGroup::classify("/{account_name}/{dataset_name}/tail"),
+        Some(RouteGroup::Query)
+    );
+    assert_eq!(
+        RouteGroup::classify("/{account_name}/{dataset_name}/ingest"),
+        Some(RouteGroup::Ingest)
+    );
+    assert_eq!(
+        RouteGroup::classify("/{account_name}/{dataset_name}/pull"),
+        Some(RouteGroup::Transfer)
+    );
+    assert_eq!(
+        RouteGroup::classify("/{account_name}/{dataset_name}/refs/{reference}"),
+        Some(RouteGroup::Transfer)
+    );
+    assert_eq!(
+        RouteGroup::classify("/{account_name}/{dataset_name}/metadata"),
+        None
+    );
+    assert_eq!(RouteGroup::classify("/system/health"), None);
+    assert_eq!(RouteGroup::classify("/platform/login"), None);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_route_group_of_request_uses_matched_route() {
+    let router = axum::Router::new()
+        .route(
+            "/{account_name}/{dataset_name}/metadata",
+            axum::routing::get(|| async {}),
+        )
+        .route(
+            "/{account_name}/{dataset_name}/query",
+            axum::routing::get(|| async {}),
+        )
+        .fallback(|| async {})
+        .layer(axum::middleware::from_fn(
+            |request: axum::extract::Request, next: axum::middleware::Next| async move {
+                let group = RouteGroup::of_request(&request);
+                let mut response = next.run(request).await;
+                response.extensions_mut().insert(group);
+                response
+            },
+        ));
+
+    let group_of = async |path: &str| {
+        let response = router
+            .clone()
+            .oneshot(
+                http::Request::get(path)
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        *response.extensions().get::<Option<RouteGroup>>().unwrap()
+    };
+
+    assert_eq!(
+        group_of("/kamu/covid19/query").await,
+        Some(RouteGroup::Query)
+    );
+    // Datasets and accounts named like the route segments
+    assert_eq!(group_of("/kamu/query/metadata").await, None);
+    assert_eq!(group_of("/push/tail/metadata").await, None);
+    assert_eq!(
+        group_of("/graphql/blocks/query").await,
+        Some(RouteGroup::Query)
+    );
+    // Requests that were not routed
+    assert_eq!(group_of("/kamu/covid19/tail").await, None);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_rate_limiter_token_bucket() {
+    let limiter = RateLimiter::new(
+        Arc::new(RateLimitConfig {
+            graphql: Some(RateLimitRuleConfig {
+                requests: 2,
+                period: "1s".parse().unwrap(),
+                burst: Some(3),
+            }),
+            ..Default::default()
+        }),
+        Arc::new(RateLimitMetrics::new()),
+    );
+
+    let alice = RateLimitKey::Ip("10.0.0.1".parse().unwrap());
+    let bob = RateLimitKey::Ip("10.0.0.2".parse().unwrap());
+    let t0 = Instant::now();
+
+    // Burst is allowed after being idle
+    for _ in 0..3 {
+        assert_eq!(limiter.check(RouteGroup::GraphQL, &alice, t0), Ok(()));
+    }
+    assert_eq!(
+        limiter.check(RouteGroup::GraphQL, &alice, t0),
+        Err(Duration::from_millis(500))
+    );
+
+    // Identities and route groups are tracked separately
+    assert_eq!(limiter.check(RouteGroup::GraphQL, &bob, t0), Ok(()));
+    assert_eq!(limiter.check(RouteGroup::Query, &alice, t0), Ok(()));
+
+    // Tokens are refilled at the configured rate
+    let t1 = t0 + Duration::from_millis(500);
+    assert_eq!(limiter.check(RouteGroup::GraphQL, &alice, t1), Ok(()));
+    assert!(limiter.check(RouteGroup::GraphQL, &alice, t1).is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,109 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use http::Method;
+use kamu_api_server::read_only::{is_allowed_in_read_only, is_graphql_mutation, is_graphql_write};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_read_only_routes() {
+    let allowed = |method: Method, route: &str| is_allowed_in_read_only(&method, Some(route));
+
+    assert!(allowed(
+        Method::GET,
+        "/{account_name}/{dataset_name}/metadata"
+    ));
+    assert!(allowed(Method::GET, "/{account_name}/{dataset_name}/pull"));
+    assert!(allowed(Method::POST, "/query"));
+    assert!(allowed(
+        Method::POST,
+        "/{account_name}/{dataset_name}/query"
+    ));
+    assert!(allowed(Method::POST, "/graphql"));
+    assert!(allowed(Method::POST, "/platform/login"));
+
+    assert!(!allowed(
+        Method::POST,
+        "/{account_name}/{dataset_name}/ingest"
+    ));
+    assert!(!allowed(Method::GET, "/{account_name}/{dataset_name}/push"));
+    assert!(!allowed(
+        Method::PUT,
+        "/{account_name}/{dataset_name}/data/{physical_hash}"
+    ));
+    assert!(!allowed(Method::POST, "/platform/file/upload/prepare"));
+
+    assert!(is_allowed_in_read_only(&Method::GET, None));
+    assert!(!is_allowed_in_read_only(&Method::POST, None));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_read_only_graphql_mutations() {
+    assert!(!is_graphql_mutation(&async_graphql::Request::new(
+        "{ apiVersion }"
+    )));
+    assert!(is_graphql_mutation(&async_graphql::Request::new(
+        "mutation { auth { __typename } }"
+    )));
+
+    let document = "query Q { apiVersion } mutation M { __typename }";
+    assert!(!is_graphql_mutation(
+        &async_graphql::Request::new(document).operation_name("Q")
+    ));
+    assert!(is_graphql_mutation(
+        &async_graphql::Request::new(document).operation_name("M")
+    ));
+    assert!(is_graphql_mutation(&async_graphql::Request::new(document)));
+
+    // Reported by the schema instead
+    assert!(!is_graphql_mutation(&async_graphql::Request::new(
+        "mutation {"
+    )));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_read_only_graphql_login_is_allowed() {
+    let is_write = |query: &str| is_graphql_write(&async_graphql::Request::new(query));
+
+    assert!(!is_write(
+        r#"mutation Login { auth { login(loginMethod: "password") { accessToken } } }"#
+    ));
+    assert!(!is_write(
+        "mutation { auth { __typename login { accessToken } } }"
+    ));
+    assert!(!is_write("{ apiVersion }"));
+
+    assert!(is_write(
+        "mutation { auth { login { accessToken } revokeAccessToken { __typename } } }"
+    ));
+    assert!(is_write(
+        "mutation { auth { login { accessToken } } datasets { __typename } }"
+    ));
+    assert!(is_write(
+        "mutation { auth { ...Login } } fragment Login on AuthMut { login { accessToken } }"
+    ));
+
+    // Only the selected operation counts
+    let document = "mutation Login { auth { login { accessToken } } } mutation Other { datasets { \
+                    __typename } }";
+    assert!(!is_graphql_write(
+        &async_graphql::Request::new(document).operation_name("Login")
+    ));
+    assert!(is_graphql_write(
+        &async_graphql::Request::new(document).operation_name("Other")
+    ));
+    assert!(is_graphql_write(&async_graphql::Request::new(document)));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,237 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use internal_error::InternalError;
+use kamu_api_server::agent_supervisor::{AgentState, BackgroundAgentsStatus};
+use kamu_api_server::readiness::{
+    CheckStatus,
+    ReadinessAudience,
+    ReadinessChecker,
+    ReadinessReport,
+    ReadinessState,
+    StartupJobsStatus,
+    readiness_handler,
+    startup_jobs_middleware,
+};
+use tower::ServiceExt as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn readiness_catalog(repo_url: url::Url) -> dill::Catalog {
+    dill::CatalogBuilder::new()
+        .add::<StartupJobsStatus>()
+        .add::<BackgroundAgentsStatus>()
+        .add::<ReadinessState>()
+        .add_builder(ReadinessChecker::builder(repo_url))
+        .build()
+}
+
+fn status_of(report: &ReadinessReport, name: &str) -> CheckStatus {
+    report
+        .checks
+        .iter()
+        .find(|c| c.name == name)
+        .unwrap()
+        .status
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_readiness_report() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let repo_url = url::Url::from_directory_path(tempdir.path()).unwrap();
+
+    let catalog = readiness_catalog(repo_url);
+
+    let checker = catalog.get_one::<ReadinessChecker>().unwrap();
+
+    let report = checker.check().await;
+    assert!(!report.ready);
+    assert_eq!(status_of(&report, "database"), CheckStatus::Skipped);
+    assert_eq!(status_of(&report, "objectStore"), CheckStatus::Ok);
+    assert_eq!(status_of(&report, "elasticsearch"), CheckStatus::Skipped);
+    assert_eq!(status_of(&report, "startupJobs"), CheckStatus::Failed);
+
+    catalog
+        .get_one::<StartupJobsStatus>()
+        .unwrap()
+        .mark_completed();
+
+    let report = checker.check().await;
+    assert!(report.ready);
+    assert!(!report.degraded);
+    assert_eq!(status_of(&report, "startupJobs"), CheckStatus::Ok);
+    assert_eq!(status_of(&report, "backgroundAgents"), CheckStatus::Ok);
+
+    let agents_status = catalog.get_one::<BackgroundAgentsStatus>().unwrap();
+    agents_status.set(
+        "dev.kamu.test.agent",
+        AgentState::Restarting {
+            restarts: 1,
+            last_error: "Boom".to_string(),
+        },
+    );
+
+    let report = checker.check().await;
+    assert!(report.ready);
+    assert!(report.degraded);
+    assert_eq!(
+        status_of(&report, "backgroundAgents"),
+        CheckStatus::Degraded
+    );
+
+    agents_status.set(
+        "dev.kamu.test.agent",
+        AgentState::Failed {
+            last_error: "Boom".to_string(),
+        },
+    );
+
+    let report = checker.check().await;
+    assert!(!report.ready);
+    assert_eq!(status_of(&report, "backgroundAgents"), CheckStatus::Failed);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_readiness_startup_jobs() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let catalog = readiness_catalog(url::Url::from_directory_path(tempdir.path()).unwrap());
+    let checker = catalog.get_one::<ReadinessChecker>().unwrap();
+    let startup_jobs_status = catalog.get_one::<StartupJobsStatus>().unwrap();
+
+    startup_jobs_status.mark_skipped();
+    let report = checker.check().await;
+    assert!(report.ready);
+    assert_eq!(status_of(&report, "startupJobs"), CheckStatus::Skipped);
+
+    startup_jobs_status.mark_failed(&InternalError::new("Migration failed"));
+    let report = checker.check().await;
+    assert!(!report.ready);
+    let check = report
+        .checks
+        .iter()
+        .find(|c| c.name == "startupJobs")
+        .unwrap();
+    assert_eq!(check.status, CheckStatus::Failed);
+    assert!(check.message.as_ref().unwrap().contains("Migration failed"));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_public_readiness_report_hides_details_and_is_cached() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let catalog = readiness_catalog(url::Url::from_directory_path(tempdir.path()).unwrap());
+
+    let get_report = async |audience: ReadinessAudience| {
+        let response = axum::Router::new()
+            .route(
+                "/system/ready",
+                axum::routing::get(readiness_handler).with_state(audience),
+            )
+            .layer(axum::extract::Extension(catalog.clone()))
+            .oneshot(
+                http::Request::get("/system/ready")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            status,
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+        )
+    };
+    let startup_jobs_message = |report: &serde_json::Value| {
+        report["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"] == "startupJobs")
+            .unwrap()
+            .get("message")
+            .cloned()
+    };
+
+    let (status, report) = get_report(ReadinessAudience::Internal).await;
+    assert_eq!(status, http::StatusCode::SERVICE_UNAVAILABLE);
+    assert!(startup_jobs_message(&report).is_some());
+
+    let (status, report) = get_report(ReadinessAudience::Public).await;
+    assert_eq!(status, http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(report["checks"].as_array().unwrap().len(), 5);
+    assert_eq!(startup_jobs_message(&report), None);
+
+    // Public clients get the cached report until it expires
+    catalog
+        .get_one::<StartupJobsStatus>()
+        .unwrap()
+        .mark_completed();
+
+    let (status, _) = get_report(ReadinessAudience::Public).await;
+    assert_eq!(status, http::StatusCode::SERVICE_UNAVAILABLE);
+
+    let (status, _) = get_report(ReadinessAudience::Internal).await;
+    assert_eq!(status, http::StatusCode::OK);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_requests_are_rejected_until_startup_jobs_complete() {
+    let startup_jobs_status = std::sync::Arc::new(StartupJobsStatus::new());
+
+    let router = axum::Router::new()
+        .route("/system/probe", axum::routing::get(|| async { "OK" }))
+        .route("/datasets", axum::routing::get(|| async { "[]" }))
+        .layer(axum::middleware::from_fn_with_state(
+            Some(startup_jobs_status.clone()),
+            startup_jobs_middleware,
+        ));
+
+    let get = async |path: &str| {
+        router
+            .clone()
+            .oneshot(
+                http::Request::get(path)
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    };
+
+    let response = get("/datasets").await;
+    assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().contains_key(http::header::RETRY_AFTER));
+
+    // Liveness probe is served while the jobs are running
+    assert_eq!(get("/system/probe").await.status(), http::StatusCode::OK);
+
+    startup_jobs_status.mark_failed(&InternalError::new("Migration failed"));
+    assert_eq!(
+        get("/datasets").await.status(),
+        http::StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    startup_jobs_status.mark_completed();
+    assert_eq!(get("/datasets").await.status(), http::StatusCode::OK);
+
+    startup_jobs_status.mark_skipped();
+    assert_eq!(get("/datasets").await.status(), http::StatusCode::OK);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
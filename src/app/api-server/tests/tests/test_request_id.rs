@@ -0,0 +1,68 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use http::HeaderValue;
+use kamu_api_server::flow_request_id::{FlowRequestIdRepository, InMemoryFlowRequestIdRepository};
+use kamu_api_server::request_id::RequestId;
+use kamu_flow_system::FlowID;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_request_id_from_header() {
+    assert_eq!(
+        RequestId::from_header(&HeaderValue::from_static("req-42_a.b:c"))
+            .unwrap()
+            .as_str(),
+        "req-42_a.b:c"
+    );
+    assert_eq!(RequestId::from_header(&HeaderValue::from_static("")), None);
+    assert_eq!(
+        RequestId::from_header(&HeaderValue::from_static("<script>")),
+        None
+    );
+    assert_eq!(
+        RequestId::from_header(&HeaderValue::from_str(&"a".repeat(129)).unwrap()),
+        None
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_flow_request_id_repository() {
+    let repository = InMemoryFlowRequestIdRepository::new();
+
+    let flow_id = FlowID::new(1);
+    let request_id = RequestId::new_random();
+
+    assert_eq!(repository.get_request_id(flow_id).await.unwrap(), None);
+
+    repository
+        .save_request_id(flow_id, &request_id)
+        .await
+        .unwrap();
+
+    // Flow that was already waiting keeps the request that triggered it first
+    repository
+        .save_request_id(flow_id, &RequestId::new_random())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository.get_request_id(flow_id).await.unwrap(),
+        Some(request_id)
+    );
+    assert_eq!(
+        repository.get_request_id(FlowID::new(2)).await.unwrap(),
+        None
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
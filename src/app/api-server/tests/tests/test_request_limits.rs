@@ -0,0 +1,115 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use http_body_util::BodyExt as _;
+use kamu_api_server::config::{HttpLimitsConfig, RequestLimitsConfig};
+use kamu_api_server::request_limits::{RequestLimits, RouteLimits, request_limits_middleware};
+use kamu_api_server::route_group::RouteGroup;
+use tower::ServiceExt as _;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_route_limits_fall_back_to_default() {
+    let limits = RouteLimits::from_config(&HttpLimitsConfig {
+        default: RequestLimitsConfig {
+            max_body_size: Some(1024),
+            timeout: Some("30s".parse().unwrap()),
+        },
+        graphql: Some(RequestLimitsConfig {
+            max_body_size: None,
+            timeout: Some("5s".parse().unwrap()),
+        }),
+        ingest: Some(RequestLimitsConfig {
+            max_body_size: Some(100 * 1024 * 1024),
+            timeout: None,
+        }),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        *limits.get(None),
+        RequestLimits {
+            max_body_size: Some(1024),
+            timeout: Some(Duration::from_secs(30)),
+        }
+    );
+    assert_eq!(
+        *limits.get(Some(RouteGroup::GraphQL)),
+        RequestLimits {
+            max_body_size: Some(1024),
+            timeout: Some(Duration::from_secs(5)),
+        }
+    );
+    assert_eq!(
+        *limits.get(Some(RouteGroup::Ingest)),
+        RequestLimits {
+            max_body_size: Some(100 * 1024 * 1024),
+            timeout: Some(Duration::from_secs(30)),
+        }
+    );
+    assert_eq!(*limits.get(Some(RouteGroup::Query)), *limits.get(None));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_request_body_limit() {
+    let route_limits = Arc::new(RouteLimits::from_config(&HttpLimitsConfig {
+        default: RequestLimitsConfig {
+            max_body_size: Some(8),
+            timeout: None,
+        },
+        ..Default::default()
+    }));
+
+    // Handler that doesn't distinguish the reasons of failing to read the body
+    let router = axum::Router::new()
+        .route(
+            "/upload",
+            axum::routing::post(|body: axum::body::Body| async move {
+                match body.collect().await {
+                    Ok(_) => http::StatusCode::OK,
+                    Err(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+                }
+            }),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            route_limits,
+            request_limits_middleware,
+        ));
+
+    let upload = |chunks: Vec<&'static str>, content_length: Option<usize>| {
+        let mut request = http::Request::post("/upload");
+        if let Some(content_length) = content_length {
+            request = request.header(http::header::CONTENT_LENGTH, content_length);
+        }
+        let body =
+            axum::body::Body::from_stream(futures::stream::iter(chunks.into_iter().map(|chunk| {
+                Ok::<_, std::io::Error>(bytes::Bytes::from_static(chunk.as_bytes()))
+            })));
+        router.clone().oneshot(request.body(body).unwrap())
+    };
+
+    let response = upload(vec!["1234", "5678"], None).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+
+    // Streamed without the length
+    let response = upload(vec!["1234", "5678", "9"], None).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Rejected by the length before reading
+    let response = upload(vec![], Some(9)).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,92 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use kamu_api_server::cli::RunRole;
+use kamu_api_server::commands::RunRoles;
+use kamu_api_server::config::ServerRole;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_run_roles() {
+    assert_eq!(
+        RunRoles::new(&[ServerRole::Api, ServerRole::Worker], false).unwrap(),
+        RunRoles {
+            http: true,
+            flightsql: true,
+            agents: true,
+        }
+    );
+    assert_eq!(
+        RunRoles::new(&[ServerRole::Api], false).unwrap(),
+        RunRoles {
+            http: true,
+            flightsql: true,
+            agents: false,
+        }
+    );
+    assert_eq!(
+        RunRoles::new(&[ServerRole::FlightSql, ServerRole::Worker], false).unwrap(),
+        RunRoles {
+            http: false,
+            flightsql: true,
+            agents: true,
+        }
+    );
+    assert_eq!(
+        RunRoles::new(&[ServerRole::Worker], false).unwrap(),
+        RunRoles {
+            http: false,
+            flightsql: false,
+            agents: true,
+        }
+    );
+    assert!(RunRoles::new(&[], false).is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_run_roles_read_only() {
+    // Read-only replicas don't run agents as they modify the shared state
+    assert_eq!(
+        RunRoles::new(&[ServerRole::Api, ServerRole::Worker], true).unwrap(),
+        RunRoles {
+            http: true,
+            flightsql: true,
+            agents: false,
+        }
+    );
+    assert_eq!(
+        RunRoles::new(&[ServerRole::Worker], true).unwrap(),
+        RunRoles {
+            http: false,
+            flightsql: false,
+            agents: false,
+        }
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_run_role_spelling_matches_config() {
+    use clap::ValueEnum as _;
+
+    for role in RunRole::value_variants() {
+        let name = role.to_possible_value().unwrap().get_name().to_string();
+        let config_role: ServerRole = serde_json::from_value(serde_json::json!(name)).unwrap();
+        assert_eq!(
+            RunRoles::new(&[ServerRole::from(*role)], false).unwrap(),
+            RunRoles::new(&[config_role], false).unwrap(),
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,293 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use http_body_util::BodyExt as _;
+use kamu::domain::Protocols;
+use kamu_api_server::config::TenantDomainConfig;
+use kamu_api_server::tenant_domains::{
+    TenantDomains,
+    TenantPath,
+    rewrite_path,
+    tenant_domain_middleware,
+};
+use tower::{Layer as _, ServiceExt as _};
+use url::Url;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn protocols() -> Protocols {
+    Protocols {
+        base_url_platform: Url::parse("https://platform.example.com/").unwrap(),
+        base_url_rest: Url::parse("https://api.example.com/").unwrap(),
+        base_url_flightsql: Url::parse("grpc://api.example.com:50050").unwrap(),
+    }
+}
+
+fn domain(host: &str, account_name: &str) -> TenantDomainConfig {
+    TenantDomainConfig {
+        host: host.to_string(),
+        account_name: account_name.to_string(),
+        base_url_platform: None,
+        base_url_rest: None,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tenant_domain_rewrite_path() {
+    let rewritten = |path: &str| TenantPath::Rewritten(path.to_string());
+
+    assert_eq!(
+        rewrite_path("/covid19/refs/head", "acme"),
+        rewritten("/acme/covid19/refs/head")
+    );
+    assert_eq!(
+        rewrite_path("/covid19/tail", "acme"),
+        rewritten("/acme/covid19/tail")
+    );
+    assert_eq!(
+        rewrite_path("/covid19/pull", "acme"),
+        rewritten("/acme/covid19/pull")
+    );
+    assert_eq!(rewrite_path("/odata", "acme"), rewritten("/odata/acme"));
+    assert_eq!(
+        rewrite_path("/odata/covid19", "acme"),
+        rewritten("/odata/acme/covid19")
+    );
+
+    // Other routes are left as is
+    assert_eq!(rewrite_path("/graphql", "acme"), TenantPath::Unchanged);
+    assert_eq!(
+        rewrite_path("/platform/login", "acme"),
+        TenantPath::Unchanged
+    );
+    assert_eq!(
+        rewrite_path("/system/health", "acme"),
+        TenantPath::Unchanged
+    );
+    assert_eq!(rewrite_path("/", "acme"), TenantPath::Unchanged);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tenant_domain_rewrite_qualified_path() {
+    let rewritten = |path: &str| TenantPath::Rewritten(path.to_string());
+
+    assert_eq!(
+        rewrite_path("/acme/covid19/refs/head", "acme"),
+        TenantPath::Unchanged
+    );
+    assert_eq!(
+        rewrite_path("/ACME/covid19/tail", "acme"),
+        TenantPath::Unchanged
+    );
+    assert_eq!(
+        rewrite_path("/odata/acme/covid19", "acme"),
+        TenantPath::Unchanged
+    );
+    assert_eq!(rewrite_path("/odata/acme", "acme"), TenantPath::Unchanged);
+
+    // The dataset named like the account
+    assert_eq!(
+        rewrite_path("/acme/tail", "acme"),
+        rewritten("/acme/acme/tail")
+    );
+    assert_eq!(
+        rewrite_path("/acme/acme/tail", "acme"),
+        TenantPath::Unchanged
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tenant_domain_rewrite_path_names_like_route_segments() {
+    let rewritten = |path: &str| TenantPath::Rewritten(path.to_string());
+
+    // Datasets
+    assert_eq!(
+        rewrite_path("/data/refs/head", "acme"),
+        rewritten("/acme/data/refs/head")
+    );
+    assert_eq!(
+        rewrite_path("/acme/data/refs/head", "acme"),
+        TenantPath::Unchanged
+    );
+    assert_eq!(
+        rewrite_path("/tail/tail", "acme"),
+        rewritten("/acme/tail/tail")
+    );
+
+    // Accounts
+    assert_eq!(
+        rewrite_path("/covid19/tail", "data"),
+        rewritten("/data/covid19/tail")
+    );
+    assert_eq!(
+        rewrite_path("/data/covid19/tail", "data"),
+        TenantPath::Unchanged
+    );
+    assert_eq!(
+        rewrite_path("/data/tail", "data"),
+        rewritten("/data/data/tail")
+    );
+    assert_eq!(
+        rewrite_path("/odata/covid19", "odata"),
+        rewritten("/odata/odata/covid19")
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tenant_domain_rewrite_path_other_account() {
+    assert_eq!(
+        rewrite_path("/globex/covid19/refs/head", "acme"),
+        TenantPath::OtherAccount
+    );
+    assert_eq!(
+        rewrite_path("/globex/covid19/push", "acme"),
+        TenantPath::OtherAccount
+    );
+
+    // Stay within the account of the domain
+    assert_eq!(
+        rewrite_path("/globex/data/refs/head", "acme"),
+        TenantPath::Rewritten("/acme/globex/data/refs/head".to_string())
+    );
+    assert_eq!(
+        rewrite_path("/odata/globex/covid19", "acme"),
+        TenantPath::Rewritten("/odata/acme/globex/covid19".to_string())
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tenant_domains_lookup() {
+    let domains = TenantDomains::from_config(
+        vec![
+            domain("data.acme.com", "acme"),
+            TenantDomainConfig {
+                base_url_platform: Some("https://portal.globex.com/".parse().unwrap()),
+                ..domain("Data.Globex.com", "globex")
+            },
+        ],
+        &protocols(),
+    )
+    .unwrap();
+
+    let acme = domains.by_host("DATA.acme.com:443").unwrap();
+    assert_eq!(acme.account_name.as_str(), "acme");
+    assert_eq!(
+        acme.server_url_config.protocols.base_url_platform.as_str(),
+        "https://data.acme.com/"
+    );
+    assert_eq!(
+        acme.server_url_config.protocols.base_url_rest.as_str(),
+        "https://data.acme.com/"
+    );
+    assert_eq!(
+        acme.server_url_config.protocols.base_url_flightsql,
+        protocols().base_url_flightsql
+    );
+
+    let globex = domains
+        .by_account(&odf::AccountName::new_unchecked("globex"))
+        .unwrap();
+    assert_eq!(globex.host, "data.globex.com");
+    assert_eq!(
+        globex
+            .server_url_config
+            .protocols
+            .base_url_platform
+            .as_str(),
+        "https://portal.globex.com/"
+    );
+
+    assert!(domains.by_host("api.example.com").is_none());
+    assert!(
+        domains
+            .by_account(&odf::AccountName::new_unchecked("initech"))
+            .is_none()
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tenant_domains_duplicate_host() {
+    let res = TenantDomains::from_config(
+        vec![
+            domain("data.acme.com", "acme"),
+            domain("DATA.ACME.COM", "globex"),
+        ],
+        &protocols(),
+    );
+    assert!(res.is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_tenant_domain_middleware() {
+    let domains =
+        TenantDomains::from_config(vec![domain("data.acme.com", "acme")], &protocols()).unwrap();
+
+    let path = |request: axum::extract::Request| async move { request.uri().path().to_string() };
+    let router = axum::Router::new()
+        .route(
+            "/{account_name}/{dataset_name}/tail",
+            axum::routing::get(path),
+        )
+        .route(
+            "/odata/{account_name}/{dataset_name}",
+            axum::routing::get(path),
+        );
+    let service = axum::middleware::from_fn_with_state(Arc::new(domains), tenant_domain_middleware)
+        .layer(router);
+
+    let get = async |host: &str, path: &str| {
+        let request = http::Request::get(path)
+            .header(http::header::HOST, host)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = service.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    };
+
+    for (path, expected) in [
+        ("/covid19/tail", "/acme/covid19/tail"),
+        ("/acme/covid19/tail", "/acme/covid19/tail"),
+        ("/odata/covid19", "/odata/acme/covid19"),
+        ("/odata/acme/covid19", "/odata/acme/covid19"),
+    ] {
+        assert_eq!(
+            get("data.acme.com", path).await,
+            (http::StatusCode::OK, expected.to_string())
+        );
+    }
+
+    assert_eq!(
+        get("data.acme.com", "/globex/covid19/tail").await.0,
+        http::StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get("api.example.com", "/globex/covid19/tail").await,
+        (http::StatusCode::OK, "/globex/covid19/tail".to_string())
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,240 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use kamu_api_server::config::{TlsConfig, TlsVersion};
+use kamu_api_server::tls::{ALPN_H2, ALPN_HTTP1, ReloadableCertResolver, build_tls_acceptor};
+use rustls::pki_types::pem::PemObject as _;
+use rustls::pki_types::{CertificateDer, ServerName};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn resource(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests/resources/tls");
+    path.push(name);
+    path
+}
+
+fn cert_der(name: &str) -> CertificateDer<'static> {
+    CertificateDer::from_pem_file(resource(name)).unwrap()
+}
+
+/// Copies a server certificate and its key to the paths the resolver watches
+fn install_cert(name: &str, cert_path: &Path, key_path: &Path) {
+    std::fs::copy(resource(&format!("{name}.crt")), cert_path).unwrap();
+    std::fs::copy(resource(&format!("{name}.key")), key_path).unwrap();
+}
+
+fn tls_config(cert_path: &Path, key_path: &Path) -> TlsConfig {
+    TlsConfig {
+        cert_path: cert_path.to_path_buf(),
+        key_path: key_path.to_path_buf(),
+        min_version: TlsVersion::Tls12,
+        reload_interval: "1m".parse().unwrap(),
+        client_auth: None,
+    }
+}
+
+struct Handshake {
+    server_cert: CertificateDer<'static>,
+    alpn_protocol: Option<Vec<u8>>,
+}
+
+/// Performs a handshake over an in-memory stream with a client that trusts the
+/// test CA
+async fn handshake(acceptor: &TlsAcceptor, client_alpn_protocols: &[&[u8]]) -> Handshake {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(cert_der("ca.crt")).unwrap();
+
+    let mut client_config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    client_config.alpn_protocols = client_alpn_protocols.iter().map(|p| p.to_vec()).collect();
+
+    let connector = TlsConnector::from(Arc::new(client_config));
+    let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+
+    let (client, server) = tokio::join!(
+        connector.connect(ServerName::try_from("localhost").unwrap(), client_io),
+        acceptor.accept(server_io),
+    );
+    let client = client.unwrap();
+    server.unwrap();
+
+    let (_, conn) = client.get_ref();
+    Handshake {
+        server_cert: conn.peer_certificates().unwrap()[0].clone().into_owned(),
+        alpn_protocol: conn.alpn_protocol().map(<[u8]>::to_vec),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_cert_resolver_reload() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cert_path = tmp_dir.path().join("server.crt");
+    let key_path = tmp_dir.path().join("server.key");
+    install_cert("server-a", &cert_path, &key_path);
+
+    let resolver = Arc::new(ReloadableCertResolver::load(&cert_path, &key_path).unwrap());
+    let acceptor = build_tls_acceptor(
+        &tls_config(&cert_path, &key_path),
+        resolver.clone(),
+        &[ALPN_HTTP1],
+    )
+    .unwrap();
+
+    assert_eq!(
+        handshake(&acceptor, &[]).await.server_cert,
+        cert_der("server-a.crt")
+    );
+
+    install_cert("server-b", &cert_path, &key_path);
+    resolver.reload().unwrap();
+
+    // The same acceptor picks up the new certificate
+    assert_eq!(
+        handshake(&acceptor, &[]).await.server_cert,
+        cert_der("server-b.crt")
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_cert_resolver_keeps_previous_cert_on_invalid_files() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cert_path = tmp_dir.path().join("server.crt");
+    let key_path = tmp_dir.path().join("server.key");
+    install_cert("server-a", &cert_path, &key_path);
+
+    let resolver = Arc::new(ReloadableCertResolver::load(&cert_path, &key_path).unwrap());
+    let acceptor = build_tls_acceptor(
+        &tls_config(&cert_path, &key_path),
+        resolver.clone(),
+        &[ALPN_HTTP1],
+    )
+    .unwrap();
+
+    // Certificate without a matching key, e.g. caught in the middle of a rotation
+    std::fs::write(&cert_path, "not a certificate").unwrap();
+    assert!(resolver.reload().is_err());
+
+    std::fs::copy(resource("server-b.crt"), &cert_path).unwrap();
+    std::fs::remove_file(&key_path).unwrap();
+    assert!(resolver.reload().is_err());
+
+    // Key of another certificate
+    std::fs::copy(resource("server-a.key"), &key_path).unwrap();
+    assert!(resolver.reload().is_err());
+
+    assert_eq!(
+        handshake(&acceptor, &[]).await.server_cert,
+        cert_der("server-a.crt")
+    );
+
+    // Invalid files are rejected on startup
+    assert!(ReloadableCertResolver::load(&cert_path, &key_path).is_err());
+
+    std::fs::write(&key_path, "not a key").unwrap();
+    assert!(ReloadableCertResolver::load(&cert_path, &key_path).is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_cert_resolver_watcher_reloads_changed_files() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cert_path = tmp_dir.path().join("server.crt");
+    let key_path = tmp_dir.path().join("server.key");
+    install_cert("server-a", &cert_path, &key_path);
+
+    let resolver = Arc::new(ReloadableCertResolver::load(&cert_path, &key_path).unwrap());
+    resolver.spawn_reload_watcher(Duration::from_millis(50));
+    let acceptor = build_tls_acceptor(
+        &tls_config(&cert_path, &key_path),
+        resolver.clone(),
+        &[ALPN_HTTP1],
+    )
+    .unwrap();
+
+    install_cert("server-b", &cert_path, &key_path);
+
+    // Don't depend on the timestamp granularity of the file system
+    let modified = SystemTime::now() + Duration::from_secs(60);
+    for path in [&cert_path, &key_path] {
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while handshake(&acceptor, &[]).await.server_cert != cert_der("server-b.crt") {
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "Certificate was not reloaded"
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_tls_acceptor_alpn() {
+    let resolver = Arc::new(
+        ReloadableCertResolver::load(resource("server-a.crt"), resource("server-a.key")).unwrap(),
+    );
+    let config = tls_config(&resource("server-a.crt"), &resource("server-a.key"));
+
+    let http_acceptor =
+        build_tls_acceptor(&config, resolver.clone(), &[ALPN_H2, ALPN_HTTP1]).unwrap();
+
+    assert_eq!(
+        handshake(&http_acceptor, &[ALPN_H2, ALPN_HTTP1])
+            .await
+            .alpn_protocol
+            .as_deref(),
+        Some(ALPN_H2)
+    );
+    assert_eq!(
+        handshake(&http_acceptor, &[ALPN_HTTP1])
+            .await
+            .alpn_protocol
+            .as_deref(),
+        Some(ALPN_HTTP1)
+    );
+    assert_eq!(handshake(&http_acceptor, &[]).await.alpn_protocol, None);
+
+    // Flight SQL listener only speaks HTTP/2
+    let flightsql_acceptor = build_tls_acceptor(&config, resolver, &[ALPN_H2]).unwrap();
+
+    assert_eq!(
+        handshake(&flightsql_acceptor, &[ALPN_H2, ALPN_HTTP1])
+            .await
+            .alpn_protocol
+            .as_deref(),
+        Some(ALPN_H2)
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,68 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use kamu_api_server::web_ui::{WebUiBundle, is_hashed_asset};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_web_ui_hashed_assets() {
+    assert!(is_hashed_asset("main-5RVBRN3K.js"));
+    assert!(is_hashed_asset("chunk-QW2E3R4T.js"));
+    assert!(is_hashed_asset("styles.3ff695c00d717f2d.css"));
+
+    assert!(!is_hashed_asset("index.html"));
+    assert!(!is_hashed_asset("favicon.ico"));
+    assert!(!is_hashed_asset("runtime-config.json"));
+    assert!(!is_hashed_asset("my-component.js"));
+    assert!(!is_hashed_asset("ABCDEFGH.js"));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_web_ui_bundle_load() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let root = tempdir.path().join("dist/browser");
+    std::fs::create_dir_all(root.join("assets")).unwrap();
+    std::fs::write(root.join("index.html"), "<html></html>").unwrap();
+    std::fs::write(root.join("main-5RVBRN3K.js"), "main()").unwrap();
+    std::fs::write(root.join("assets/logo.svg"), "<svg/>").unwrap();
+
+    let bundle = WebUiBundle::load(tempdir.path()).unwrap();
+
+    let index = bundle.resolve("/", true).unwrap();
+    assert_eq!(&index.content[..], b"<html></html>");
+    assert_eq!(index.content_type, "text/html");
+    assert_eq!(index.cache_control, "no-cache");
+
+    let main = bundle.resolve("/main-5RVBRN3K.js", false).unwrap();
+    assert_eq!(main.content_type, "text/javascript");
+    assert_eq!(main.cache_control, "public, max-age=31536000, immutable");
+
+    let logo = bundle.resolve("/assets/logo.svg", false).unwrap();
+    assert_eq!(logo.content_type, "image/svg+xml");
+
+    // Pages are routed by the UI
+    let page = bundle.resolve("/kamu/com.example.dataset", true).unwrap();
+    assert_eq!(&page.content[..], b"<html></html>");
+    assert!(bundle.resolve("/assets/missing.js", false).is_none());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_web_ui_bundle_requires_index() {
+    let tempdir = tempfile::tempdir().unwrap();
+    std::fs::write(tempdir.path().join("main.js"), "main()").unwrap();
+
+    assert!(WebUiBundle::load(tempdir.path()).is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
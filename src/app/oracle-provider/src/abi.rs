@@ -0,0 +1,397 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::str::FromStr as _;
+
+use alloy::dyn_abi::DynSolValue;
+use alloy::primitives::{B256, I256, U256};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Encodes query results returned in `JsonAoa` layout into a Solidity ABI
+/// array of tuples, so that consumers can read them via
+/// `abi.decode(data, (Row[]))` where `Row` is a struct with one member per
+/// column.
+///
+/// Column types are derived from the `ArrowJson` schema of the result:
+///
+/// | Arrow type                              | Solidity type       | Notes                                |
+/// |-----------------------------------------|---------------------|--------------------------------------|
+/// | `Boolean`                               | `bool`              |                                      |
+/// | `Int8`, `Int16`, `Int32`, `Int64`       | `int8` .. `int64`   |                                      |
+/// | `UInt8`, `UInt16`, `UInt32`, `UInt64`   | `uint8` .. `uint64` |                                      |
+/// | `Decimal128(p, s)`, `Decimal256(p, s)`  | `int256`            | Fixed-point value scaled by `10^s`   |
+/// | `Utf8`, `LargeUtf8`, `Utf8View`         | `string`            |                                      |
+/// | `Binary`, `LargeBinary`, `BinaryView`   | `bytes`             |                                      |
+/// | `FixedSizeBinary(n)`                    | `bytesN`            | Falls back to `bytes` when `n > 32`  |
+/// | `Timestamp(unit, tz)`                   | `int64`             | Number of `unit`s since Unix epoch   |
+/// | `Date32`                                | `int32`             | Days since Unix epoch                |
+/// | `Date64`                                | `int64`             | Milliseconds since Unix epoch        |
+///
+/// ABI has no notion of optional values, so nulls are encoded as the zero
+/// value of the column type (`false`, `0`, empty string or bytes). Use
+/// `COALESCE` in the query if such values need to be distinguished.
+///
+/// Floating point, nested, and other types not listed above are rejected.
+/// Floats should be cast to `DECIMAL` in the query to get exact fixed-point
+/// representation.
+pub fn encode_rows(
+    schema: &serde_json::Value,
+    data: &serde_json::Value,
+) -> Result<DynSolValue, AbiEncodeError> {
+    let columns = decode_schema(schema)?;
+
+    let Some(rows) = data.as_array() else {
+        return Err(AbiEncodeError::MalformedData(
+            "Expected an array of rows".to_string(),
+        ));
+    };
+
+    let mut rows_encoded = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let Some(row) = row.as_array() else {
+            return Err(AbiEncodeError::MalformedData(
+                "Expected row to be an array of values".to_string(),
+            ));
+        };
+        if row.len() != columns.len() {
+            return Err(AbiEncodeError::MalformedData(format!(
+                "Expected {} values in a row but got {}",
+                columns.len(),
+                row.len()
+            )));
+        }
+
+        let mut row_encoded = Vec::with_capacity(columns.len());
+        for ((name, typ), value) in columns.iter().zip(row) {
+            let Some(value_encoded) = typ.encode(value) else {
+                return Err(AbiEncodeError::InvalidValue {
+                    column: name.clone(),
+                    value: value.to_string(),
+                    sol_type: typ.sol_type_name(),
+                });
+            };
+            row_encoded.push(value_encoded);
+        }
+
+        rows_encoded.push(DynSolValue::Tuple(row_encoded));
+    }
+
+    Ok(DynSolValue::Array(rows_encoded))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, thiserror::Error)]
+pub enum AbiEncodeError {
+    #[error("Malformed schema: {0}")]
+    MalformedSchema(String),
+    #[error("Malformed data: {0}")]
+    MalformedData(String),
+    #[error("Column '{column}' of type {data_type} is not supported by ABI encoding")]
+    UnsupportedType { column: String, data_type: String },
+    #[error("Column '{column}' contains value {value} that cannot be encoded as {sol_type}")]
+    InvalidValue {
+        column: String,
+        value: String,
+        sol_type: String,
+    },
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn decode_schema(
+    schema: &serde_json::Value,
+) -> Result<Vec<(String, AbiColumnType)>, AbiEncodeError> {
+    let Some(fields) = schema.get("fields").and_then(serde_json::Value::as_array) else {
+        return Err(AbiEncodeError::MalformedSchema(
+            "Expected an ArrowJson schema with 'fields' array".to_string(),
+        ));
+    };
+
+    let mut columns = Vec::with_capacity(fields.len());
+
+    for field in fields {
+        let (Some(name), Some(data_type)) = (
+            field.get("name").and_then(serde_json::Value::as_str),
+            field.get("data_type"),
+        ) else {
+            return Err(AbiEncodeError::MalformedSchema(format!(
+                "Expected field to have 'name' and 'data_type': {field}"
+            )));
+        };
+
+        let Some(typ) = AbiColumnType::from_arrow_json(data_type) else {
+            return Err(AbiEncodeError::UnsupportedType {
+                column: name.to_string(),
+                data_type: data_type.to_string(),
+            });
+        };
+
+        columns.push((name.to_string(), typ));
+    }
+
+    Ok(columns)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AbiColumnType {
+    Bool,
+    Int(usize),
+    Uint(usize),
+    Decimal(u8),
+    String,
+    Bytes,
+    FixedBytes(usize),
+    Timestamp(TimeUnit),
+    Date32,
+    Date64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeUnit {
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+impl AbiColumnType {
+    /// Parses the `data_type` of a field in the serde representation of
+    /// `arrow::datatypes::Schema`
+    fn from_arrow_json(data_type: &serde_json::Value) -> Option<Self> {
+        use serde_json::Value as V;
+
+        match data_type {
+            V::String(name) => match name.as_str() {
+                "Boolean" => Some(Self::Bool),
+                "Int8" => Some(Self::Int(8)),
+                "Int16" => Some(Self::Int(16)),
+                "Int32" => Some(Self::Int(32)),
+                "Int64" => Some(Self::Int(64)),
+                "UInt8" => Some(Self::Uint(8)),
+                "UInt16" => Some(Self::Uint(16)),
+                "UInt32" => Some(Self::Uint(32)),
+                "UInt64" => Some(Self::Uint(64)),
+                "Utf8" | "LargeUtf8" | "Utf8View" => Some(Self::String),
+                "Binary" | "LargeBinary" | "BinaryView" => Some(Self::Bytes),
+                "Date32" => Some(Self::Date32),
+                "Date64" => Some(Self::Date64),
+                _ => None,
+            },
+            V::Object(obj) if obj.len() == 1 => {
+                let (name, params) = obj.iter().next().unwrap();
+                match (name.as_str(), params) {
+                    ("Decimal128" | "Decimal256", V::Array(params)) if params.len() == 2 => params
+                        [1]
+                    .as_u64()
+                    .and_then(|scale| u8::try_from(scale).ok())
+                    .map(Self::Decimal),
+                    ("FixedSizeBinary", V::Number(size)) => match size.as_u64()? {
+                        size @ 1..=32 => Some(Self::FixedBytes(usize::try_from(size).ok()?)),
+                        _ => Some(Self::Bytes),
+                    },
+                    ("Timestamp", V::Array(params)) if !params.is_empty() => {
+                        let unit = match params[0].as_str()? {
+                            "Second" => TimeUnit::Second,
+                            "Millisecond" => TimeUnit::Millisecond,
+                            "Microsecond" => TimeUnit::Microsecond,
+                            "Nanosecond" => TimeUnit::Nanosecond,
+                            _ => return None,
+                        };
+                        Some(Self::Timestamp(unit))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn sol_type_name(self) -> String {
+        match self {
+            Self::Bool => "bool".to_string(),
+            Self::Int(bits) => format!("int{bits}"),
+            Self::Uint(bits) => format!("uint{bits}"),
+            Self::Decimal(_) => "int256".to_string(),
+            Self::String => "string".to_string(),
+            Self::Bytes => "bytes".to_string(),
+            Self::FixedBytes(size) => format!("bytes{size}"),
+            Self::Timestamp(_) | Self::Date64 => "int64".to_string(),
+            Self::Date32 => "int32".to_string(),
+        }
+    }
+
+    fn zero_value(self) -> DynSolValue {
+        match self {
+            Self::Bool => DynSolValue::Bool(false),
+            Self::Int(bits) => DynSolValue::Int(I256::ZERO, bits),
+            Self::Uint(bits) => DynSolValue::Uint(U256::ZERO, bits),
+            Self::Decimal(_) => DynSolValue::Int(I256::ZERO, 256),
+            Self::String => DynSolValue::String(String::new()),
+            Self::Bytes => DynSolValue::Bytes(Vec::new()),
+            Self::FixedBytes(size) => DynSolValue::FixedBytes(B256::ZERO, size),
+            Self::Timestamp(_) | Self::Date64 => DynSolValue::Int(I256::ZERO, 64),
+            Self::Date32 => DynSolValue::Int(I256::ZERO, 32),
+        }
+    }
+
+    fn encode(self, value: &serde_json::Value) -> Option<DynSolValue> {
+        use serde_json::Value as V;
+
+        if value.is_null() {
+            return Some(self.zero_value());
+        }
+
+        match self {
+            Self::Bool => value.as_bool().map(DynSolValue::Bool),
+            Self::Int(bits) => {
+                let v = match value {
+                    V::Number(n) => I256::try_from(n.as_i64()?).ok()?,
+                    V::String(s) => I256::from_dec_str(s).ok()?,
+                    _ => return None,
+                };
+                Some(DynSolValue::Int(v, bits))
+            }
+            Self::Uint(bits) => {
+                let v = match value {
+                    V::Number(n) => U256::from(n.as_u64()?),
+                    V::String(s) => U256::from_str(s).ok()?,
+                    _ => return None,
+                };
+                Some(DynSolValue::Uint(v, bits))
+            }
+            Self::Decimal(scale) => {
+                let v = match value {
+                    V::Number(n) => parse_decimal(&n.to_string(), scale)?,
+                    V::String(s) => parse_decimal(s, scale)?,
+                    _ => return None,
+                };
+                Some(DynSolValue::Int(v, 256))
+            }
+            Self::String => value.as_str().map(|s| DynSolValue::String(s.to_string())),
+            Self::Bytes => Some(DynSolValue::Bytes(decode_hex(value.as_str()?)?)),
+            Self::FixedBytes(size) => {
+                let bytes = decode_hex(value.as_str()?)?;
+                if bytes.len() != size {
+                    return None;
+                }
+                Some(DynSolValue::FixedBytes(
+                    B256::right_padding_from(&bytes),
+                    size,
+                ))
+            }
+            Self::Timestamp(unit) => {
+                let v = match value {
+                    V::Number(n) => n.as_i64()?,
+                    V::String(s) => {
+                        let dt = parse_datetime(s)?;
+                        match unit {
+                            TimeUnit::Second => dt.timestamp(),
+                            TimeUnit::Millisecond => dt.timestamp_millis(),
+                            TimeUnit::Microsecond => dt.timestamp_micros(),
+                            TimeUnit::Nanosecond => dt.timestamp_nanos_opt()?,
+                        }
+                    }
+                    _ => return None,
+                };
+                Some(DynSolValue::Int(I256::try_from(v).ok()?, 64))
+            }
+            Self::Date32 => {
+                let v = match value {
+                    V::Number(n) => n.as_i64()?,
+                    V::String(s) => {
+                        let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+                        date.signed_duration_since(DateTime::UNIX_EPOCH.date_naive())
+                            .num_days()
+                    }
+                    _ => return None,
+                };
+                Some(DynSolValue::Int(I256::try_from(v).ok()?, 32))
+            }
+            Self::Date64 => {
+                let v = match value {
+                    V::Number(n) => n.as_i64()?,
+                    V::String(s) => {
+                        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+                            date.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis()
+                        } else {
+                            parse_datetime(s)?.timestamp_millis()
+                        }
+                    }
+                    _ => return None,
+                };
+                Some(DynSolValue::Int(I256::try_from(v).ok()?, 64))
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Converts decimal string like `-12.34` into an integer scaled by
+/// `10^scale`
+fn parse_decimal(s: &str, scale: u8) -> Option<I256> {
+    let scale = usize::from(scale);
+
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    // Tolerate extra trailing zeroes, but never lose precision
+    let frac_part = if frac_part.len() > scale {
+        let (kept, dropped) = frac_part.split_at(scale);
+        if dropped.bytes().any(|c| c != b'0') {
+            return None;
+        }
+        kept
+    } else {
+        frac_part
+    };
+
+    let mut digits = String::with_capacity(1 + int_part.len() + scale);
+    digits.push_str(sign);
+    digits.push_str(int_part);
+    digits.push_str(frac_part);
+    digits.extend(std::iter::repeat_n('0', scale - frac_part.len()));
+
+    I256::from_dec_str(&digits).ok()
+}
+
+fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    // Timestamps without timezone are rendered without an offset
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .map(|dt| dt.and_utc())
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).ok()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
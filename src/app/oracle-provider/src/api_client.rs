@@ -13,7 +13,7 @@ use internal_error::*;
 
 /// Client interface for making ODF data queries
 #[async_trait::async_trait]
-pub trait OdfApiClient {
+pub trait OdfApiClient: Send + Sync {
     async fn query(&self, request: QueryRequest) -> Result<QueryResponse, QueryError>;
 }
 
@@ -35,6 +35,10 @@ pub struct QueryRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data_format: Option<DataFormat>,
 
+    /// What representation to use for the schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_format: Option<SchemaFormat>,
+
     /// What information to include
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include: Vec<Include>,
@@ -76,6 +80,14 @@ pub struct Outputs {
 
     /// How data is layed out in the response
     pub data_format: DataFormat,
+
+    /// Schema of the resulting data
+    #[serde(default)]
+    pub schema: Option<serde_json::Value>,
+
+    /// What representation is used for the schema
+    #[serde(default)]
+    pub schema_format: Option<SchemaFormat>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -139,6 +151,17 @@ pub enum DataFormat {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SchemaFormat {
+    ArrowJson,
+    OdfJson,
+    OdfYaml,
+    Parquet,
+    ParquetJson,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DatasetState {
@@ -177,9 +200,19 @@ pub struct ApiRequestError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Account the requests are authenticated as
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountResponse {
+    pub account_name: String,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
 pub struct OdfApiClientRest {
     client: reqwest::Client,
     query_url: url::Url,
+    account_url: url::Url,
 }
 
 #[async_trait::async_trait]
@@ -231,9 +264,36 @@ impl OdfApiClientRest {
             .build()
             .int_err()?;
 
-        let query_url =
-            url::Url::parse(&format!("{}/query", url.as_str().trim_end_matches('/'))).unwrap();
+        let base_url = url.as_str().trim_end_matches('/');
+        let query_url = url::Url::parse(&format!("{base_url}/query")).unwrap();
+        let account_url = url::Url::parse(&format!("{base_url}/accounts/me")).unwrap();
 
-        Ok(Self { client, query_url })
+        Ok(Self {
+            client,
+            query_url,
+            account_url,
+        })
+    }
+
+    /// Returns the account the access token belongs to, failing for anonymous
+    /// and rejected credentials
+    pub async fn current_account(&self) -> Result<AccountResponse, QueryError> {
+        let http_resp = self
+            .client
+            .get(self.account_url.clone())
+            .send()
+            .await
+            .int_err()?;
+
+        match http_resp.status() {
+            reqwest::StatusCode::OK => Ok(http_resp.json().await.int_err()?),
+            status => {
+                let body = http_resp.text().await.ok();
+                Err(QueryError::ApiRequestError(ApiRequestError {
+                    status,
+                    body,
+                }))
+            }
+        }
     }
 }
@@ -7,25 +7,29 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::future::IntoFuture as _;
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use alloy::network::EthereumWallet;
+use alloy::primitives::U256;
 use alloy::providers::{DynProvider, Provider, ProviderBuilder};
 use alloy::signers::Signer as _;
 use alloy::signers::local::PrivateKeySigner;
 use internal_error::*;
 use observability::axum::unknown_fallback_handler;
+use observability::metrics::MetricsProvider as _;
 
 use crate::api_client::{OdfApiClient, OdfApiClientRest};
 use crate::provider::*;
-use crate::{Cli, Config};
+use crate::{CheckArgs, Cli, Config};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 const BINARY_NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+const HTTP_SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 const DEFAULT_RUST_LOG: &str = "debug,kamu=trace,alloy_transport_http=info,alloy_rpc_client=info,\
                                 reqwest=info,hyper=info,h2=info";
 
@@ -36,6 +40,8 @@ pub async fn run(args: Cli, config: Config) -> Result<(), InternalError> {
 
     let http_address = config.http_address.parse().unwrap();
     let http_port = config.http_port;
+    let gas_spend_token = config.gas_spend_token.clone();
+    let drain_timeout: std::time::Duration = config.shutdown_drain_timeout.into();
 
     let rpc_client = init_rpc_client(&config).await?;
     let api_client = init_api_client(&config).await?;
@@ -48,28 +54,53 @@ pub async fn run(args: Cli, config: Config) -> Result<(), InternalError> {
 
     let provider = OdfOracleProvider::new(config, rpc_client, api_client, metrics);
 
-    let catalog = dill::CatalogBuilder::new().add_value(metrics_reg).build();
+    let catalog = dill::CatalogBuilder::new()
+        .add_value(metrics_reg)
+        .add_value(provider.gas_spend())
+        .build();
 
-    let (http_server, local_addr) = build_http_server(http_address, http_port, catalog).await?;
+    let (http_server, local_addr) =
+        build_http_server(http_address, http_port, gas_spend_token, catalog).await?;
 
     tracing::info!("HTTP API is listening on {}", local_addr);
 
     let shutdown_requested = graceful_shutdown::trap_signals();
 
-    let http_server = Box::pin(async move {
-        let server_with_graceful_shutdown = http_server.with_graceful_shutdown(async move {
-            shutdown_requested.await;
-        });
-
-        server_with_graceful_shutdown.await
-    });
+    // Stop scanning for new requests, but let in-flight ones complete before
+    // stopping the HTTP API
+    let mut coordinator = graceful_shutdown::ShutdownCoordinator::new();
+    let drain_requests = coordinator.add_phase("drain-requests", drain_timeout);
+    let stop_http = coordinator.add_phase("stop-http", HTTP_SHUTDOWN_TIMEOUT);
 
     tracing::info!("Entering provider loop");
 
-    tokio::select! {
-        res = http_server => { res.int_err() },
-        res = provider.run() => { res.int_err() },
-    }
+    let mut provider_task = drain_requests.spawn(provider.run(drain_requests.token()));
+    let mut http_task = stop_http.spawn(
+        http_server
+            .with_graceful_shutdown(stop_http.cancelled())
+            .into_future(),
+    );
+
+    let res = tokio::select! {
+        res = &mut http_task => res.int_err().and_then(|res| res.int_err()),
+        res = &mut provider_task => res.int_err().and_then(|res| res),
+        () = shutdown_requested => Ok(()),
+    };
+
+    let shutdown_res = coordinator.shutdown().await;
+
+    res?;
+    shutdown_res.int_err()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Prints a pass/fail report of preflight checks and returns whether all of
+/// them succeeded
+pub async fn check(args: &CheckArgs, config: &Config) -> bool {
+    let report = crate::check::run_checks(config, U256::from(args.min_balance)).await;
+    print!("{report}");
+    report.is_success()
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -96,9 +127,10 @@ pub struct InvalidChainId {
 
 pub async fn init_rpc_client(config: &Config) -> Result<DynProvider, InternalError> {
     // Prepare wallet
-    let signer = PrivateKeySigner::from_str(config.provider_private_key.as_str())
-        .unwrap()
-        .with_chain_id(Some(config.chain_id));
+    let signer = match PrivateKeySigner::from_str(config.provider_private_key.as_str()) {
+        Ok(signer) => signer.with_chain_id(Some(config.chain_id)),
+        Err(err) => return InternalError::bail(format!("Invalid provider private key: {err}")),
+    };
     let wallet = EthereumWallet::from(signer);
 
     // Init RPC client
@@ -138,6 +170,7 @@ pub async fn init_api_client(config: &Config) -> Result<Arc<dyn OdfApiClient>, I
 async fn build_http_server(
     address: std::net::IpAddr,
     http_port: u16,
+    gas_spend_token: Option<String>,
     catalog: dill::Catalog,
 ) -> Result<
     (
@@ -159,6 +192,7 @@ async fn build_http_server(
             "/system/metrics",
             axum::routing::get(observability::metrics::metrics_handler),
         )
+        .merge(crate::gas::gas_spend_router(gas_spend_token.as_deref()))
         .fallback(unknown_fallback_handler)
         .layer(axum::extract::Extension(catalog));
 
@@ -0,0 +1,346 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::str::FromStr as _;
+
+use alloy::primitives::U256;
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::signers::local::PrivateKeySigner;
+
+use crate::Config;
+use crate::api_client::*;
+use crate::provider::IOdfProvider;
+use crate::publication::Publication;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Runs preflight checks of the provider's configuration and its
+/// environment without submitting any transactions.
+///
+/// Checks that depend on a failed one are reported as skipped.
+pub async fn run_checks(config: &Config, min_balance: U256) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    check_config(config, &mut report);
+
+    // Connecting over HTTP doesn't open a connection, so the endpoint is probed
+    // with a request
+    let rpc_client = match ProviderBuilder::new()
+        .connect(config.rpc_url.as_str())
+        .await
+    {
+        Ok(rpc_client) => {
+            let rpc_client = rpc_client.erased();
+            match rpc_client.get_chain_id().await {
+                Ok(chain_id) => {
+                    report.pass(RPC_ENDPOINT, format!("Connected to {}", config.rpc_url));
+                    Some((rpc_client, chain_id))
+                }
+                Err(err) => {
+                    report.fail(
+                        RPC_ENDPOINT,
+                        format!("Request to {} failed: {err}", config.rpc_url),
+                    );
+                    None
+                }
+            }
+        }
+        Err(err) => {
+            report.fail(
+                RPC_ENDPOINT,
+                format!("Failed to connect to {}: {err}", config.rpc_url),
+            );
+            None
+        }
+    };
+
+    if let Some((rpc_client, chain_id)) = rpc_client {
+        check_chain(config, &rpc_client, chain_id, min_balance, &mut report).await;
+    } else {
+        for name in [CHAIN_ID, ORACLE_CONTRACT, AUTHORIZATION, BALANCE] {
+            report.skip(name, "RPC endpoint is unavailable");
+        }
+    }
+
+    check_api(config, &mut report).await;
+
+    report
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const RPC_ENDPOINT: &str = "RPC endpoint";
+const CHAIN_ID: &str = "Chain ID";
+const ORACLE_CONTRACT: &str = "Oracle contract";
+const AUTHORIZATION: &str = "Provider authorization";
+const BALANCE: &str = "Provider balance";
+
+fn check_config(config: &Config, report: &mut CheckReport) {
+    match PrivateKeySigner::from_str(config.provider_private_key.as_str()) {
+        Ok(signer) if signer.address() == config.provider_address => {
+            report.pass("Provider key", "Private key matches the provider address");
+        }
+        Ok(signer) => report.fail(
+            "Provider key",
+            format!(
+                "Private key belongs to {} while provider address is {}",
+                signer.address(),
+                config.provider_address
+            ),
+        ),
+        Err(err) => report.fail("Provider key", format!("Invalid private key: {err}")),
+    }
+
+    if config.chain_id == 0 {
+        report.fail("Chain ID config", "Chain ID is not specified");
+    } else {
+        report.pass("Chain ID config", config.chain_id.to_string());
+    }
+
+    if config.blocks_stride == 0 {
+        report.fail("Blocks stride", "Must be greater than zero");
+    }
+
+    if !config.publications.is_empty() {
+        match Publication::from_configs(&config.publications) {
+            Ok(publications) => report.pass(
+                "Publications",
+                format!("{} publication(s) configured", publications.len()),
+            ),
+            Err(err) => report.fail("Publications", err.to_string()),
+        }
+    }
+}
+
+async fn check_chain(
+    config: &Config,
+    rpc_client: &DynProvider,
+    chain_id: u64,
+    min_balance: U256,
+    report: &mut CheckReport,
+) {
+    if chain_id == config.chain_id {
+        report.pass(CHAIN_ID, chain_id.to_string());
+    } else {
+        report.fail(
+            CHAIN_ID,
+            format!("Expected {} but node reports {chain_id}", config.chain_id),
+        );
+    }
+
+    let has_contract = match rpc_client.get_code_at(config.oracle_contract_address).await {
+        Ok(code) if !code.is_empty() => {
+            report.pass(
+                ORACLE_CONTRACT,
+                format!(
+                    "Found {} bytes of code at {}",
+                    code.len(),
+                    config.oracle_contract_address
+                ),
+            );
+            true
+        }
+        Ok(_) => {
+            report.fail(
+                ORACLE_CONTRACT,
+                format!("No code deployed at {}", config.oracle_contract_address),
+            );
+            false
+        }
+        Err(err) => {
+            report.fail(ORACLE_CONTRACT, format!("Failed to get code: {err}"));
+            false
+        }
+    };
+
+    if has_contract {
+        let oracle_contract = IOdfProvider::new(config.oracle_contract_address, rpc_client.clone());
+
+        match oracle_contract
+            .canProvideResults(config.provider_address)
+            .from(config.provider_address)
+            .call()
+            .await
+        {
+            Ok(true) => report.pass(
+                AUTHORIZATION,
+                format!("{} can provide results", config.provider_address),
+            ),
+            Ok(false) => report.fail(
+                AUTHORIZATION,
+                format!(
+                    "{} is not authorized to provide results",
+                    config.provider_address
+                ),
+            ),
+            Err(err) => report.fail(
+                AUTHORIZATION,
+                format!("Failed to call canProvideResults: {err}"),
+            ),
+        }
+    } else {
+        report.skip(AUTHORIZATION, "Oracle contract is unavailable");
+    }
+
+    match rpc_client.get_balance(config.provider_address).await {
+        Ok(balance) if balance >= min_balance => {
+            report.pass(BALANCE, format!("{balance} wei"));
+        }
+        Ok(balance) => report.fail(
+            BALANCE,
+            format!("{balance} wei is below the expected minimum of {min_balance} wei"),
+        ),
+        Err(err) => report.fail(BALANCE, format!("Failed to get balance: {err}")),
+    }
+}
+
+/// Checks that the API server accepts the access token when it's configured,
+/// and that it executes queries
+pub async fn check_api(config: &Config, report: &mut CheckReport) {
+    const API: &str = "API server";
+    const ACCESS_TOKEN: &str = "API access token";
+
+    let api_client =
+        match OdfApiClientRest::new(config.api_url.clone(), config.api_access_token.clone()) {
+            Ok(api_client) => api_client,
+            Err(err) => {
+                report.fail(API, format!("Failed to create API client: {err}"));
+                return;
+            }
+        };
+
+    // Queries succeed anonymously, so the token is verified against an endpoint
+    // that requires authentication
+    if config.api_access_token.is_some() {
+        match api_client.current_account().await {
+            Ok(account) => report.pass(
+                ACCESS_TOKEN,
+                format!(
+                    "Authenticated at {} as {}",
+                    config.api_url, account.account_name
+                ),
+            ),
+            Err(QueryError::ApiRequestError(err))
+                if matches!(
+                    err.status,
+                    reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+                ) =>
+            {
+                report.fail(
+                    ACCESS_TOKEN,
+                    format!("Access token was rejected ({})", err.status),
+                );
+                report.skip(API, "Access token was rejected");
+                return;
+            }
+            Err(err) => report.fail(
+                ACCESS_TOKEN,
+                format!("Authentication at {} failed: {err}", config.api_url),
+            ),
+        }
+    }
+
+    let request = QueryRequest {
+        query: "select 1".to_string(),
+        query_dialect: Some(QueryDialect::SqlDataFusion),
+        data_format: Some(DataFormat::JsonAoa),
+        schema_format: None,
+        include: Vec::new(),
+        datasets: None,
+        skip: None,
+        limit: None,
+    };
+
+    match api_client.query(request).await {
+        Ok(_) if config.api_access_token.is_some() => {
+            report.pass(API, format!("Executed test query at {}", config.api_url))
+        }
+        Ok(_) => report.pass(
+            API,
+            format!(
+                "Executed test query at {} anonymously (no access token configured)",
+                config.api_url
+            ),
+        ),
+        Err(err) => report.fail(
+            API,
+            format!("Test query at {} failed: {err}", config.api_url),
+        ),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    pub checks: Vec<CheckOutcome>,
+}
+
+#[derive(Debug)]
+pub struct CheckOutcome {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub details: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    Skip,
+}
+
+impl CheckReport {
+    pub fn is_success(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+
+    fn push(&mut self, name: &'static str, status: CheckStatus, details: impl Into<String>) {
+        self.checks.push(CheckOutcome {
+            name,
+            status,
+            details: details.into(),
+        });
+    }
+
+    fn pass(&mut self, name: &'static str, details: impl Into<String>) {
+        self.push(name, CheckStatus::Pass, details);
+    }
+
+    fn fail(&mut self, name: &'static str, details: impl Into<String>) {
+        self.push(name, CheckStatus::Fail, details);
+    }
+
+    fn skip(&mut self, name: &'static str, details: impl Into<String>) {
+        self.push(name, CheckStatus::Skip, details);
+    }
+}
+
+impl std::fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "[{}] {}: {}", check.status, check.name, check.details)?;
+        }
+
+        let failed = self
+            .checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Fail)
+            .count();
+
+        if failed == 0 {
+            writeln!(f, "All checks passed")
+        } else {
+            writeln!(f, "{failed} check(s) failed")
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -18,13 +18,25 @@ pub struct Cli {
     pub config: PathBuf,
 
     #[command(subcommand)]
-    run: Command,
+    pub command: Command,
 }
 
 #[derive(Debug, clap::Subcommand)]
 pub enum Command {
+    /// Run the provider loop
     Run(RunArgs),
+    /// Validate the config and the environment without submitting any
+    /// transactions
+    Check(CheckArgs),
 }
 
 #[derive(Debug, clap::Args)]
 pub struct RunArgs {}
+
+#[derive(Debug, clap::Args)]
+pub struct CheckArgs {
+    /// Minimal provider balance (in wei) considered sufficient to submit
+    /// transactions
+    #[arg(long, default_value_t = 1)]
+    pub min_balance: u128,
+}
@@ -7,6 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::path::PathBuf;
+
 use alloy::primitives::Address;
 use setty::types::duration_string::DurationString;
 use url::Url;
@@ -21,6 +23,10 @@ pub struct Config {
     #[config(default = 0)]
     pub http_port: u16,
 
+    /// Bearer token required to access `/system/gas-spend` (the endpoint is
+    /// not served when not specified)
+    pub gas_spend_token: Option<String>,
+
     /// Ethereum-compatible JSON-RPC address
     #[config(default_str = "http://localhost:8545")]
     pub rpc_url: Url,
@@ -69,6 +75,15 @@ pub struct Config {
     #[config(default_str = "1m")]
     pub transaction_timeout: DurationString,
 
+    /// Maximum time to wait for in-flight requests to be executed and
+    /// submitted after receiving a shutdown signal
+    #[config(default_str = "1m")]
+    pub shutdown_drain_timeout: DurationString,
+
+    /// Path to a file where provider persists its progress to resume from it
+    /// after a restart (takes precedence over the scan_* options)
+    pub state_path: Option<PathBuf>,
+
     /// URL of the ODF-compatible API server that will execute requests
     #[config(default_str = "http://localhost:8080")]
     pub api_url: Url,
@@ -86,4 +101,70 @@ pub struct Config {
     #[config(default)]
     #[schemars(with = "Vec<String>")]
     pub ignore_consumers: Vec<Address>,
+
+    /// Consumer addresses that get their own `consumer_address` label in the
+    /// gas metrics, spend of the rest is aggregated under `other` to keep the
+    /// number of series bounded
+    #[config(default)]
+    #[schemars(with = "Vec<String>")]
+    pub gas_metrics_consumers: Vec<Address>,
+
+    /// Results that provider proactively pushes on-chain when datasets change
+    #[config(default)]
+    pub publications: Vec<PublicationConfig>,
+
+    /// How often to check heads of the published datasets for changes
+    #[config(default_str = "30s")]
+    pub publications_check_interval: DurationString,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Query result that is pushed to a contract whenever the dataset changes,
+/// similarly to price feeds
+#[derive(setty::Config)]
+pub struct PublicationConfig {
+    /// Unique name of the publication used in logs, metrics, and state
+    pub name: String,
+
+    /// Alias under which the dataset is referred to in the query
+    pub dataset_alias: String,
+
+    /// DID of the dataset whose head changes trigger re-evaluation
+    pub dataset_id: String,
+
+    /// SQL query producing the published result
+    pub sql: String,
+
+    /// Encoding of the result, same as in responses to requests
+    #[config(default)]
+    pub format: PublicationFormat,
+
+    /// Address of the contract to push results to
+    #[config(combine(replace))]
+    #[schemars(with = "String")]
+    pub target_address: Address,
+
+    /// Signature of the function that accepts the encoded result as its only
+    /// argument, e.g. `updateResult(bytes)`
+    pub target_function: String,
+
+    /// Numeric column in the first row of the result that `min_change` is
+    /// applied to
+    pub value_column: Option<String>,
+
+    /// Minimal relative change of the value since the last publication (e.g.
+    /// `0.005` for 0.5%) required to publish again
+    pub min_change: Option<f64>,
+
+    /// Maximal time between publications - the result is pushed once it
+    /// elapses even if the data did not change
+    pub heartbeat: Option<DurationString>,
+}
+
+#[derive(setty::Config, setty::Default)]
+pub enum PublicationFormat {
+    #[default]
+    Cbor,
+    Abi,
 }
@@ -0,0 +1,274 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use alloy::primitives::Address;
+use chrono::NaiveDate;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Gas spent on submitting results to a single consumer during one day (UTC)
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasSpendEntry {
+    pub date: NaiveDate,
+    pub consumer_address: Address,
+    /// Number of `provideResult` transactions included in the chain
+    pub transactions: u64,
+    pub gas_used: u64,
+    /// Sum of `gas_used * effective_gas_price` over all transactions
+    #[serde(with = "wei_as_string")]
+    pub cost_wei: u128,
+}
+
+/// Gas spend aggregated per consumer and per day
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct GasSpend {
+    /// Entries ordered by date and then by consumer address
+    entries: Vec<GasSpendEntry>,
+}
+
+impl GasSpend {
+    pub fn entries(&self) -> &[GasSpendEntry] {
+        &self.entries
+    }
+
+    pub fn record(
+        &mut self,
+        date: NaiveDate,
+        consumer_address: Address,
+        gas_used: u64,
+        effective_gas_price: u128,
+    ) {
+        let cost_wei = u128::from(gas_used).saturating_mul(effective_gas_price);
+
+        match self
+            .entries
+            .binary_search_by(|e| (e.date, e.consumer_address).cmp(&(date, consumer_address)))
+        {
+            Ok(i) => {
+                let entry = &mut self.entries[i];
+                entry.transactions += 1;
+                entry.gas_used = entry.gas_used.saturating_add(gas_used);
+                entry.cost_wei = entry.cost_wei.saturating_add(cost_wei);
+            }
+            Err(i) => self.entries.insert(
+                i,
+                GasSpendEntry {
+                    date,
+                    consumer_address,
+                    transactions: 1,
+                    gas_used,
+                    cost_wei,
+                },
+            ),
+        }
+    }
+
+    /// Totals over the whole history per consumer
+    pub fn totals_by_consumer(&self) -> Vec<GasSpendTotal> {
+        let mut totals: BTreeMap<Address, GasSpendTotal> = BTreeMap::new();
+
+        for entry in &self.entries {
+            let total = totals
+                .entry(entry.consumer_address)
+                .or_insert_with(|| GasSpendTotal {
+                    consumer_address: entry.consumer_address,
+                    transactions: 0,
+                    gas_used: 0,
+                    cost_wei: 0,
+                });
+            total.transactions += entry.transactions;
+            total.gas_used = total.gas_used.saturating_add(entry.gas_used);
+            total.cost_wei = total.cost_wei.saturating_add(entry.cost_wei);
+        }
+
+        totals.into_values().collect()
+    }
+
+    /// Renders entries as CSV with a header row
+    pub fn to_csv(&self) -> String {
+        use std::fmt::Write as _;
+
+        let mut csv = String::from("date,consumer_address,transactions,gas_used,cost_wei\n");
+        for e in &self.entries {
+            writeln!(
+                csv,
+                "{},{},{},{},{}",
+                e.date, e.consumer_address, e.transactions, e.gas_used, e.cost_wei
+            )
+            .unwrap();
+        }
+        csv
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasSpendTotal {
+    pub consumer_address: Address,
+    pub transactions: u64,
+    pub gas_used: u64,
+    #[serde(with = "wei_as_string")]
+    pub cost_wei: u128,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Shared handle to [`GasSpend`] that is updated by the provider loop and read
+/// by the admin HTTP endpoints
+#[derive(Debug, Default, Clone)]
+pub struct GasSpendTracker {
+    inner: Arc<Mutex<GasSpend>>,
+}
+
+impl GasSpendTracker {
+    pub fn record(
+        &self,
+        date: NaiveDate,
+        consumer_address: Address,
+        gas_used: u64,
+        effective_gas_price: u128,
+    ) {
+        self.inner
+            .lock()
+            .unwrap()
+            .record(date, consumer_address, gas_used, effective_gas_price);
+    }
+
+    pub fn snapshot(&self) -> GasSpend {
+        self.inner.lock().unwrap().clone()
+    }
+
+    pub fn restore(&self, gas_spend: GasSpend) {
+        *self.inner.lock().unwrap() = gas_spend;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Amounts in wei exceed the integer precision of most JSON parsers, so they
+/// are serialized as decimal strings
+mod wei_as_string {
+    pub fn serialize<S: serde::Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<u128, D::Error> {
+        let value: String = serde::Deserialize::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// HTTP
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GasSpendFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct GasSpendParams {
+    #[serde(default)]
+    pub format: GasSpendFormat,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasSpendResponse {
+    pub totals: Vec<GasSpendTotal>,
+    pub daily: Vec<GasSpendEntry>,
+}
+
+/// Routes that report the gas spend, served only when the bearer token to
+/// access them is configured as the report reveals consumers and their costs
+pub fn gas_spend_router(token: Option<&str>) -> axum::Router {
+    let Some(token) = token else {
+        return axum::Router::new();
+    };
+
+    axum::Router::new()
+        .route("/system/gas-spend", axum::routing::get(gas_spend_handler))
+        .route_layer(axum::middleware::from_fn_with_state(
+            Arc::new(token.to_string()),
+            bearer_token_middleware,
+        ))
+}
+
+async fn bearer_token_middleware(
+    axum::extract::State(expected_token): axum::extract::State<Arc<String>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse as _;
+
+    let token = request
+        .headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    if !token.is_some_and(|token| secrets_eq(token.as_bytes(), expected_token.as_bytes())) {
+        return (
+            http::StatusCode::UNAUTHORIZED,
+            [(http::header::WWW_AUTHENTICATE, "Bearer")],
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Compares secrets without leaking the position of the first mismatch via
+/// timing
+fn secrets_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Reports gas spend per consumer as JSON (default) or as CSV with daily
+/// entries when requested via `?format=csv`
+pub async fn gas_spend_handler(
+    axum::extract::Extension(catalog): axum::extract::Extension<dill::Catalog>,
+    axum::extract::Query(params): axum::extract::Query<GasSpendParams>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse as _;
+
+    let gas_spend = catalog.get_one::<GasSpendTracker>().unwrap().snapshot();
+
+    match params.format {
+        GasSpendFormat::Json => axum::Json(GasSpendResponse {
+            totals: gas_spend.totals_by_consumer(),
+            daily: gas_spend.entries().to_vec(),
+        })
+        .into_response(),
+        GasSpendFormat::Csv => (
+            [
+                (http::header::CONTENT_TYPE, "text/csv"),
+                (
+                    http::header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"gas-spend.csv\"",
+                ),
+            ],
+            gas_spend.to_csv(),
+        )
+            .into_response(),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
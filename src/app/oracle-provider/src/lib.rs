@@ -7,13 +7,18 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+pub mod abi;
 pub mod api_client;
 pub mod app;
 mod cbor;
+pub mod check;
 mod cli;
 mod config;
+pub mod gas;
 pub mod provider;
+pub mod publication;
+pub mod state;
 
-pub use cli::Cli;
-pub use config::Config;
+pub use cli::{CheckArgs, Cli, Command, RunArgs};
+pub use config::{Config, PublicationConfig, PublicationFormat};
 pub use provider::{OdfOracleProvider, OdfOracleProviderMetrics};
@@ -8,7 +8,8 @@
 // by the Apache License, Version 2.0.
 
 use clap::Parser;
-use kamu_oracle_provider::{Cli, Config};
+use kamu_oracle_provider::publication::Publication;
+use kamu_oracle_provider::{Cli, Command, Config};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -23,7 +24,7 @@ fn main() {
 
     let args = Cli::parse();
 
-    let config: Config = setty::Config::new()
+    let config: Result<Config, _> = setty::Config::new()
         .with_source(setty::source::File::<setty::format::Yaml>::new(
             &args.config,
         ))
@@ -31,8 +32,23 @@ fn main() {
             "KAMU_ORACLE_CONFIG__",
             "__",
         ))
-        .extract()
-        .unwrap();
+        .extract();
+
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Invalid config: {err}");
+            std::process::exit(1)
+        }
+    };
+
+    // Check command reports invalid publications along with other problems
+    if !matches!(args.command, Command::Check(_))
+        && let Err(err) = Publication::from_configs(&config.publications)
+    {
+        eprintln!("Invalid config: {err}");
+        std::process::exit(1)
+    }
 
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -45,6 +61,11 @@ fn main() {
 }
 
 async fn main_async(args: Cli, config: Config) -> i32 {
+    if let Command::Check(check_args) = &args.command {
+        let success = kamu_oracle_provider::app::check(check_args, &config).await;
+        return if success { 0 } else { 1 };
+    }
+
     let _guard = kamu_oracle_provider::app::init_observability();
     match kamu_oracle_provider::app::run(args, config).await {
         Ok(_) => 0,
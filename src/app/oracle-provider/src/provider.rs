@@ -11,16 +11,20 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use alloy::eips::BlockNumberOrTag;
-use alloy::primitives::U256;
+use alloy::network::TransactionBuilder as _;
+use alloy::primitives::{Address, U256};
 use alloy::providers::{DynProvider, Provider};
-use alloy::rpc::types::eth::{Filter, Log};
+use alloy::rpc::types::eth::{Filter, Log, TransactionReceipt, TransactionRequest};
 use alloy::sol_types::{SolEvent, SolEventInterface};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use internal_error::*;
 use tracing::Instrument;
 
-use crate::Config;
 use crate::api_client::*;
+use crate::gas::GasSpendTracker;
+use crate::publication::{Publication, extract_value};
+use crate::state::{ProviderState, ProviderStateStore, PublicationState};
+use crate::{Config, PublicationFormat};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -78,20 +82,45 @@ struct OdfRequest {
     pub id: u64,
     pub sql: String,
     pub aliases: Vec<(String, odf::DatasetID)>,
+    pub format: OdfResponseFormat,
     pub log: Log<IOdfProvider::SendRequest>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Encoding of the response that consumer expects
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum OdfResponseFormat {
+    /// See [`OdfResult::into_cbor`]
+    #[default]
+    Cbor,
+    /// See [`OdfResult::into_abi`]
+    Abi,
+}
+
+impl From<&PublicationFormat> for OdfResponseFormat {
+    fn from(value: &PublicationFormat) -> Self {
+        match value {
+            PublicationFormat::Cbor => Self::Cbor,
+            PublicationFormat::Abi => Self::Abi,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug)]
 struct OdfResult {
     pub request_id: u64,
+    pub consumer_address: Address,
+    pub format: OdfResponseFormat,
     pub inner: Result<OdfResultOk, OdfResultErr>,
 }
 
 #[derive(Debug)]
 struct OdfResultOk {
     pub data: serde_json::Value,
+    pub schema: Option<serde_json::Value>,
     pub state: Vec<(odf::DatasetID, odf::Multihash)>,
 }
 
@@ -103,6 +132,20 @@ struct OdfResultErr {
 impl OdfResult {
     const VERSION: u16 = 1;
 
+    pub fn encode(self) -> Result<Vec<u8>, InternalError> {
+        match self.format {
+            OdfResponseFormat::Cbor => {
+                let mut result_encoded = Vec::new();
+                ciborium::into_writer(&self.into_cbor(), &mut result_encoded).int_err()?;
+                Ok(result_encoded)
+            }
+            OdfResponseFormat::Abi => Ok(self.into_abi()),
+        }
+    }
+
+    /// Response layout in CBOR is:
+    /// - success: `[version, true, data, proof, [id1, hash1, id2, hash2, ...]]`
+    /// - failure: `[version, false, error_message]`
     pub fn into_cbor(self) -> ciborium::Value {
         use ciborium::Value as V;
 
@@ -130,6 +173,63 @@ impl OdfResult {
             ]),
         }
     }
+
+    /// Response layout in Solidity ABI encoding is:
+    /// - success: `abi.encode(uint16 version, bool ok, Row[] data, bytes proof,
+    ///   bytes[] state)`
+    /// - failure: `abi.encode(uint16 version, bool ok, string errorMessage)`
+    ///
+    /// Consumers are expected to first decode the `(uint16, bool)` prefix and
+    /// then the rest depending on the `ok` flag. See
+    /// [`crate::abi::encode_rows`] for how `Row` members map to the columns of
+    /// the result. State is flattened the same way as in CBOR layout.
+    pub fn into_abi(self) -> Vec<u8> {
+        use alloy::dyn_abi::DynSolValue as V;
+
+        let error_message = match self.inner {
+            Ok(v) => {
+                let rows = match v.schema {
+                    Some(schema) => super::abi::encode_rows(&schema, &v.data),
+                    None => Err(super::abi::AbiEncodeError::MalformedSchema(
+                        "Response does not include the schema".to_string(),
+                    )),
+                };
+
+                match rows {
+                    Ok(rows) => {
+                        let mut state = Vec::new();
+                        for (id, block_hash) in v.state {
+                            state.push(V::Bytes(id.as_bytes().to_vec()));
+                            state.push(V::Bytes(block_hash.as_bytes().to_vec()));
+                        }
+
+                        return V::Tuple(vec![
+                            V::Uint(U256::from(Self::VERSION), 16),
+                            V::Bool(true),
+                            rows,
+                            V::Bytes(Vec::new()),
+                            V::Array(state),
+                        ])
+                        .abi_encode_params();
+                    }
+                    // Results that cannot be represented in ABI are reported to the consumer
+                    // as an error instead of being retried forever
+                    Err(err) => {
+                        tracing::warn!(error = %err, "Failed to ABI-encode the result");
+                        err.to_string()
+                    }
+                }
+            }
+            Err(v) => v.error_message,
+        };
+
+        V::Tuple(vec![
+            V::Uint(U256::from(Self::VERSION), 16),
+            V::Bool(false),
+            V::String(error_message),
+        ])
+        .abi_encode_params()
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -140,10 +240,18 @@ pub struct ProviderUnauthorized;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Value of the `consumer_address` label that aggregates gas spend of the
+/// consumers not listed in `gas_metrics_consumers`
+pub const OTHER_CONSUMERS_LABEL: &str = "other";
+
+#[derive(Clone)]
 pub struct OdfOracleProviderMetrics {
     pub wallet_balance: prometheus::Gauge,
     pub api_queries_num: prometheus::IntCounter,
     pub transactions_num: prometheus::IntCounter,
+    pub gas_used: prometheus::IntCounterVec,
+    pub gas_spent_wei: prometheus::CounterVec,
+    pub publications_num: prometheus::IntCounterVec,
 }
 
 impl OdfOracleProviderMetrics {
@@ -170,13 +278,45 @@ impl OdfOracleProviderMetrics {
                 .const_label("chain_id", chain_id.to_string()),
             )
             .unwrap(),
+            gas_used: IntCounterVec::new(
+                Opts::new(
+                    "gas_used_total",
+                    "Gas used by the confirmed result transactions",
+                )
+                .const_label("chain_id", chain_id.to_string()),
+                &["consumer_address"],
+            )
+            .unwrap(),
+            gas_spent_wei: CounterVec::new(
+                Opts::new(
+                    "gas_spent_wei_total",
+                    "Amount spent on gas by the confirmed result transactions",
+                )
+                .const_label("chain_id", chain_id.to_string()),
+                &["consumer_address"],
+            )
+            .unwrap(),
+            publications_num: IntCounterVec::new(
+                Opts::new(
+                    "publications_total",
+                    "Results pushed on-chain by publications",
+                )
+                .const_label("chain_id", chain_id.to_string()),
+                &["publication"],
+            )
+            .unwrap(),
         }
     }
+}
 
-    pub fn register(&self, reg: &prometheus::Registry) -> Result<(), prometheus::Error> {
+impl observability::metrics::MetricsProvider for OdfOracleProviderMetrics {
+    fn register(&self, reg: &prometheus::Registry) -> prometheus::Result<()> {
         reg.register(Box::new(self.wallet_balance.clone()))?;
         reg.register(Box::new(self.api_queries_num.clone()))?;
         reg.register(Box::new(self.transactions_num.clone()))?;
+        reg.register(Box::new(self.gas_used.clone()))?;
+        reg.register(Box::new(self.gas_spent_wei.clone()))?;
+        reg.register(Box::new(self.publications_num.clone()))?;
         Ok(())
     }
 }
@@ -189,6 +329,8 @@ pub struct OdfOracleProvider {
     oracle_contract: IOdfProvider::IOdfProviderInstance<DynProvider>,
     api_client: Arc<dyn OdfApiClient>,
     metrics: OdfOracleProviderMetrics,
+    state_store: Option<ProviderStateStore>,
+    gas_spend: GasSpendTracker,
 }
 
 impl OdfOracleProvider {
@@ -199,6 +341,7 @@ impl OdfOracleProvider {
         metrics: OdfOracleProviderMetrics,
     ) -> Self {
         let oracle_contract = IOdfProvider::new(config.oracle_contract_address, rpc_client.clone());
+        let state_store = config.state_path.as_ref().map(ProviderStateStore::new);
 
         Self {
             config,
@@ -206,9 +349,17 @@ impl OdfOracleProvider {
             api_client,
             oracle_contract,
             metrics,
+            state_store,
+            gas_spend: GasSpendTracker::default(),
         }
     }
 
+    /// Handle to the gas spend accounting that stays valid while the provider
+    /// is running
+    pub fn gas_spend(&self) -> GasSpendTracker {
+        self.gas_spend.clone()
+    }
+
     /// Check whether the provider is authorized to submit results
     pub async fn is_authorized(&self) -> Result<bool, InternalError> {
         match self
@@ -324,14 +475,54 @@ impl OdfOracleProvider {
         Ok(approx_block_number)
     }
 
-    pub async fn run(self) -> Result<(), InternalError> {
-        let mut from_block = self.get_starting_block().await?;
+    /// Runs the provider loop until the `shutdown` token is cancelled.
+    ///
+    /// Shutdown is observed between the pages of logs and between the
+    /// requests of a block range, so that requests that are already being
+    /// executed or submitted get a chance to finish. An interrupted range is
+    /// not marked as processed and is scanned again after a restart, skipping
+    /// the requests that were fulfilled meanwhile. Callers are expected to
+    /// bound the time they wait for this future to complete after signalling.
+    pub async fn run(
+        self,
+        shutdown: graceful_shutdown::CancellationToken,
+    ) -> Result<(), InternalError> {
+        let publications = Publication::from_configs(&self.config.publications).int_err()?;
+        let publications_check_interval: Duration = self.config.publications_check_interval.into();
+        let mut last_publications_check: Option<std::time::Instant> = None;
+
+        let mut state = self.load_state()?;
+        let mut persisted_state = state.clone();
+        self.gas_spend.restore(std::mem::take(&mut state.gas_spend));
+
+        let mut from_block = if let Some(last_processed_block) = state.last_processed_block {
+            tracing::info!(last_processed_block, "Resuming from the persisted state");
+            last_processed_block + 1
+        } else {
+            self.get_starting_block().await?
+        };
         let mut idle_start = None;
 
         // Pre-flight loop: Wait until we have basic pre-requisites to function
-        self.wait_for_auth_and_balance().await?;
+        tokio::select! {
+            res = self.wait_for_auth_and_balance() => res?,
+            () = shutdown.cancelled() => {
+                tracing::info!("Shutdown requested before provider became operational");
+                return Ok(());
+            }
+        }
+
+        while !shutdown.is_cancelled() {
+            if !publications.is_empty()
+                && last_publications_check
+                    .is_none_or(|t| t.elapsed() >= publications_check_interval)
+            {
+                last_publications_check = Some(std::time::Instant::now());
+                self.process_publications(&publications, &mut state).await;
+                state.gas_spend = self.gas_spend.snapshot();
+                self.save_state(&state, &mut persisted_state)?;
+            }
 
-        loop {
             // TODO: Operate on blocks that have >N confirmations to avoid running into too
             // many reorgs?
             let to_block = self.rpc_client.get_block_number().await.int_err()?;
@@ -343,7 +534,10 @@ impl OdfOracleProvider {
                     idle_start = Some(std::time::Instant::now());
                 }
 
-                tokio::time::sleep(self.config.loop_idle_time.into()).await;
+                tokio::select! {
+                    () = tokio::time::sleep(self.config.loop_idle_time.into()) => {}
+                    () = shutdown.cancelled() => {}
+                }
                 continue;
             } else {
                 idle_start = None;
@@ -353,11 +547,21 @@ impl OdfOracleProvider {
                 observability::tracing::root_span!("process_block_range", from_block, to_block);
 
             match self
-                .process_block_range(from_block, to_block)
+                .process_block_range(from_block, to_block, &shutdown)
                 .instrument(span)
                 .await
             {
                 Ok(()) => Ok(()),
+                Err(ProcessBlockRangeError::Interrupted) => {
+                    tracing::info!(
+                        from_block,
+                        to_block,
+                        "Block range processing was interrupted by shutdown",
+                    );
+                    state.gas_spend = self.gas_spend.snapshot();
+                    self.save_state(&state, &mut persisted_state)?;
+                    break;
+                }
                 Err(ProcessBlockRangeError::InconsistentHeadBlock) => {
                     tracing::warn!(
                         "Detected inconsistent block head - assuming a node load-balancing issue \
@@ -370,7 +574,18 @@ impl OdfOracleProvider {
 
             // TODO: Chain reorg resistance
             from_block = to_block + 1;
+
+            state.last_processed_block = Some(to_block);
+            state.gas_spend = self.gas_spend.snapshot();
+            self.save_state(&state, &mut persisted_state)?;
         }
+
+        tracing::info!(
+            last_processed_block = ?state.last_processed_block,
+            "Provider loop stopped",
+        );
+
+        Ok(())
     }
 
     pub async fn run_once(
@@ -395,13 +610,42 @@ impl OdfOracleProvider {
             return Ok(());
         }
 
-        self.process_block_range(from_block, to_block)
-            .await
-            .int_err()?;
+        self.process_block_range(
+            from_block,
+            to_block,
+            &graceful_shutdown::CancellationToken::new(),
+        )
+        .await
+        .int_err()?;
 
         Ok(())
     }
 
+    fn load_state(&self) -> Result<ProviderState, InternalError> {
+        match &self.state_store {
+            Some(state_store) => state_store.load(),
+            None => Ok(ProviderState::default()),
+        }
+    }
+
+    /// Persists the state unless it's the same as the last persisted one
+    fn save_state(
+        &self,
+        state: &ProviderState,
+        persisted_state: &mut ProviderState,
+    ) -> Result<(), InternalError> {
+        let Some(state_store) = &self.state_store else {
+            return Ok(());
+        };
+        if state == persisted_state {
+            return Ok(());
+        }
+
+        state_store.save(state)?;
+        persisted_state.clone_from(state);
+        Ok(())
+    }
+
     async fn wait_for_auth_and_balance(&self) -> Result<(), InternalError> {
         let mut first = true;
         loop {
@@ -435,20 +679,194 @@ impl OdfOracleProvider {
         Ok(())
     }
 
+    /// Re-evaluates publications whose datasets changed or whose heartbeat is
+    /// due and pushes their results on-chain.
+    ///
+    /// Failures are logged and retried on the next check so that one broken
+    /// publication does not stop serving requests.
+    async fn process_publications(&self, publications: &[Publication], state: &mut ProviderState) {
+        for publication in publications {
+            let span = observability::tracing::root_span!(
+                "process_publication",
+                publication = %publication.name
+            );
+
+            let publication_state = state
+                .publications
+                .entry(publication.name.clone())
+                .or_default();
+
+            if let Err(err) = self
+                .process_publication(publication, publication_state)
+                .instrument(span)
+                .await
+            {
+                tracing::error!(
+                    publication = %publication.name,
+                    error = ?err,
+                    error_msg = %err,
+                    "Failed to process publication",
+                );
+            }
+        }
+    }
+
+    async fn process_publication(
+        &self,
+        publication: &Publication,
+        state: &mut PublicationState,
+    ) -> Result<(), InternalError> {
+        let now = Utc::now();
+        let heartbeat_due = publication.is_heartbeat_due(state.last_published_at, now);
+
+        let Some(head) = self.get_dataset_head(publication).await? else {
+            tracing::warn!("Published dataset was not found");
+            return Ok(());
+        };
+
+        if !heartbeat_due && state.last_evaluated_block_hash.as_ref() == Some(&head) {
+            tracing::debug!(%head, "Dataset did not change");
+            return Ok(());
+        }
+
+        let aliases = vec![(
+            publication.dataset_alias.clone(),
+            publication.dataset_id.clone(),
+        )];
+
+        // Schema is needed to locate the value column regardless of the format
+        let Some(inner) = self
+            .query_api(publication.sql.clone(), aliases, true)
+            .await?
+        else {
+            tracing::warn!("Published dataset was not found");
+            return Ok(());
+        };
+
+        let value = match &inner {
+            Ok(ok) => match (&publication.value_column, &ok.schema) {
+                (Some(column), Some(schema)) => extract_value(schema, &ok.data, column),
+                _ => None,
+            },
+            Err(err) => {
+                // Unlike with requests there is nobody awaiting an error on-chain
+                tracing::warn!(error_message = %err.error_message, "Publication query failed");
+                return Ok(());
+            }
+        };
+
+        let last_published_value = state.last_published_at.map(|_| state.last_published_value);
+
+        if !heartbeat_due && !publication.is_change_significant(last_published_value, value) {
+            tracing::debug!(
+                ?value,
+                last_published_value = ?state.last_published_value,
+                "Change is below the threshold",
+            );
+            state.last_evaluated_block_hash = Some(head);
+            return Ok(());
+        }
+
+        let result_encoded = OdfResult {
+            request_id: 0,
+            consumer_address: publication.target_address,
+            format: (&publication.format).into(),
+            inner,
+        }
+        .encode()?;
+
+        tracing::info!(?value, heartbeat_due, "Publishing result");
+
+        let transaction = TransactionRequest::default()
+            .with_from(self.config.provider_address)
+            .with_to(publication.target_address)
+            .with_input(publication.encode_call(result_encoded));
+
+        self.submit_transaction(transaction, publication.target_address)
+            .await?;
+
+        self.metrics
+            .publications_num
+            .with_label_values(&[publication.name.as_str()])
+            .inc();
+
+        state.last_evaluated_block_hash = Some(head);
+        state.last_published_value = value;
+        state.last_published_at = Some(now);
+        Ok(())
+    }
+
+    /// Returns the current head of the published dataset as seen by the API
+    async fn get_dataset_head(
+        &self,
+        publication: &Publication,
+    ) -> Result<Option<String>, InternalError> {
+        // Query with no output rows only to learn the state of the inputs
+        let request = QueryRequest {
+            query: format!(
+                "select * from \"{}\" limit 0",
+                publication.dataset_alias.replace('"', "\"\"")
+            ),
+            query_dialect: Some(QueryDialect::SqlDataFusion),
+            data_format: Some(DataFormat::JsonAoa),
+            schema_format: None,
+            include: vec![Include::Input],
+            datasets: Some(vec![DatasetState {
+                alias: publication.dataset_alias.clone(),
+                id: publication.dataset_id.clone(),
+                block_hash: None,
+            }]),
+            skip: None,
+            limit: None,
+        };
+
+        self.metrics.api_queries_num.inc();
+
+        match self.api_client.query(request).await {
+            Ok(response) => Ok(response
+                .input
+                .and_then(|input| input.datasets)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|ds| ds.id == publication.dataset_id)
+                .and_then(|ds| ds.block_hash)
+                .map(|block_hash| block_hash.to_string())),
+            Err(QueryError::DatasetNotFound(_)) => Ok(None),
+            Err(QueryError::BadRequest(msg)) => Err(InternalError::new(msg)),
+            Err(QueryError::ApiRequestError(err)) => Err(err.int_err()),
+            Err(QueryError::Internal(err)) => Err(err),
+        }
+    }
+
     async fn process_block_range(
         &self,
         from_block: u64,
         to_block: u64,
+        shutdown: &graceful_shutdown::CancellationToken,
     ) -> Result<(), ProcessBlockRangeError> {
         // TODO: Refactor towards concurrent streams model where blockchain scanning
         // continues independently from execution and submitting
         // transactions
-        let pending_requests = self.scan_block_range(from_block, to_block).await?;
-        if !pending_requests.is_empty() {
-            let results = self.process_request_batch(pending_requests).await?;
-            self.send_results(results).await?;
+        let pending_requests = self
+            .scan_block_range(from_block, to_block, shutdown)
+            .await?;
+        if pending_requests.is_empty() {
+            return Ok(());
+        }
+
+        let (results, completed) = self
+            .process_request_batch(pending_requests, shutdown)
+            .await?;
+
+        // Results that were already computed are still submitted, so that the
+        // consumers don't have to wait for the restart
+        self.send_results(results).await?;
+
+        if completed {
+            Ok(())
+        } else {
+            Err(ProcessBlockRangeError::Interrupted)
         }
-        Ok(())
     }
 
     // TODO: This code is much more complex than it should be because of the issue
@@ -462,6 +880,7 @@ impl OdfOracleProvider {
         &self,
         from_block: u64,
         to_block: u64,
+        shutdown: &graceful_shutdown::CancellationToken,
     ) -> Result<Vec<Log<IOdfProvider::SendRequest>>, ProcessBlockRangeError> {
         assert!(from_block <= to_block);
 
@@ -477,6 +896,10 @@ impl OdfOracleProvider {
         let mut from_block_page = from_block;
 
         while from_block_page <= to_block {
+            if shutdown.is_cancelled() {
+                return Err(ProcessBlockRangeError::Interrupted);
+            }
+
             let to_block_page = u64::min(to_block, from_block_page + self.config.blocks_stride);
 
             tracing::info!(
@@ -548,14 +971,25 @@ impl OdfOracleProvider {
         Ok(pending_requests)
     }
 
+    /// Executes the requests until the `shutdown` token is cancelled, returning
+    /// the results and whether all requests were executed
     #[tracing::instrument(level = "info", skip_all)]
     async fn process_request_batch(
         &self,
         requests_batch: Vec<Log<IOdfProvider::SendRequest>>,
-    ) -> Result<Vec<OdfResult>, InternalError> {
+        shutdown: &graceful_shutdown::CancellationToken,
+    ) -> Result<(Vec<OdfResult>, bool), InternalError> {
         let mut results = Vec::new();
 
         for log in requests_batch {
+            if shutdown.is_cancelled() {
+                tracing::info!(
+                    num_results = results.len(),
+                    "Stopping to execute requests due to shutdown",
+                );
+                return Ok((results, false));
+            }
+
             // TODO: Handle malformed requests
             let request = Self::decode_request(log)?;
             // TODO: Handle invalid requests
@@ -565,7 +999,7 @@ impl OdfOracleProvider {
             }
         }
 
-        Ok(results)
+        Ok((results, true))
     }
 
     /// Request layout in CBOR is:
@@ -574,6 +1008,7 @@ impl OdfOracleProvider {
     ///   "ds", "alias1", "did:odf:...",
     ///   "ds", "alias2", "did:odf:...",
     ///   "sql", "select ...",
+    ///   "fmt", "abi", // optional, defaults to "cbor"
     ///   ...
     /// ]
     fn decode_request(log: Log<IOdfProvider::SendRequest>) -> Result<OdfRequest, InternalError> {
@@ -594,6 +1029,7 @@ impl OdfOracleProvider {
 
         let mut sql = None;
         let mut aliases = Vec::new();
+        let mut format = OdfResponseFormat::default();
 
         while let Some(key) = raw.next() {
             let ciborium::Value::Text(key) = key else {
@@ -618,6 +1054,16 @@ impl OdfOracleProvider {
                     };
                     sql = Some(query);
                 }
+                "fmt" => {
+                    let Some(ciborium::Value::Text(fmt)) = raw.next() else {
+                        Err("Expected a response format".int_err())?
+                    };
+                    format = match fmt.as_str() {
+                        "cbor" => OdfResponseFormat::Cbor,
+                        "abi" => OdfResponseFormat::Abi,
+                        _ => Err(format!("Unsupported response format {fmt}").int_err())?,
+                    };
+                }
                 _ => Err(format!("Unknown key {key}").int_err())?,
             }
         }
@@ -630,6 +1076,7 @@ impl OdfOracleProvider {
             id,
             sql,
             aliases,
+            format,
             log,
         })
     }
@@ -638,14 +1085,52 @@ impl OdfOracleProvider {
     async fn execute_query(&self, request: OdfRequest) -> Result<Option<OdfResult>, InternalError> {
         tracing::debug!(?request, "Executing API query");
 
+        let consumer_address = request.log.inner.consumerAddr;
+
+        // ABI encoding needs column types to pick corresponding Solidity types
+        let include_schema = request.format == OdfResponseFormat::Abi;
+
+        let inner = match self
+            .query_api(request.sql, request.aliases, include_schema)
+            .await?
+        {
+            Some(inner) => inner,
+            None => return Ok(None),
+        };
+
+        Ok(Some(OdfResult {
+            request_id: request.id,
+            consumer_address,
+            format: request.format,
+            inner,
+        }))
+    }
+
+    /// Executes the query via the API. Returns `None` if some of the datasets
+    /// were not found and `Some(Err(..))` if the query itself is invalid.
+    async fn query_api(
+        &self,
+        sql: String,
+        aliases: Vec<(String, odf::DatasetID)>,
+        include_schema: bool,
+    ) -> Result<Option<Result<OdfResultOk, OdfResultErr>>, InternalError> {
+        let (include, schema_format) = if include_schema {
+            (
+                vec![Include::Input, Include::Schema],
+                Some(SchemaFormat::ArrowJson),
+            )
+        } else {
+            (vec![Include::Input], None)
+        };
+
         let rest_request = QueryRequest {
-            include: vec![Include::Input],
-            query: request.sql,
+            include,
+            query: sql,
             query_dialect: Some(QueryDialect::SqlDataFusion),
             data_format: Some(DataFormat::JsonAoa),
+            schema_format,
             datasets: Some(
-                request
-                    .aliases
+                aliases
                     .into_iter()
                     .map(|(alias, id)| DatasetState {
                         alias,
@@ -664,27 +1149,22 @@ impl OdfOracleProvider {
         match self.api_client.query(rest_request).await {
             Ok(rest_response) => {
                 tracing::debug!(?rest_response, "Writing successful response");
-                Ok(Some(OdfResult {
-                    request_id: request.id,
-                    inner: Ok(OdfResultOk {
-                        data: rest_response.output.data,
-                        state: rest_response
-                            .input
-                            .unwrap()
-                            .datasets
-                            .unwrap_or_default()
-                            .into_iter()
-                            .map(|i| (i.id, i.block_hash.unwrap()))
-                            .collect(),
-                    }),
-                }))
+                Ok(Some(Ok(OdfResultOk {
+                    data: rest_response.output.data,
+                    schema: rest_response.output.schema,
+                    state: rest_response
+                        .input
+                        .unwrap()
+                        .datasets
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|i| (i.id, i.block_hash.unwrap()))
+                        .collect(),
+                })))
             }
             Err(QueryError::BadRequest(msg)) => {
                 tracing::warn!("Writing unsuccessful response");
-                Ok(Some(OdfResult {
-                    request_id: request.id,
-                    inner: Err(OdfResultErr { error_message: msg }),
-                }))
+                Ok(Some(Err(OdfResultErr { error_message: msg })))
             }
             Err(QueryError::DatasetNotFound(info)) => {
                 tracing::info!(info, "Ignoring request for unknown dataset(s)");
@@ -722,27 +1202,39 @@ impl OdfOracleProvider {
     #[tracing::instrument(level = "debug", skip_all)]
     async fn send_result(&self, result: OdfResult) -> Result<(), InternalError> {
         let request_id = result.request_id;
+        let consumer_address = result.consumer_address;
 
-        let mut result_encoded = Vec::new();
-        ciborium::into_writer(&result.into_cbor(), &mut result_encoded).int_err()?;
+        let result_encoded = result.encode()?;
 
         tracing::debug!(result_hex = hex::encode(&result_encoded), "Encoded result");
 
         let transaction = self
             .oracle_contract
             .provideResult(request_id, result_encoded.into())
-            .from(self.config.provider_address);
-
-        self.metrics.transactions_num.inc();
+            .from(self.config.provider_address)
+            .into_transaction_request();
 
         // TODO: We should ingore RequestNotFound errors as indicating that request was
         // already satisfied by another provider. But getting error data is currently
         // hard with alloy
         // See: https://github.com/alloy-rs/alloy/issues/787
-        let pending_tx = match transaction.send().await {
-            Ok(tr) => Ok(tr),
-            Err(err) => Err(err.int_err()),
-        }?;
+        self.submit_transaction(transaction, consumer_address).await
+    }
+
+    /// Sends the transaction and waits for it to be confirmed, accounting the
+    /// gas spent towards the specified consumer
+    async fn submit_transaction(
+        &self,
+        transaction: TransactionRequest,
+        consumer_address: Address,
+    ) -> Result<(), InternalError> {
+        self.metrics.transactions_num.inc();
+
+        let pending_tx = self
+            .rpc_client
+            .send_transaction(transaction)
+            .await
+            .int_err()?;
 
         tracing::debug!(
             transaction_confirmations = self.config.transaction_confirmations,
@@ -759,11 +1251,84 @@ impl OdfOracleProvider {
 
         tracing::info!(receipt = ?receipt, "Transaction confirmed");
 
+        let date = self.get_receipt_date(&receipt).await;
+
+        self.record_gas_spend(
+            date,
+            consumer_address,
+            receipt.gas_used,
+            receipt.effective_gas_price,
+        );
+
         // Fetch balance to update metric
         self.get_balance().await?;
 
         Ok(())
     }
+
+    /// Returns the day of the block that included the transaction, falling
+    /// back to the current day when the block can't be read, as the
+    /// transaction is already confirmed at this point
+    async fn get_receipt_date(&self, receipt: &TransactionReceipt) -> NaiveDate {
+        let block_timestamp = async {
+            let block_number = receipt
+                .block_number
+                .ok_or("Receipt is missing the block number".int_err())?;
+
+            let block = self
+                .rpc_client
+                .get_block_by_number(BlockNumberOrTag::Number(block_number))
+                .await
+                .int_err()?
+                .ok_or("Could not read block".int_err())?;
+
+            DateTime::from_timestamp(i64::try_from(block.header.timestamp).int_err()?, 0)
+                .ok_or("Block timestamp is out of range".int_err())
+        };
+
+        match block_timestamp.await {
+            Ok(block_timestamp) => block_timestamp.date_naive(),
+            Err(err) => {
+                tracing::warn!(
+                    error = %err,
+                    error_dbg = ?err,
+                    "Could not read the block time, accounting gas spend to the current day",
+                );
+                Utc::now().date_naive()
+            }
+        }
+    }
+
+    fn record_gas_spend(
+        &self,
+        date: NaiveDate,
+        consumer_address: Address,
+        gas_used: u64,
+        effective_gas_price: u128,
+    ) {
+        let consumer_label = if self
+            .config
+            .gas_metrics_consumers
+            .contains(&consumer_address)
+        {
+            consumer_address.to_string()
+        } else {
+            OTHER_CONSUMERS_LABEL.to_string()
+        };
+
+        self.metrics
+            .gas_used
+            .with_label_values(&[consumer_label.as_str()])
+            .inc_by(gas_used);
+
+        self.metrics
+            .gas_spent_wei
+            .with_label_values(&[consumer_label.as_str()])
+            .inc_by(u128::from(gas_used).saturating_mul(effective_gas_price) as f64);
+
+        self.gas_spend
+            .record(date, consumer_address, gas_used, effective_gas_price);
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -795,6 +1360,9 @@ enum ProcessBlockRangeError {
     /// a slighly stale head and is rejected
     #[error("Inconistent head block - please retry")]
     InconsistentHeadBlock,
+    /// Shutdown was requested before all requests of the range were processed
+    #[error("Block range processing was interrupted")]
+    Interrupted,
     #[error(transparent)]
     Internal(#[from] InternalError),
 }
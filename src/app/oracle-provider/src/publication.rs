@@ -0,0 +1,207 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use chrono::{DateTime, Utc};
+
+use crate::{PublicationConfig, PublicationFormat};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Validated form of [`PublicationConfig`]
+#[derive(Debug, Clone)]
+pub struct Publication {
+    pub name: String,
+    pub dataset_alias: String,
+    pub dataset_id: odf::DatasetID,
+    pub sql: String,
+    pub format: PublicationFormat,
+    pub target_address: Address,
+    pub target_selector: [u8; 4],
+    pub value_column: Option<String>,
+    pub min_change: Option<f64>,
+    pub heartbeat: Option<Duration>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid publication '{name}': {reason}")]
+pub struct InvalidPublication {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct InvalidPublications(pub Vec<InvalidPublication>);
+
+impl Publication {
+    /// Validates all publications, including that their names are unique, as
+    /// names identify the persisted state and metrics of a publication
+    pub fn from_configs(configs: &[PublicationConfig]) -> Result<Vec<Self>, InvalidPublications> {
+        let mut publications = Vec::new();
+        let mut errors = Vec::new();
+        let mut names = std::collections::BTreeSet::new();
+
+        for config in configs {
+            if !names.insert(config.name.as_str()) {
+                errors.push(InvalidPublication {
+                    name: config.name.clone(),
+                    reason: "Duplicate publication name".to_string(),
+                });
+                continue;
+            }
+
+            match Self::from_config(config) {
+                Ok(publication) => publications.push(publication),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(publications)
+        } else {
+            Err(InvalidPublications(errors))
+        }
+    }
+
+    pub fn from_config(config: &PublicationConfig) -> Result<Self, InvalidPublication> {
+        let invalid = |reason: String| InvalidPublication {
+            name: config.name.clone(),
+            reason,
+        };
+
+        let dataset_id = odf::DatasetID::from_did_str(&config.dataset_id)
+            .map_err(|err| invalid(format!("Invalid dataset ID: {err}")))?;
+
+        // Encoded result is passed to the target function as the only argument
+        let function_name = config
+            .target_function
+            .strip_suffix("(bytes)")
+            .ok_or_else(|| {
+                invalid(format!(
+                    "Target function '{}' must accept a single bytes argument, e.g. \
+                     'updateResult(bytes)'",
+                    config.target_function
+                ))
+            })?;
+        if function_name.is_empty()
+            || !function_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(invalid(format!(
+                "Invalid target function name '{function_name}'"
+            )));
+        }
+
+        let selector = alloy::primitives::keccak256(config.target_function.as_bytes());
+
+        match (config.min_change, &config.value_column) {
+            (Some(_), None) => {
+                return Err(invalid(
+                    "Value column must be specified together with min change".to_string(),
+                ));
+            }
+            (Some(min_change), _) if min_change.is_nan() || min_change < 0.0 => {
+                return Err(invalid(format!(
+                    "Min change must be non-negative, got {min_change}"
+                )));
+            }
+            _ => {}
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            dataset_alias: config.dataset_alias.clone(),
+            dataset_id,
+            sql: config.sql.clone(),
+            format: config.format.clone(),
+            target_address: config.target_address,
+            target_selector: selector[..4].try_into().unwrap(),
+            value_column: config.value_column.clone(),
+            min_change: config.min_change,
+            heartbeat: config.heartbeat.map(Into::into),
+        })
+    }
+
+    /// Calldata of the target function invocation with the encoded result
+    pub fn encode_call(&self, result: Vec<u8>) -> Vec<u8> {
+        use alloy::dyn_abi::DynSolValue as V;
+
+        let mut calldata = self.target_selector.to_vec();
+        calldata.extend(V::Tuple(vec![V::Bytes(result)]).abi_encode_params());
+        calldata
+    }
+
+    /// Whether the result has to be published regardless of data changes
+    pub fn is_heartbeat_due(
+        &self,
+        last_published_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        match (self.heartbeat, last_published_at) {
+            (Some(heartbeat), Some(last_published_at)) => {
+                (now - last_published_at).to_std().unwrap_or_default() >= heartbeat
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Whether the new value deviates enough from the last published one,
+    /// where `last_value` is `None` when nothing was published yet.
+    ///
+    /// Publications without a threshold, the first publication, and values
+    /// appearing or disappearing are always considered significant.
+    pub fn is_change_significant(
+        &self,
+        last_value: Option<Option<f64>>,
+        new_value: Option<f64>,
+    ) -> bool {
+        let Some(min_change) = self.min_change else {
+            return true;
+        };
+        let Some(last_value) = last_value else {
+            return true;
+        };
+
+        match (last_value, new_value) {
+            (Some(last), Some(new)) if last == 0.0 => new != 0.0,
+            (Some(last), Some(new)) => ((new - last) / last).abs() >= min_change,
+            (None, None) => false,
+            _ => true,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Extracts the numeric value of the specified column from the first row of a
+/// result in `JsonAoa` layout. Numbers that don't fit into JSON (e.g.
+/// decimals) are expected to be represented as strings.
+pub fn extract_value(
+    schema: &serde_json::Value,
+    data: &serde_json::Value,
+    column: &str,
+) -> Option<f64> {
+    let index = schema["fields"]
+        .as_array()?
+        .iter()
+        .position(|f| f["name"].as_str() == Some(column))?;
+
+    match &data.as_array()?.first()?[index] {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
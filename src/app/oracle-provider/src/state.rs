@@ -0,0 +1,87 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use internal_error::*;
+
+use crate::gas::GasSpend;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Progress of the provider that survives restarts
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderState {
+    /// Last block whose requests were fully processed and submitted
+    #[serde(default)]
+    pub last_processed_block: Option<u64>,
+
+    /// Gas spent on submitting results per consumer and per day
+    #[serde(default)]
+    pub gas_spend: GasSpend,
+
+    /// Progress of the publications by their names
+    #[serde(default)]
+    pub publications: BTreeMap<String, PublicationState>,
+}
+
+/// Progress of a single publication
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicationState {
+    /// Dataset head at which the publication was last evaluated
+    #[serde(default)]
+    pub last_evaluated_block_hash: Option<String>,
+
+    /// Value of the last published result used to apply the change threshold
+    #[serde(default)]
+    pub last_published_value: Option<f64>,
+
+    #[serde(default)]
+    pub last_published_at: Option<DateTime<Utc>>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Stores [`ProviderState`] as a JSON file
+pub struct ProviderStateStore {
+    path: PathBuf,
+}
+
+impl ProviderStateStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns default state if the file does not exist yet
+    pub fn load(&self) -> Result<ProviderState, InternalError> {
+        match std::fs::read(&self.path) {
+            Ok(data) => serde_json::from_slice(&data).int_err(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ProviderState::default()),
+            Err(err) => Err(err.int_err()),
+        }
+    }
+
+    /// Writes state into a temporary file first and then atomically replaces
+    /// the previous one so that a crash never leaves a corrupted file behind
+    pub fn save(&self, state: &ProviderState) -> Result<(), InternalError> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+
+        let data = serde_json::to_vec_pretty(state).int_err()?;
+        std::fs::write(&tmp_path, data).int_err()?;
+        std::fs::rename(&tmp_path, &self.path).int_err()?;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -7,5 +7,10 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod test_abi;
+mod test_check;
 mod test_config;
 mod test_e2e;
+mod test_gas;
+mod test_publication;
+mod test_state;
@@ -0,0 +1,137 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use alloy::dyn_abi::{DynSolType, DynSolValue};
+use alloy::primitives::{B256, I256, U256};
+use kamu_oracle_provider::abi::*;
+use serde_json::json;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn field(name: &str, data_type: serde_json::Value) -> serde_json::Value {
+    json!({
+        "name": name,
+        "data_type": data_type,
+        "nullable": true,
+        "dict_id": 0,
+        "dict_is_ordered": false,
+        "metadata": {},
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_abi_encode_rows() {
+    let schema = json!({
+        "fields": [
+            field("province", json!("Utf8")),
+            field("total_cases", json!("UInt64")),
+            field("delta", json!("Int32")),
+            field("price", json!({"Decimal128": [10, 2]})),
+            field("event_time", json!({"Timestamp": ["Millisecond", "UTC"]})),
+            field("date", json!("Date32")),
+            field("flag", json!("Boolean")),
+            field("hash", json!({"FixedSizeBinary": 4})),
+        ],
+        "metadata": {},
+    });
+
+    let data = json!([
+        [
+            "ON",
+            100500,
+            -5,
+            "12.3",
+            "2024-09-02T21:50:00.123Z",
+            "1970-01-11",
+            true,
+            "0a0b0c0d"
+        ],
+        [null, null, null, null, null, null, null, null],
+    ]);
+
+    let actual = encode_rows(&schema, &data).unwrap();
+
+    let expected = DynSolValue::Array(vec![
+        DynSolValue::Tuple(vec![
+            DynSolValue::String("ON".to_string()),
+            DynSolValue::Uint(U256::from(100_500), 64),
+            DynSolValue::Int(I256::try_from(-5).unwrap(), 32),
+            DynSolValue::Int(I256::try_from(1230).unwrap(), 256),
+            DynSolValue::Int(I256::try_from(1_725_313_800_123_i64).unwrap(), 64),
+            DynSolValue::Int(I256::try_from(10).unwrap(), 32),
+            DynSolValue::Bool(true),
+            DynSolValue::FixedBytes(B256::right_padding_from(&[10, 11, 12, 13]), 4),
+        ]),
+        DynSolValue::Tuple(vec![
+            DynSolValue::String(String::new()),
+            DynSolValue::Uint(U256::ZERO, 64),
+            DynSolValue::Int(I256::ZERO, 32),
+            DynSolValue::Int(I256::ZERO, 256),
+            DynSolValue::Int(I256::ZERO, 64),
+            DynSolValue::Int(I256::ZERO, 32),
+            DynSolValue::Bool(false),
+            DynSolValue::FixedBytes(B256::ZERO, 4),
+        ]),
+    ]);
+
+    assert_eq!(actual, expected);
+
+    // Round-trip the way Solidity consumer would decode it
+    let row_type =
+        DynSolType::parse("(string,uint64,int32,int256,int64,int32,bool,bytes4)[]").unwrap();
+    let decoded = row_type.abi_decode(&actual.abi_encode()).unwrap();
+    assert_eq!(decoded, expected);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_abi_encode_rows_rejects_unsupported_types() {
+    let schema = json!({
+        "fields": [field("value", json!("Float64"))],
+    });
+
+    let res = encode_rows(&schema, &json!([[1.5]]));
+
+    assert!(
+        matches!(
+            res,
+            Err(AbiEncodeError::UnsupportedType { ref column, .. }) if column == "value"
+        ),
+        "{res:?}"
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_abi_encode_rows_rejects_precision_loss() {
+    let schema = json!({
+        "fields": [field("price", json!({"Decimal128": [10, 2]}))],
+    });
+
+    assert_eq!(
+        encode_rows(&schema, &json!([["1.50"], ["1.500"]])).unwrap(),
+        DynSolValue::Array(vec![
+            DynSolValue::Tuple(vec![DynSolValue::Int(I256::try_from(150).unwrap(), 256)]),
+            DynSolValue::Tuple(vec![DynSolValue::Int(I256::try_from(150).unwrap(), 256)]),
+        ])
+    );
+
+    let res = encode_rows(&schema, &json!([["1.505"]]));
+
+    assert!(
+        matches!(res, Err(AbiEncodeError::InvalidValue { .. })),
+        "{res:?}"
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
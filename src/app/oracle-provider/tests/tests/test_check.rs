@@ -0,0 +1,183 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::future::IntoFuture as _;
+
+use kamu_oracle_provider::Config;
+use kamu_oracle_provider::check::*;
+use serde_json::json;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_check_api_verifies_access_token() {
+    let api_url = serve_api_mock().await;
+
+    // Token is verified, and queries are executed with it
+    let report = check_api_report(api_url.clone(), Some("valid-token")).await;
+    assert_eq!(statuses(&report), [CheckStatus::Pass, CheckStatus::Pass]);
+    assert!(report.checks[0].details.contains("as alice"));
+    assert_eq!(report.checks[1].name, "API server");
+
+    // Anonymous queries would succeed with an invalid token too
+    let report = check_api_report(api_url.clone(), Some("invalid-token")).await;
+    assert_eq!(statuses(&report), [CheckStatus::Fail, CheckStatus::Skip]);
+    assert!(report.checks[0].details.contains("rejected"));
+
+    let report = check_api_report(api_url, None).await;
+    assert_eq!(statuses(&report), [CheckStatus::Pass]);
+    assert!(report.checks[0].details.contains("anonymously"));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_check_rpc_endpoint_is_probed() {
+    let rpc_url = serve_rpc_mock().await;
+    let api_url = closed_port_url().await;
+
+    let report = run_checks(
+        &Config {
+            rpc_url,
+            chain_id: 1,
+            ..config(api_url.clone(), None)
+        },
+        alloy::primitives::U256::ZERO,
+    )
+    .await;
+    assert_eq!(status(&report, "RPC endpoint"), CheckStatus::Pass);
+    assert_eq!(status(&report, "Chain ID"), CheckStatus::Pass);
+
+    // Connecting to an unreachable node succeeds, but requests fail
+    let report = run_checks(
+        &Config {
+            rpc_url: closed_port_url().await,
+            chain_id: 1,
+            ..config(api_url, None)
+        },
+        alloy::primitives::U256::ZERO,
+    )
+    .await;
+    assert_eq!(status(&report, "RPC endpoint"), CheckStatus::Fail);
+    assert_eq!(status(&report, "Chain ID"), CheckStatus::Skip);
+    assert_eq!(status(&report, "Provider balance"), CheckStatus::Skip);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn statuses(report: &CheckReport) -> Vec<CheckStatus> {
+    report.checks.iter().map(|check| check.status).collect()
+}
+
+fn status(report: &CheckReport, name: &str) -> CheckStatus {
+    report
+        .checks
+        .iter()
+        .find(|check| check.name == name)
+        .unwrap()
+        .status
+}
+
+async fn check_api_report(api_url: url::Url, access_token: Option<&str>) -> CheckReport {
+    let mut report = CheckReport::default();
+    check_api(&config(api_url, access_token), &mut report).await;
+    report
+}
+
+/// Serves `/accounts/me` that accepts only `valid-token` and `/query` that
+/// accepts any request
+async fn serve_api_mock() -> url::Url {
+    async fn account_handler(headers: http::HeaderMap) -> axum::response::Response {
+        use axum::response::IntoResponse as _;
+
+        match headers.get(http::header::AUTHORIZATION) {
+            Some(value) if value == "Bearer valid-token" => {
+                axum::Json(json!({"id": "did:odf:alice", "accountName": "alice"})).into_response()
+            }
+            _ => http::StatusCode::UNAUTHORIZED.into_response(),
+        }
+    }
+
+    async fn query_handler() -> axum::Json<serde_json::Value> {
+        axum::Json(json!({"output": {"data": [[1]], "dataFormat": "JsonAoa"}}))
+    }
+
+    let router = axum::Router::new()
+        .route("/accounts/me", axum::routing::get(account_handler))
+        .route("/query", axum::routing::post(query_handler));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(axum::serve(listener, router).into_future());
+
+    url::Url::parse(&format!("http://{addr}")).unwrap()
+}
+
+/// Serves the JSON-RPC API of a node of the chain with ID `1` that supports
+/// only `eth_chainId`
+async fn serve_rpc_mock() -> url::Url {
+    async fn rpc_handler(
+        axum::Json(request): axum::Json<serde_json::Value>,
+    ) -> axum::Json<serde_json::Value> {
+        let id = request["id"].clone();
+        axum::Json(match request["method"].as_str() {
+            Some("eth_chainId") => json!({"jsonrpc": "2.0", "id": id, "result": "0x1"}),
+            _ => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": -32601, "message": "Method not found"},
+            }),
+        })
+    }
+
+    let router = axum::Router::new().route("/", axum::routing::post(rpc_handler));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(axum::serve(listener, router).into_future());
+
+    url::Url::parse(&format!("http://{addr}")).unwrap()
+}
+
+async fn closed_port_url() -> url::Url {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    url::Url::parse(&format!("http://{addr}")).unwrap()
+}
+
+fn config(api_url: url::Url, access_token: Option<&str>) -> Config {
+    Config {
+        http_address: "127.0.0.1".to_string(),
+        http_port: 0,
+        gas_spend_token: None,
+        rpc_url: url::Url::parse("http://localhost:8545").unwrap(),
+        chain_id: 0,
+        oracle_contract_address: alloy::primitives::Address::ZERO,
+        provider_address: alloy::primitives::Address::ZERO,
+        provider_private_key: String::new(),
+        scan_from_block: None,
+        scan_last_blocks: None,
+        scan_last_blocks_period: None,
+        blocks_stride: 100_000,
+        loop_idle_time: "1s".parse().unwrap(),
+        transaction_confirmations: 1,
+        transaction_timeout: "1m".parse().unwrap(),
+        shutdown_drain_timeout: "1m".parse().unwrap(),
+        state_path: None,
+        api_url,
+        api_access_token: access_token.map(ToString::to_string),
+        ignore_requests: Vec::new(),
+        ignore_consumers: Vec::new(),
+        gas_metrics_consumers: Vec::new(),
+        publications: Vec::new(),
+        publications_check_interval: "30s".parse().unwrap(),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
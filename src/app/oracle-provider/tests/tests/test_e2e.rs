@@ -68,110 +68,213 @@ fn get_contracts_dir() -> PathBuf {
 #[test_group::group(e2e, oracle, flaky)]
 #[test_log::test(tokio::test)]
 async fn test_oracle_e2e() {
-    let contracts_dir = get_contracts_dir();
+    let anvil = alloy::node_bindings::Anvil::new().spawn();
+    let harness = OracleHarness::setup(&anvil, None).await;
+
+    let metrics = provider::OdfOracleProviderMetrics::new(0, "localhost");
+    let provider = harness
+        .provider_with_metrics(Arc::new(MockOdfApiClient), metrics.clone())
+        .await;
+
+    provider.run_once(Some(0), None).await.unwrap();
+
+    assert_eq!(harness.consumer.province().call().await.unwrap(), "ON");
+    assert_eq!(harness.consumer.totalCases().call().await.unwrap(), 100500);
+
+    // Consumers that are not listed explicitly don't get their own series
+    assert!(
+        metrics
+            .gas_used
+            .with_label_values(&[provider::provider::OTHER_CONSUMERS_LABEL])
+            .get()
+            > 0
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_group::group(e2e, oracle, flaky)]
+#[test_log::test(tokio::test)]
+async fn test_oracle_drains_in_flight_request_on_shutdown() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let state_path = tempdir.path().join("state.json");
 
     let anvil = alloy::node_bindings::Anvil::new().spawn();
-    let rpc_endpoint = anvil.endpoint();
-    let admin_address = anvil.addresses()[0];
-    let admin_key = hex::encode(anvil.keys()[0].to_bytes());
-    let provider_address = anvil.addresses()[1];
-    let provider_private_key = hex::encode(anvil.keys()[1].to_bytes());
-    let oracle_contract_address: Address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
-        .parse()
-        .unwrap();
-    let consumer_contract_address: Address = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512"
-        .parse()
-        .unwrap();
+    let harness = OracleHarness::setup(&anvil, Some(state_path.clone())).await;
 
-    std::process::Command::new("forge")
-        .current_dir(&contracts_dir)
-        .args([
-            "script",
-            "script/Deploy.s.sol",
-            "--fork-url",
-            rpc_endpoint.as_str(),
-            "--private-key",
-            admin_key.as_str(),
-            "--broadcast",
-        ])
-        .status()
-        .expect("Failed to deploy contracts. Is foundry installed?")
-        .exit_ok()
-        .unwrap();
+    let query_started = Arc::new(tokio::sync::Notify::new());
+    let provider = harness
+        .provider(Arc::new(SlowOdfApiClient {
+            query_started: query_started.clone(),
+        }))
+        .await;
 
-    let config = provider::Config {
-        http_address: "127.0.0.1".into(),
-        http_port: 0,
-        rpc_url: url::Url::parse(&anvil.endpoint()).unwrap(),
-        chain_id: anvil.chain_id(),
-        oracle_contract_address,
-        scan_from_block: Some(0),
-        scan_last_blocks: None,
-        scan_last_blocks_period: None,
-        provider_address,
-        provider_private_key,
-        blocks_stride: 100_000,
-        loop_idle_time: "1s".parse().unwrap(),
-        transaction_confirmations: 1,
-        transaction_timeout: "5s".parse().unwrap(),
-        api_url: url::Url::parse("http://dontcare.com").unwrap(),
-        api_access_token: None,
-        ignore_requests: Vec::new(),
-        ignore_consumers: Vec::new(),
-    };
+    let shutdown = graceful_shutdown::CancellationToken::new();
+    let run = tokio::spawn(provider.run(shutdown.clone()));
+
+    // Shutdown is requested while the query of the request is being executed
+    query_started.notified().await;
+    shutdown.cancel();
 
-    // Authorize provider and generate a request
-    let admin_rpc_client = alloy::providers::ProviderBuilder::new()
-        .with_gas_estimation()
-        .with_cached_nonce_management()
-        .connect(&rpc_endpoint)
+    tokio::time::timeout(std::time::Duration::from_secs(30), run)
         .await
+        .expect("Provider did not drain in time")
         .unwrap()
-        .erased();
+        .unwrap();
 
-    let oracle_admin = IOdfAdmin::new(oracle_contract_address, admin_rpc_client.clone());
-    let consumer = Consumer::new(consumer_contract_address, admin_rpc_client.clone());
+    // The in-flight request was still fulfilled and the progress persisted
+    assert_eq!(harness.consumer.province().call().await.unwrap(), "ON");
 
-    oracle_admin
-        .addProvider(provider_address)
-        .from(admin_address)
-        .send()
-        .await
-        .unwrap()
-        .get_receipt()
-        .await
+    let state = provider::state::ProviderStateStore::new(state_path)
+        .load()
         .unwrap();
+    assert!(state.last_processed_block.is_some());
+}
 
-    consumer
-        .initiateQuery()
-        .from(admin_address)
-        .send()
-        .await
-        .unwrap()
-        .get_receipt()
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Oracle and consumer contracts deployed to the node with the provider
+/// authorized and a request initiated by the consumer
+struct OracleHarness {
+    config: provider::Config,
+    consumer: Consumer::ConsumerInstance<alloy::providers::DynProvider>,
+}
+
+impl OracleHarness {
+    async fn setup(
+        anvil: &alloy::node_bindings::AnvilInstance,
+        state_path: Option<PathBuf>,
+    ) -> Self {
+        let contracts_dir = get_contracts_dir();
+
+        let rpc_endpoint = anvil.endpoint();
+        let admin_address = anvil.addresses()[0];
+        let admin_key = hex::encode(anvil.keys()[0].to_bytes());
+        let provider_address = anvil.addresses()[1];
+        let provider_private_key = hex::encode(anvil.keys()[1].to_bytes());
+        let oracle_contract_address: Address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+            .parse()
+            .unwrap();
+        let consumer_contract_address: Address = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512"
+            .parse()
+            .unwrap();
+
+        std::process::Command::new("forge")
+            .current_dir(&contracts_dir)
+            .args([
+                "script",
+                "script/Deploy.s.sol",
+                "--fork-url",
+                rpc_endpoint.as_str(),
+                "--private-key",
+                admin_key.as_str(),
+                "--broadcast",
+            ])
+            .status()
+            .expect("Failed to deploy contracts. Is foundry installed?")
+            .exit_ok()
+            .unwrap();
+
+        let config = provider::Config {
+            http_address: "127.0.0.1".into(),
+            http_port: 0,
+            gas_spend_token: None,
+            rpc_url: url::Url::parse(&anvil.endpoint()).unwrap(),
+            chain_id: anvil.chain_id(),
+            oracle_contract_address,
+            scan_from_block: Some(0),
+            scan_last_blocks: None,
+            scan_last_blocks_period: None,
+            provider_address,
+            provider_private_key,
+            blocks_stride: 100_000,
+            loop_idle_time: "1s".parse().unwrap(),
+            transaction_confirmations: 1,
+            transaction_timeout: "5s".parse().unwrap(),
+            shutdown_drain_timeout: "5s".parse().unwrap(),
+            state_path,
+            api_url: url::Url::parse("http://dontcare.com").unwrap(),
+            api_access_token: None,
+            ignore_requests: Vec::new(),
+            ignore_consumers: Vec::new(),
+            gas_metrics_consumers: Vec::new(),
+            publications: Vec::new(),
+            publications_check_interval: "30s".parse().unwrap(),
+        };
+
+        // Authorize provider and generate a request
+        let admin_rpc_client = alloy::providers::ProviderBuilder::new()
+            .with_gas_estimation()
+            .with_cached_nonce_management()
+            .connect(&rpc_endpoint)
+            .await
+            .unwrap()
+            .erased();
+
+        let oracle_admin = IOdfAdmin::new(oracle_contract_address, admin_rpc_client.clone());
+        let consumer = Consumer::new(consumer_contract_address, admin_rpc_client.clone());
+
+        oracle_admin
+            .addProvider(provider_address)
+            .from(admin_address)
+            .send()
+            .await
+            .unwrap()
+            .get_receipt()
+            .await
+            .unwrap();
+
+        consumer
+            .initiateQuery()
+            .from(admin_address)
+            .send()
+            .await
+            .unwrap()
+            .get_receipt()
+            .await
+            .unwrap();
+
+        Self { config, consumer }
+    }
+
+    async fn provider(&self, api_client: Arc<dyn OdfApiClient>) -> provider::OdfOracleProvider {
+        self.provider_with_metrics(
+            api_client,
+            provider::OdfOracleProviderMetrics::new(0, "localhost"),
+        )
         .await
-        .unwrap();
+    }
 
-    // Setup and run provider
-    let rpc_client = provider::app::init_rpc_client(&config).await.unwrap();
+    async fn provider_with_metrics(
+        &self,
+        api_client: Arc<dyn OdfApiClient>,
+        metrics: provider::OdfOracleProviderMetrics,
+    ) -> provider::OdfOracleProvider {
+        let rpc_client = provider::app::init_rpc_client(&self.config).await.unwrap();
 
-    let api_client = Arc::new(MockOdfApiClient);
+        // let api_client = Arc::new(
+        //     OdfApiClientRest::new(url::Url::parse("https://api.demo.kamu.dev").unwrap(), None).unwrap(),
+        // );
 
-    // let api_client = Arc::new(
-    //     OdfApiClientRest::new(url::Url::parse("https://api.demo.kamu.dev").unwrap(), None).unwrap(),
-    // );
+        provider::OdfOracleProvider::new(self.config.clone(), rpc_client, api_client, metrics)
+    }
+}
 
-    let provider = provider::OdfOracleProvider::new(
-        config,
-        rpc_client.clone(),
-        api_client,
-        provider::OdfOracleProviderMetrics::new(0, "localhost"),
-    );
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-    provider.run_once(Some(0), None).await.unwrap();
+/// Takes a while to respond, so that the shutdown can be requested while the
+/// query is in flight
+struct SlowOdfApiClient {
+    query_started: Arc<tokio::sync::Notify>,
+}
 
-    assert_eq!(consumer.province().call().await.unwrap(), "ON");
-    assert_eq!(consumer.totalCases().call().await.unwrap(), 100500);
+#[async_trait::async_trait]
+impl OdfApiClient for SlowOdfApiClient {
+    async fn query(&self, request: QueryRequest) -> Result<QueryResponse, QueryError> {
+        self.query_started.notify_one();
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        MockOdfApiClient.query(request).await
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -202,6 +305,7 @@ impl OdfApiClient for MockOdfApiClient {
                 query: request.query,
                 query_dialect: Some(QueryDialect::SqlDataFusion),
                 data_format: Some(DataFormat::JsonAoa),
+                schema_format: None,
                 datasets: Some(vec![DatasetState {
                     id: odf::DatasetID::from_did_str("did:odf:fed01dcda047d51fc88246c730db522d36791c9e2286af23d9f2b920f09c65952e3d0").unwrap(),
                     alias: "kamu/covid19.canada.case-details".to_string(),
@@ -213,6 +317,8 @@ impl OdfApiClient for MockOdfApiClient {
             output: Outputs{
                 data: json!([["ON", 100500]]),
                 data_format: DataFormat::JsonAoa,
+                schema: None,
+                schema_format: None,
             }
         })
     }
@@ -0,0 +1,149 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::future::IntoFuture as _;
+
+use alloy::primitives::{Address, address};
+use chrono::NaiveDate;
+use kamu_oracle_provider::gas::*;
+use kamu_oracle_provider::state::ProviderState;
+use serde_json::json;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const CONSUMER_A: Address = address!("0x00000000000000000000000000000000000000aa");
+const CONSUMER_B: Address = address!("0x00000000000000000000000000000000000000bb");
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 1, day).unwrap()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_gas_spend_aggregation() {
+    let mut gas_spend = GasSpend::default();
+    gas_spend.record(date(2), CONSUMER_B, 100, 10);
+    gas_spend.record(date(1), CONSUMER_A, 50, 2);
+    gas_spend.record(date(2), CONSUMER_B, 200, 20);
+    gas_spend.record(date(2), CONSUMER_A, 10, 1);
+
+    assert_eq!(
+        gas_spend.to_csv(),
+        [
+            "date,consumer_address,transactions,gas_used,cost_wei".to_string(),
+            format!("2026-01-01,{CONSUMER_A},1,50,100"),
+            format!("2026-01-02,{CONSUMER_A},1,10,10"),
+            format!("2026-01-02,{CONSUMER_B},2,300,5000"),
+            String::new(),
+        ]
+        .join("\n")
+    );
+
+    assert_eq!(
+        gas_spend.totals_by_consumer(),
+        vec![
+            GasSpendTotal {
+                consumer_address: CONSUMER_A,
+                transactions: 2,
+                gas_used: 60,
+                cost_wei: 110,
+            },
+            GasSpendTotal {
+                consumer_address: CONSUMER_B,
+                transactions: 2,
+                gas_used: 300,
+                cost_wei: 5000,
+            },
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_gas_spend_state_roundtrip() {
+    let mut state = ProviderState {
+        last_processed_block: Some(123),
+        ..Default::default()
+    };
+    state.gas_spend.record(date(1), CONSUMER_A, 50, 2);
+
+    let json = serde_json::to_string(&state).unwrap();
+    let actual: ProviderState = serde_json::from_str(&json).unwrap();
+    assert_eq!(actual, state);
+
+    // Costs are kept as strings to not lose precision in JSON parsers
+    let mut gas_spend = GasSpend::default();
+    gas_spend.record(date(1), CONSUMER_A, 1_000_000, 1_000_000_000_000_000);
+    let json = serde_json::to_value(&gas_spend).unwrap();
+    assert_eq!(json[0]["costWei"], json!("1000000000000000000000"));
+    assert_eq!(serde_json::from_value::<GasSpend>(json).unwrap(), gas_spend);
+
+    // State written before gas accounting was introduced is still readable
+    let actual: ProviderState = serde_json::from_str(r#"{"lastProcessedBlock": 5}"#).unwrap();
+    assert_eq!(actual.last_processed_block, Some(5));
+    assert_eq!(actual.gas_spend, GasSpend::default());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_gas_spend_endpoint_requires_token() {
+    let tracker = GasSpendTracker::default();
+    tracker.record(date(1), CONSUMER_A, 50, 2);
+
+    let client = reqwest::Client::new();
+
+    // Not served without a token
+    let url = serve_gas_spend(tracker.clone(), None).await;
+    let response = client.get(url).send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+    let url = serve_gas_spend(tracker, Some("secret")).await;
+
+    let response = client.get(url.clone()).send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .get(url.clone())
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
+
+    let response = client.get(url).bearer_auth("secret").send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(
+        response.json::<serde_json::Value>().await.unwrap()["totals"],
+        json!([{
+            "consumerAddress": CONSUMER_A,
+            "transactions": 1,
+            "gasUsed": 50,
+            "costWei": "100",
+        }])
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+async fn serve_gas_spend(tracker: GasSpendTracker, token: Option<&str>) -> url::Url {
+    let catalog = dill::CatalogBuilder::new().add_value(tracker).build();
+
+    let router = gas_spend_router(token).layer(axum::extract::Extension(catalog));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(axum::serve(listener, router).into_future());
+
+    url::Url::parse(&format!("http://{addr}/system/gas-spend")).unwrap()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,154 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use alloy::primitives::address;
+use chrono::{TimeZone, Utc};
+use kamu_oracle_provider::publication::*;
+use kamu_oracle_provider::{PublicationConfig, PublicationFormat};
+use serde_json::json;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn publication_config() -> PublicationConfig {
+    PublicationConfig {
+        name: "price".to_string(),
+        dataset_alias: "prices".to_string(),
+        dataset_id: "did:odf:fed01dcda047d51fc88246c730db522d36791c9e2286af23d9f2b920f09c65952e3d0"
+            .to_string(),
+        sql: "select price from prices order by event_time desc limit 1".to_string(),
+        format: PublicationFormat::Abi,
+        target_address: address!("0x5FbDB2315678afecb367f032d93F642f64180aa3"),
+        target_function: "updateResult(bytes)".to_string(),
+        value_column: Some("price".to_string()),
+        min_change: Some(0.01),
+        heartbeat: Some("1h".parse().unwrap()),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_publication_validation() {
+    let publication = Publication::from_config(&publication_config()).unwrap();
+    let selector = &alloy::primitives::keccak256("updateResult(bytes)")[..4];
+    let mut expected = selector.to_vec();
+    expected.extend(
+        hex::decode(
+            "0000000000000000000000000000000000000000000000000000000000000020\
+             0000000000000000000000000000000000000000000000000000000000000001\
+             ab00000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap(),
+    );
+    assert_eq!(publication.encode_call(vec![0xab]), expected);
+
+    let err = Publication::from_config(&PublicationConfig {
+        target_function: "updateResult(uint256)".to_string(),
+        ..publication_config()
+    })
+    .unwrap_err();
+    assert!(err.reason.contains("single bytes argument"), "{err}");
+
+    let err = Publication::from_config(&PublicationConfig {
+        value_column: None,
+        ..publication_config()
+    })
+    .unwrap_err();
+    assert!(err.reason.contains("Value column"), "{err}");
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_publication_thresholds() {
+    let publication = Publication::from_config(&publication_config()).unwrap();
+
+    assert!(publication.is_change_significant(Some(None), Some(100.0)));
+    assert!(!publication.is_change_significant(Some(Some(100.0)), Some(100.5)));
+    assert!(publication.is_change_significant(Some(Some(100.0)), Some(101.0)));
+    assert!(publication.is_change_significant(Some(Some(100.0)), Some(98.0)));
+    assert!(publication.is_change_significant(Some(Some(100.0)), None));
+    assert!(!publication.is_change_significant(Some(None), None));
+
+    // First publication goes out even when there is no value yet
+    assert!(publication.is_change_significant(None, Some(100.0)));
+    assert!(publication.is_change_significant(None, None));
+
+    let last_published_at = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+    assert!(publication.is_heartbeat_due(None, last_published_at));
+    assert!(!publication.is_heartbeat_due(
+        Some(last_published_at),
+        last_published_at + chrono::Duration::minutes(59)
+    ));
+    assert!(publication.is_heartbeat_due(
+        Some(last_published_at),
+        last_published_at + chrono::Duration::minutes(60)
+    ));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_publication_extract_value() {
+    let schema = json!({
+        "fields": [
+            {"name": "event_time", "data_type": {"Timestamp": ["Millisecond", "UTC"]}},
+            {"name": "price", "data_type": {"Decimal128": [10, 2]}},
+            {"name": "volume", "data_type": "UInt64"},
+        ],
+    });
+    let data = json!([
+        ["2026-01-01T00:00:00Z", "12.34", 100],
+        ["2026-01-01T00:01:00Z", "12.35", 200],
+    ]);
+
+    assert_eq!(extract_value(&schema, &data, "price"), Some(12.34));
+    assert_eq!(extract_value(&schema, &data, "volume"), Some(100.0));
+    assert_eq!(extract_value(&schema, &data, "event_time"), None);
+    assert_eq!(extract_value(&schema, &data, "unknown"), None);
+    assert_eq!(extract_value(&schema, &json!([]), "price"), None);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_publication_names_are_unique() {
+    let publications = Publication::from_configs(&[
+        publication_config(),
+        PublicationConfig {
+            name: "volume".to_string(),
+            ..publication_config()
+        },
+    ])
+    .unwrap();
+    assert_eq!(publications.len(), 2);
+
+    // State and metrics of publications are keyed by name
+    let err = Publication::from_configs(&[
+        publication_config(),
+        PublicationConfig {
+            target_function: "updateResult(uint256)".to_string(),
+            ..publication_config()
+        },
+        PublicationConfig {
+            name: "volume".to_string(),
+            value_column: None,
+            ..publication_config()
+        },
+    ])
+    .unwrap_err();
+    assert_eq!(err.0.len(), 2);
+    assert_eq!(
+        err.0[0].to_string(),
+        "Invalid publication 'price': Duplicate publication name"
+    );
+    assert!(err.0[1].reason.contains("Value column"), "{err}");
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,65 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use alloy::primitives::address;
+use chrono::{NaiveDate, TimeZone as _, Utc};
+use kamu_oracle_provider::state::*;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_state_store_roundtrip() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("state.json");
+    let store = ProviderStateStore::new(&path);
+
+    // Provider starts from scratch when there is no state yet
+    assert_eq!(store.load().unwrap(), ProviderState::default());
+
+    let mut state = ProviderState {
+        last_processed_block: Some(100),
+        ..Default::default()
+    };
+    state.gas_spend.record(
+        NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+        address!("0x00000000000000000000000000000000000000aa"),
+        50,
+        2,
+    );
+    state.publications.insert(
+        "price".to_string(),
+        PublicationState {
+            last_evaluated_block_hash: Some("f1620".to_string()),
+            last_published_value: Some(1.5),
+            last_published_at: Some(Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap()),
+        },
+    );
+    store.save(&state).unwrap();
+    assert_eq!(store.load().unwrap(), state);
+
+    // Progress is overwritten and no temporary files are left behind
+    state.last_processed_block = Some(200);
+    store.save(&state).unwrap();
+    assert_eq!(ProviderStateStore::new(&path).load().unwrap(), state);
+    assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 1);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_state_store_rejects_corrupted_file() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("state.json");
+    std::fs::write(&path, "{\"lastProcessedBlock\":").unwrap();
+
+    // Starting over from the scan options would re-submit the processed requests
+    assert!(ProviderStateStore::new(&path).load().is_err());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,176 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Orchestrates the shutdown of a service in phases that are executed in the
+/// order they were added.
+///
+/// Every phase hands out a [`CancellationToken`] that is cancelled when the
+/// phase starts, and tracks the tasks spawned via [`ShutdownPhase::spawn`].
+/// The coordinator proceeds to the next phase once all tracked tasks of the
+/// current one completed or its timeout has elapsed, in which case the
+/// remaining tasks are aborted.
+///
+/// ```ignore
+/// let mut coordinator = ShutdownCoordinator::new();
+/// let stop_accepting = coordinator.add_phase("stop-accepting", Duration::ZERO);
+/// let drain = coordinator.add_phase("drain", Duration::from_secs(30));
+///
+/// let server = drain.spawn(server.with_graceful_shutdown(stop_accepting.cancelled()));
+///
+/// trap_signals().await;
+/// coordinator.shutdown().await?;
+/// ```
+#[derive(Debug, Default)]
+pub struct ShutdownCoordinator {
+    phases: Vec<ShutdownPhase>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_phase(&mut self, name: &'static str, timeout: Duration) -> ShutdownPhase {
+        let phase = ShutdownPhase {
+            name,
+            timeout,
+            token: CancellationToken::new(),
+            tracker: TaskTracker::new(),
+            abort_handles: Arc::default(),
+        };
+        self.phases.push(phase.clone());
+        phase
+    }
+
+    /// Executes all phases. Phases that did not complete in time don't stop
+    /// the subsequent ones from running, but are reported in the error.
+    pub async fn shutdown(self) -> Result<(), ShutdownTimeoutError> {
+        let mut timed_out_phases = Vec::new();
+
+        for phase in self.phases {
+            tracing::info!(
+                phase = phase.name,
+                timeout = ?phase.timeout,
+                "Entering shutdown phase",
+            );
+            let start = Instant::now();
+
+            phase.token.cancel();
+            phase.tracker.close();
+
+            if tokio::time::timeout(phase.timeout, phase.tracker.wait())
+                .await
+                .is_err()
+            {
+                tracing::warn!(
+                    phase = phase.name,
+                    pending_tasks = phase.tracker.len(),
+                    "Shutdown phase did not complete within the timeout, aborting pending tasks",
+                );
+                phase.abort_pending();
+                timed_out_phases.push(phase.name);
+                continue;
+            }
+
+            tracing::info!(
+                phase = phase.name,
+                elapsed = ?start.elapsed(),
+                "Shutdown phase completed",
+            );
+        }
+
+        if timed_out_phases.is_empty() {
+            Ok(())
+        } else {
+            Err(ShutdownTimeoutError {
+                phases: timed_out_phases,
+            })
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Handle to a phase of [`ShutdownCoordinator`] that can be cheaply cloned
+#[derive(Debug, Clone)]
+pub struct ShutdownPhase {
+    name: &'static str,
+    timeout: Duration,
+    token: CancellationToken,
+    tracker: TaskTracker,
+    abort_handles: Arc<Mutex<Vec<AbortHandle>>>,
+}
+
+impl ShutdownPhase {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the token that is cancelled when the phase starts
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Returns a future that completes when the phase starts
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.token.clone().cancelled_owned()
+    }
+
+    /// Spawns a task that has to complete before the phase is over. The task
+    /// is aborted if it's still running when the phase times out.
+    pub fn spawn<F>(&self, task: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let handle = self.tracker.spawn(task);
+
+        let mut abort_handles = self.abort_handles.lock().unwrap();
+        abort_handles.retain(|h| !h.is_finished());
+        abort_handles.push(handle.abort_handle());
+
+        handle
+    }
+
+    fn abort_pending(&self) {
+        for handle in self.abort_handles.lock().unwrap().drain(..) {
+            handle.abort();
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct ShutdownTimeoutError {
+    pub phases: Vec<&'static str>,
+}
+
+impl std::fmt::Display for ShutdownTimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Shutdown phases did not complete in time: {}",
+            self.phases.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ShutdownTimeoutError {}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -7,6 +7,13 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod coordinator;
+
+pub use coordinator::*;
+pub use tokio_util::sync::CancellationToken;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
 /// Returns a future that completes when SIGINT or SIGTERM signal is received.
 /// Can be combined with facilities like Axum's [`with_graceful_shutdown`](https://docs.rs/axum/latest/axum/serve/struct.Serve.html#method.with_graceful_shutdown).
 pub async fn trap_signals() {
@@ -36,3 +43,5 @@ pub async fn trap_signals() {
         },
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,88 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use graceful_shutdown::ShutdownCoordinator;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_phases_run_in_order() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let mut coordinator = ShutdownCoordinator::new();
+    let stop_accepting = coordinator.add_phase("stop-accepting", Duration::ZERO);
+    let drain = coordinator.add_phase("drain", Duration::from_secs(5));
+    let flush = coordinator.add_phase("flush", Duration::from_secs(5));
+
+    let server = drain.spawn({
+        let events = events.clone();
+        let stop_accepting = stop_accepting.cancelled();
+        async move {
+            stop_accepting.await;
+            // Draining takes a while, the next phase has to wait for it
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            events.lock().unwrap().push("drained");
+        }
+    });
+
+    flush.spawn({
+        let events = events.clone();
+        let started = flush.cancelled();
+        async move {
+            started.await;
+            events.lock().unwrap().push("flushed");
+        }
+    });
+
+    // Tokens are not cancelled before the shutdown is requested
+    assert!(!stop_accepting.token().is_cancelled());
+    assert!(!drain.token().is_cancelled());
+
+    coordinator.shutdown().await.unwrap();
+
+    assert!(server.await.is_ok());
+    assert_eq!(*events.lock().unwrap(), ["drained", "flushed"]);
+    assert!(flush.token().is_cancelled());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_timed_out_tasks_are_aborted_and_reported() {
+    let mut coordinator = ShutdownCoordinator::new();
+    let stuck = coordinator.add_phase("stuck", Duration::from_millis(100));
+    let next = coordinator.add_phase("next", Duration::from_secs(5));
+
+    // Ignores the cancellation
+    let stuck_task = stuck.spawn(std::future::pending::<()>());
+    let completed_task = stuck.spawn(async {});
+
+    let next_task = next.spawn({
+        let started = next.cancelled();
+        async move { started.await }
+    });
+
+    let err = coordinator.shutdown().await.unwrap_err();
+    assert_eq!(err.phases, ["stuck"]);
+    assert_eq!(
+        err.to_string(),
+        "Shutdown phases did not complete in time: stuck"
+    );
+
+    assert!(stuck_task.await.unwrap_err().is_cancelled());
+    assert!(completed_task.await.is_ok());
+
+    // Subsequent phases still run
+    assert!(next_task.await.is_ok());
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////